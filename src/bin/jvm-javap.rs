@@ -0,0 +1,42 @@
+use std::env;
+use std::fs;
+use std::process;
+
+use jvm::classfile::class_file_stream::ClassFileStream;
+use jvm::classfile::parsed_class::ParsedClass;
+use jvm::javap::disassemble;
+
+fn main() {
+    let paths: Vec<String> = env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("Usage: jvm-javap <class file>...");
+        process::exit(2);
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) => {
+                eprintln!("Error: cannot read {}: {}", path, err);
+                failed = true;
+                continue;
+            }
+        };
+        let text =
+            ParsedClass::new(ClassFileStream::new(bytes)).and_then(|class| disassemble(&class));
+        match text {
+            Ok(text) => {
+                println!("Classfile {}", path);
+                print!("{}", text);
+            }
+            Err(err) => {
+                eprintln!("Error: {}: {}", path, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
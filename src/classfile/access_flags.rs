@@ -1 +1,179 @@
+use std::fmt;
+use std::ops::BitOr;
 
+/// `access_flags` of a class, field, method or inner class (JVMS 4.1, 4.5, 4.6).
+/// Several bits are shared between the tables and mean different things,
+/// e.g. 0x0020 is `ACC_SUPER` on a class and `ACC_SYNCHRONIZED` on a method.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct AccessFlags(u16);
+
+pub const ACC_PUBLIC: u16 = 0x0001;
+pub const ACC_PRIVATE: u16 = 0x0002;
+pub const ACC_PROTECTED: u16 = 0x0004;
+pub const ACC_STATIC: u16 = 0x0008;
+pub const ACC_FINAL: u16 = 0x0010;
+pub const ACC_SUPER: u16 = 0x0020;
+pub const ACC_SYNCHRONIZED: u16 = 0x0020;
+pub const ACC_VOLATILE: u16 = 0x0040;
+pub const ACC_BRIDGE: u16 = 0x0040;
+pub const ACC_TRANSIENT: u16 = 0x0080;
+pub const ACC_VARARGS: u16 = 0x0080;
+pub const ACC_NATIVE: u16 = 0x0100;
+pub const ACC_INTERFACE: u16 = 0x0200;
+pub const ACC_ABSTRACT: u16 = 0x0400;
+pub const ACC_STRICT: u16 = 0x0800;
+pub const ACC_SYNTHETIC: u16 = 0x1000;
+pub const ACC_ANNOTATION: u16 = 0x2000;
+pub const ACC_ENUM: u16 = 0x4000;
+pub const ACC_MODULE: u16 = 0x8000;
+
+impl AccessFlags {
+    pub fn new(bits: u16) -> AccessFlags {
+        AccessFlags(bits)
+    }
+
+    pub fn bits(&self) -> u16 {
+        self.0
+    }
+
+    pub fn contains(&self, flags: u16) -> bool {
+        self.0 & flags == flags
+    }
+
+    pub fn is_public(&self) -> bool {
+        self.contains(ACC_PUBLIC)
+    }
+
+    pub fn is_private(&self) -> bool {
+        self.contains(ACC_PRIVATE)
+    }
+
+    pub fn is_protected(&self) -> bool {
+        self.contains(ACC_PROTECTED)
+    }
+
+    /// neither public, private nor protected
+    pub fn is_package_private(&self) -> bool {
+        self.0 & (ACC_PUBLIC | ACC_PRIVATE | ACC_PROTECTED) == 0
+    }
+
+    pub fn is_static(&self) -> bool {
+        self.contains(ACC_STATIC)
+    }
+
+    pub fn is_final(&self) -> bool {
+        self.contains(ACC_FINAL)
+    }
+
+    pub fn is_super(&self) -> bool {
+        self.contains(ACC_SUPER)
+    }
+
+    pub fn is_synchronized(&self) -> bool {
+        self.contains(ACC_SYNCHRONIZED)
+    }
+
+    pub fn is_volatile(&self) -> bool {
+        self.contains(ACC_VOLATILE)
+    }
+
+    pub fn is_bridge(&self) -> bool {
+        self.contains(ACC_BRIDGE)
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.contains(ACC_TRANSIENT)
+    }
+
+    pub fn is_varargs(&self) -> bool {
+        self.contains(ACC_VARARGS)
+    }
+
+    pub fn is_native(&self) -> bool {
+        self.contains(ACC_NATIVE)
+    }
+
+    pub fn is_interface(&self) -> bool {
+        self.contains(ACC_INTERFACE)
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.contains(ACC_ABSTRACT)
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.contains(ACC_STRICT)
+    }
+
+    pub fn is_synthetic(&self) -> bool {
+        self.contains(ACC_SYNTHETIC)
+    }
+
+    pub fn is_annotation(&self) -> bool {
+        self.contains(ACC_ANNOTATION)
+    }
+
+    pub fn is_enum(&self) -> bool {
+        self.contains(ACC_ENUM)
+    }
+
+    pub fn is_module(&self) -> bool {
+        self.contains(ACC_MODULE)
+    }
+}
+
+impl From<u16> for AccessFlags {
+    fn from(bits: u16) -> Self {
+        AccessFlags(bits)
+    }
+}
+
+impl BitOr<u16> for AccessFlags {
+    type Output = AccessFlags;
+
+    fn bitor(self, rhs: u16) -> AccessFlags {
+        AccessFlags(self.0 | rhs)
+    }
+}
+
+impl fmt::Debug for AccessFlags {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "AccessFlags({:#06x})", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::access_flags::{
+        AccessFlags, ACC_ABSTRACT, ACC_INTERFACE, ACC_NATIVE, ACC_PUBLIC, ACC_STATIC,
+    };
+
+    #[test]
+    fn should_test_single_flags() {
+        let flags = AccessFlags::new(ACC_PUBLIC | ACC_STATIC | ACC_NATIVE);
+        assert!(flags.is_public());
+        assert!(flags.is_static());
+        assert!(flags.is_native());
+        assert!(!flags.is_private());
+        assert!(!flags.is_abstract());
+        assert!(!flags.is_package_private());
+    }
+
+    #[test]
+    fn should_combine_flags() {
+        let flags = AccessFlags::default() | ACC_INTERFACE | ACC_ABSTRACT;
+        assert_eq!(0x0600, flags.bits());
+        assert!(flags.contains(ACC_INTERFACE | ACC_ABSTRACT));
+        assert!(!flags.contains(ACC_INTERFACE | ACC_PUBLIC));
+        assert!(flags.is_package_private());
+    }
+
+    #[test]
+    fn should_share_bits_between_tables() {
+        // ACC_SUPER on a class, ACC_SYNCHRONIZED on a method
+        let flags = AccessFlags::new(0x0020);
+        assert!(flags.is_super());
+        assert!(flags.is_synchronized());
+        assert_eq!("AccessFlags(0x0020)", format!("{:?}", flags));
+    }
+}
@@ -0,0 +1,344 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_file_writer::ClassFileWriter;
+use crate::classfile::class_format_error::ClassFormatError;
+
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    pub type_index: u16,
+    pub element_value_pairs: Vec<ElementValuePair>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ElementValuePair {
+    pub element_name_index: u16,
+    pub value: ElementValue,
+}
+
+#[derive(Clone, Debug)]
+pub enum ElementValue {
+    // tag is one of B C D F I J S Z s
+    Const {
+        tag: u8,
+        const_value_index: u16,
+    },
+    Enum {
+        type_name_index: u16,
+        const_name_index: u16,
+    },
+    Class {
+        class_info_index: u16,
+    },
+    Annotation(Annotation),
+    Array(Vec<ElementValue>),
+}
+
+#[derive(Clone, Debug)]
+pub struct TypeAnnotation {
+    pub target_type: u8,
+    pub target_info: TargetInfo,
+    // (type_path_kind, type_argument_index)
+    pub type_path: Vec<(u8, u8)>,
+    pub annotation: Annotation,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalVarTargetEntry {
+    pub start_pc: u16,
+    pub length: u16,
+    pub index: u16,
+}
+
+#[derive(Clone, Debug)]
+pub enum TargetInfo {
+    TypeParameter {
+        type_parameter_index: u8,
+    },
+    SuperType {
+        supertype_index: u16,
+    },
+    TypeParameterBound {
+        type_parameter_index: u8,
+        bound_index: u8,
+    },
+    Empty,
+    FormalParameter {
+        formal_parameter_index: u8,
+    },
+    Throws {
+        throws_type_index: u16,
+    },
+    LocalVar {
+        table: Vec<LocalVarTargetEntry>,
+    },
+    Catch {
+        exception_table_index: u16,
+    },
+    Offset {
+        offset: u16,
+    },
+    TypeArgument {
+        offset: u16,
+        type_argument_index: u8,
+    },
+}
+
+pub fn read_annotations(stream: &mut ClassFileStream) -> Result<Vec<Annotation>, ClassFormatError> {
+    let count = stream.read_u16()?;
+    let mut annotations = Vec::with_capacity(count as usize);
+    for _i in 0..count {
+        annotations.push(read_annotation(stream)?);
+    }
+    Ok(annotations)
+}
+
+pub fn read_parameter_annotations(
+    stream: &mut ClassFileStream,
+) -> Result<Vec<Vec<Annotation>>, ClassFormatError> {
+    let num_parameters = stream.read_u8()?;
+    let mut parameters = Vec::with_capacity(num_parameters as usize);
+    for _i in 0..num_parameters {
+        parameters.push(read_annotations(stream)?);
+    }
+    Ok(parameters)
+}
+
+pub fn read_annotation(stream: &mut ClassFileStream) -> Result<Annotation, ClassFormatError> {
+    let type_index = stream.read_u16()?;
+    let num_pairs = stream.read_u16()?;
+    let mut element_value_pairs = Vec::with_capacity(num_pairs as usize);
+    for _i in 0..num_pairs {
+        element_value_pairs.push(ElementValuePair {
+            element_name_index: stream.read_u16()?,
+            value: read_element_value(stream)?,
+        });
+    }
+    Ok(Annotation {
+        type_index,
+        element_value_pairs,
+    })
+}
+
+pub fn read_element_value(stream: &mut ClassFileStream) -> Result<ElementValue, ClassFormatError> {
+    let offset = stream.current;
+    let tag = stream.read_u8()?;
+    let value = match tag {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' | b's' => ElementValue::Const {
+            tag,
+            const_value_index: stream.read_u16()?,
+        },
+        b'e' => ElementValue::Enum {
+            type_name_index: stream.read_u16()?,
+            const_name_index: stream.read_u16()?,
+        },
+        b'c' => ElementValue::Class {
+            class_info_index: stream.read_u16()?,
+        },
+        b'@' => ElementValue::Annotation(read_annotation(stream)?),
+        b'[' => {
+            let num_values = stream.read_u16()?;
+            let mut values = Vec::with_capacity(num_values as usize);
+            for _i in 0..num_values {
+                values.push(read_element_value(stream)?);
+            }
+            ElementValue::Array(values)
+        }
+        _ => {
+            return Err(ClassFormatError::UnknownTag {
+                offset,
+                tag,
+                kind: "element value",
+            })
+        }
+    };
+    Ok(value)
+}
+
+pub fn read_type_annotations(
+    stream: &mut ClassFileStream,
+) -> Result<Vec<TypeAnnotation>, ClassFormatError> {
+    let count = stream.read_u16()?;
+    let mut annotations = Vec::with_capacity(count as usize);
+    for _i in 0..count {
+        annotations.push(read_type_annotation(stream)?);
+    }
+    Ok(annotations)
+}
+
+fn read_type_annotation(stream: &mut ClassFileStream) -> Result<TypeAnnotation, ClassFormatError> {
+    let offset = stream.current;
+    let target_type = stream.read_u8()?;
+    let target_info = match target_type {
+        0x00 | 0x01 => TargetInfo::TypeParameter {
+            type_parameter_index: stream.read_u8()?,
+        },
+        0x10 => TargetInfo::SuperType {
+            supertype_index: stream.read_u16()?,
+        },
+        0x11 | 0x12 => TargetInfo::TypeParameterBound {
+            type_parameter_index: stream.read_u8()?,
+            bound_index: stream.read_u8()?,
+        },
+        0x13..=0x15 => TargetInfo::Empty,
+        0x16 => TargetInfo::FormalParameter {
+            formal_parameter_index: stream.read_u8()?,
+        },
+        0x17 => TargetInfo::Throws {
+            throws_type_index: stream.read_u16()?,
+        },
+        0x40 | 0x41 => {
+            let table_length = stream.read_u16()?;
+            let mut table = Vec::with_capacity(table_length as usize);
+            for _i in 0..table_length {
+                table.push(LocalVarTargetEntry {
+                    start_pc: stream.read_u16()?,
+                    length: stream.read_u16()?,
+                    index: stream.read_u16()?,
+                });
+            }
+            TargetInfo::LocalVar { table }
+        }
+        0x42 => TargetInfo::Catch {
+            exception_table_index: stream.read_u16()?,
+        },
+        0x43..=0x46 => TargetInfo::Offset {
+            offset: stream.read_u16()?,
+        },
+        0x47..=0x4b => TargetInfo::TypeArgument {
+            offset: stream.read_u16()?,
+            type_argument_index: stream.read_u8()?,
+        },
+        _ => {
+            return Err(ClassFormatError::UnknownTag {
+                offset,
+                tag: target_type,
+                kind: "type annotation target",
+            })
+        }
+    };
+
+    let path_length = stream.read_u8()?;
+    let mut type_path = Vec::with_capacity(path_length as usize);
+    for _i in 0..path_length {
+        type_path.push((stream.read_u8()?, stream.read_u8()?));
+    }
+
+    Ok(TypeAnnotation {
+        target_type,
+        target_info,
+        type_path,
+        annotation: read_annotation(stream)?,
+    })
+}
+
+pub fn write_annotations(annotations: &[Annotation], writer: &mut ClassFileWriter) {
+    writer.write_u16(annotations.len() as u16);
+    for annotation in annotations {
+        write_annotation(annotation, writer);
+    }
+}
+
+pub fn write_parameter_annotations(parameters: &[Vec<Annotation>], writer: &mut ClassFileWriter) {
+    writer.write_u8(parameters.len() as u8);
+    for annotations in parameters {
+        write_annotations(annotations, writer);
+    }
+}
+
+pub fn write_annotation(annotation: &Annotation, writer: &mut ClassFileWriter) {
+    writer.write_u16(annotation.type_index);
+    writer.write_u16(annotation.element_value_pairs.len() as u16);
+    for pair in &annotation.element_value_pairs {
+        writer.write_u16(pair.element_name_index);
+        write_element_value(&pair.value, writer);
+    }
+}
+
+pub fn write_element_value(value: &ElementValue, writer: &mut ClassFileWriter) {
+    match value {
+        ElementValue::Const {
+            tag,
+            const_value_index,
+        } => {
+            writer.write_u8(*tag);
+            writer.write_u16(*const_value_index);
+        }
+        ElementValue::Enum {
+            type_name_index,
+            const_name_index,
+        } => {
+            writer.write_u8(b'e');
+            writer.write_u16(*type_name_index);
+            writer.write_u16(*const_name_index);
+        }
+        ElementValue::Class { class_info_index } => {
+            writer.write_u8(b'c');
+            writer.write_u16(*class_info_index);
+        }
+        ElementValue::Annotation(annotation) => {
+            writer.write_u8(b'@');
+            write_annotation(annotation, writer);
+        }
+        ElementValue::Array(values) => {
+            writer.write_u8(b'[');
+            writer.write_u16(values.len() as u16);
+            for value in values {
+                write_element_value(value, writer);
+            }
+        }
+    }
+}
+
+pub fn write_type_annotations(annotations: &[TypeAnnotation], writer: &mut ClassFileWriter) {
+    writer.write_u16(annotations.len() as u16);
+    for annotation in annotations {
+        write_type_annotation(annotation, writer);
+    }
+}
+
+fn write_type_annotation(annotation: &TypeAnnotation, writer: &mut ClassFileWriter) {
+    writer.write_u8(annotation.target_type);
+    match &annotation.target_info {
+        TargetInfo::TypeParameter {
+            type_parameter_index,
+        } => writer.write_u8(*type_parameter_index),
+        TargetInfo::SuperType { supertype_index } => writer.write_u16(*supertype_index),
+        TargetInfo::TypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        } => {
+            writer.write_u8(*type_parameter_index);
+            writer.write_u8(*bound_index);
+        }
+        TargetInfo::Empty => {}
+        TargetInfo::FormalParameter {
+            formal_parameter_index,
+        } => writer.write_u8(*formal_parameter_index),
+        TargetInfo::Throws { throws_type_index } => writer.write_u16(*throws_type_index),
+        TargetInfo::LocalVar { table } => {
+            writer.write_u16(table.len() as u16);
+            for entry in table {
+                writer.write_u16(entry.start_pc);
+                writer.write_u16(entry.length);
+                writer.write_u16(entry.index);
+            }
+        }
+        TargetInfo::Catch {
+            exception_table_index,
+        } => writer.write_u16(*exception_table_index),
+        TargetInfo::Offset { offset } => writer.write_u16(*offset),
+        TargetInfo::TypeArgument {
+            offset,
+            type_argument_index,
+        } => {
+            writer.write_u16(*offset);
+            writer.write_u8(*type_argument_index);
+        }
+    }
+
+    writer.write_u8(annotation.type_path.len() as u8);
+    for (kind, index) in &annotation.type_path {
+        writer.write_u8(*kind);
+        writer.write_u8(*index);
+    }
+    write_annotation(&annotation.annotation, writer);
+}
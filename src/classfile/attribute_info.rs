@@ -1,74 +1,132 @@
+use crate::classfile::annotations::{
+    read_annotations, read_element_value, read_parameter_annotations, read_type_annotations,
+    write_annotations, write_element_value, write_parameter_annotations, write_type_annotations,
+    Annotation, ElementValue, TypeAnnotation,
+};
 use crate::classfile::class_file_stream::ClassFileStream;
-use crate::classfile::constant_pool::CpEntry;
-
-const ConstantValue: &str = "ConstantValue";
-const Code: &str = "Code";
-const Exceptions: &str = "Exceptions";
-const SourceFile: &str = "SourceFile";
-const LineNumberTable: &str = "LineNumberTable";
-const LocalVariableTable: &str = "local_variable_table";
-const InnerClasses: &str = "InnerClasses";
-const Synthetic: &str = "Synthetic";
-const Deprecated: &str = "Deprecated";
-const EnclosingMethod: &str = "EnclosingMethod";
-const Signature: &str = "Signature";
-const SourceDebugExtension: &str = "SourceDebugExtension";
-const LocalVariableTypeTable: &str = "LocalVariableTypeTable";
-const RuntimeVisibleAnnotations: &str = "RuntimeVisibleAnnotations";
-const RuntimeInvisibleAnnotations: &str = "RuntimeInvisibleAnnotations";
-const RuntimeVisibleParameterAnnotations: &str = "RuntimeVisibleParameterAnnotations";
-const RuntimeInvisibleParameterAnnotation: &str = "RuntimeInvisibleParameterAnnotations";
-const AnnotationDefault: &str = "AnnotationDefault";
-const StackMapTable: &str = "StackMapTable";
-const BootstrapMethods: &str = "BootstrapMethods";
-const RuntimeVisibleTypeAnnotations: &str = "RuntimeVisibleTypeAnnotations";
-const RuntimeInvisibleTypeAnnotations: &str = "RuntimeInvisibleTypeAnnotations";
-const MethodParameters: &str = "MethodParameters";
-const Module: &str = "Module";
-const ModulePackages: &str = "ModulePackages";
-const ModuleMainClass: &str = "ModuleMainClass";
-const NestHost: &str = "NestHost";
-const NestMembers: &str = "NestMembers";
+use crate::classfile::class_file_writer::ClassFileWriter;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::classfile::constant_pool::ConstantPool;
+use crate::classfile::stack_map_table::{
+    read_stack_map_frames, write_stack_map_frames, StackMapFrame,
+};
+
+pub const CONSTANT_VALUE: &str = "ConstantValue";
+pub const CODE: &str = "Code";
+pub const EXCEPTIONS: &str = "Exceptions";
+pub const SOURCE_FILE: &str = "SourceFile";
+pub const LINE_NUMBER_TABLE: &str = "LineNumberTable";
+pub const LOCAL_VARIABLE_TABLE: &str = "LocalVariableTable";
+pub const INNER_CLASSES: &str = "InnerClasses";
+pub const SYNTHETIC: &str = "Synthetic";
+pub const DEPRECATED: &str = "Deprecated";
+pub const ENCLOSING_METHOD: &str = "EnclosingMethod";
+pub const SIGNATURE: &str = "Signature";
+pub const SOURCE_DEBUG_EXTENSION: &str = "SourceDebugExtension";
+pub const LOCAL_VARIABLE_TYPE_TABLE: &str = "LocalVariableTypeTable";
+pub const RUNTIME_VISIBLE_ANNOTATIONS: &str = "RuntimeVisibleAnnotations";
+pub const RUNTIME_INVISIBLE_ANNOTATIONS: &str = "RuntimeInvisibleAnnotations";
+pub const RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS: &str = "RuntimeVisibleParameterAnnotations";
+pub const RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS: &str = "RuntimeInvisibleParameterAnnotations";
+pub const ANNOTATION_DEFAULT: &str = "AnnotationDefault";
+pub const STACK_MAP_TABLE: &str = "StackMapTable";
+pub const BOOTSTRAP_METHODS: &str = "BootstrapMethods";
+pub const RUNTIME_VISIBLE_TYPE_ANNOTATIONS: &str = "RuntimeVisibleTypeAnnotations";
+pub const RUNTIME_INVISIBLE_TYPE_ANNOTATIONS: &str = "RuntimeInvisibleTypeAnnotations";
+pub const METHOD_PARAMETERS: &str = "MethodParameters";
+pub const MODULE: &str = "Module";
+pub const MODULE_PACKAGES: &str = "ModulePackages";
+pub const MODULE_MAIN_CLASS: &str = "ModuleMainClass";
+pub const NEST_HOST: &str = "NestHost";
+pub const NEST_MEMBERS: &str = "NestMembers";
+pub const RECORD: &str = "Record";
+pub const PERMITTED_SUBCLASSES: &str = "PermittedSubclasses";
 
 #[derive(Clone, Debug)]
 pub enum AttributeInfo {
-    None(),
-    ConstantValue(),
+    ConstantValue(ConstantValueAttribute),
     Code(CodeAttribute),
-    Exceptions(),
+    Exceptions(ExceptionsAttribute),
     SourceFile(SourceFile),
     LineNumberTable(LineNumberTableAttribute),
     LocalVariableTable(LocalVariableTable),
-    InnerClasses(),
+    InnerClasses(InnerClassesAttribute),
     Synthetic(),
     Deprecated(),
-    EnclosingMethod(),
-    Signature(),
-    SourceDebugExtension(),
-    LocalVariableTypeTable(),
-    RuntimeVisibleAnnotations(),
-    RuntimeInvisibleAnnotations(),
-    RuntimeVisibleParameterAnnotations(),
-    RuntimeInvisibleParameterAnnotation(),
-    AnnotationDefault(),
-    StackMapTable(),
-    BootstrapMethods(),
-    RuntimeVisibleTypeAnnotations(),
-    RuntimeInvisibleTypeAnnotations(),
-    MethodParameters(),
-    Module(),
-    ModulePackages(),
-    ModuleMainClass(),
-    NestHost(),
-    NestMembers(),
+    EnclosingMethod(EnclosingMethodAttribute),
+    Signature(SignatureAttribute),
+    SourceDebugExtension(SourceDebugExtensionAttribute),
+    LocalVariableTypeTable(LocalVariableTable),
+    RuntimeVisibleAnnotations(Vec<Annotation>),
+    RuntimeInvisibleAnnotations(Vec<Annotation>),
+    RuntimeVisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    RuntimeInvisibleParameterAnnotations(Vec<Vec<Annotation>>),
+    AnnotationDefault(ElementValue),
+    StackMapTable(Vec<StackMapFrame>),
+    BootstrapMethods(BootstrapMethodsAttribute),
+    RuntimeVisibleTypeAnnotations(Vec<TypeAnnotation>),
+    RuntimeInvisibleTypeAnnotations(Vec<TypeAnnotation>),
+    MethodParameters(MethodParametersAttribute),
+    Module(ModuleAttribute),
+    ModulePackages(ModulePackagesAttribute),
+    ModuleMainClass(ModuleMainClassAttribute),
+    NestHost(NestHostAttribute),
+    NestMembers(NestMembersAttribute),
+    Record(RecordAttribute),
+    PermittedSubclasses(PermittedSubclassesAttribute),
+    // attributes this parser does not know, kept as they were read
+    Unknown(UnknownAttribute),
+}
+
+impl AttributeInfo {
+    /// The name the attribute is stored under in the class file.
+    pub fn name(&self) -> &str {
+        match self {
+            AttributeInfo::ConstantValue(_) => CONSTANT_VALUE,
+            AttributeInfo::Code(_) => CODE,
+            AttributeInfo::Exceptions(_) => EXCEPTIONS,
+            AttributeInfo::SourceFile(_) => SOURCE_FILE,
+            AttributeInfo::LineNumberTable(_) => LINE_NUMBER_TABLE,
+            AttributeInfo::LocalVariableTable(_) => LOCAL_VARIABLE_TABLE,
+            AttributeInfo::InnerClasses(_) => INNER_CLASSES,
+            AttributeInfo::Synthetic() => SYNTHETIC,
+            AttributeInfo::Deprecated() => DEPRECATED,
+            AttributeInfo::EnclosingMethod(_) => ENCLOSING_METHOD,
+            AttributeInfo::Signature(_) => SIGNATURE,
+            AttributeInfo::SourceDebugExtension(_) => SOURCE_DEBUG_EXTENSION,
+            AttributeInfo::LocalVariableTypeTable(_) => LOCAL_VARIABLE_TYPE_TABLE,
+            AttributeInfo::RuntimeVisibleAnnotations(_) => RUNTIME_VISIBLE_ANNOTATIONS,
+            AttributeInfo::RuntimeInvisibleAnnotations(_) => RUNTIME_INVISIBLE_ANNOTATIONS,
+            AttributeInfo::RuntimeVisibleParameterAnnotations(_) => {
+                RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS
+            }
+            AttributeInfo::RuntimeInvisibleParameterAnnotations(_) => {
+                RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS
+            }
+            AttributeInfo::AnnotationDefault(_) => ANNOTATION_DEFAULT,
+            AttributeInfo::StackMapTable(_) => STACK_MAP_TABLE,
+            AttributeInfo::BootstrapMethods(_) => BOOTSTRAP_METHODS,
+            AttributeInfo::RuntimeVisibleTypeAnnotations(_) => RUNTIME_VISIBLE_TYPE_ANNOTATIONS,
+            AttributeInfo::RuntimeInvisibleTypeAnnotations(_) => RUNTIME_INVISIBLE_TYPE_ANNOTATIONS,
+            AttributeInfo::MethodParameters(_) => METHOD_PARAMETERS,
+            AttributeInfo::Module(_) => MODULE,
+            AttributeInfo::ModulePackages(_) => MODULE_PACKAGES,
+            AttributeInfo::ModuleMainClass(_) => MODULE_MAIN_CLASS,
+            AttributeInfo::NestHost(_) => NEST_HOST,
+            AttributeInfo::NestMembers(_) => NEST_MEMBERS,
+            AttributeInfo::Record(_) => RECORD,
+            AttributeInfo::PermittedSubclasses(_) => PERMITTED_SUBCLASSES,
+            AttributeInfo::Unknown(unknown) => &unknown.name,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ExceptionTableEntry {
-    start_pc: u16,
-    end_pc: u16,
-    handler_pc: u16,
-    catch_type: u16,
+    pub start_pc: u16,
+    pub end_pc: u16,
+    pub handler_pc: u16,
+    pub catch_type: u16,
 }
 
 #[derive(Clone, Debug)]
@@ -78,11 +136,12 @@ pub struct CodeAttribute {
     pub code: Vec<u8>,
     pub exception_table: Vec<ExceptionTableEntry>,
     pub attribute_table: Vec<AttributeInfo>,
+    pub attribute_name_indices: Vec<u16>,
 }
 
 #[derive(Clone, Debug)]
 pub struct LineNumberTableAttribute {
-    line_number_table: Vec<LineNumberTableEntry>,
+    pub line_number_table: Vec<LineNumberTableEntry>,
 }
 
 impl LineNumberTableAttribute {
@@ -94,14 +153,15 @@ impl LineNumberTableAttribute {
 }
 
 #[derive(Clone, Debug)]
-struct LineNumberTableEntry {
-    start_pc: u16,
-    line_number: u16,
+pub struct LineNumberTableEntry {
+    pub start_pc: u16,
+    pub line_number: u16,
 }
 
+/// Also used for LocalVariableTypeTable, where `descriptor_index` points at a signature.
 #[derive(Clone, Debug)]
 pub struct LocalVariableTable {
-    local_variable_table: Vec<LocalVariableTableEntry>,
+    pub local_variable_table: Vec<LocalVariableTableEntry>,
 }
 
 #[derive(Clone, Debug)]
@@ -118,6 +178,144 @@ pub struct SourceFile {
     pub source_file_index: u16,
 }
 
+#[derive(Clone, Debug)]
+pub struct ConstantValueAttribute {
+    pub constant_value_index: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct ExceptionsAttribute {
+    pub exception_index_table: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InnerClassesAttribute {
+    pub classes: Vec<InnerClassEntry>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InnerClassEntry {
+    pub inner_class_info_index: u16,
+    pub outer_class_info_index: u16,
+    pub inner_name_index: u16,
+    pub inner_class_access_flags: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct EnclosingMethodAttribute {
+    pub class_index: u16,
+    pub method_index: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct SignatureAttribute {
+    pub signature_index: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct SourceDebugExtensionAttribute {
+    pub debug_extension: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BootstrapMethodsAttribute {
+    pub bootstrap_methods: Vec<BootstrapMethod>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BootstrapMethod {
+    pub bootstrap_method_ref: u16,
+    pub bootstrap_arguments: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MethodParametersAttribute {
+    pub parameters: Vec<MethodParameter>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MethodParameter {
+    pub name_index: u16,
+    pub access_flags: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModuleAttribute {
+    pub module_name_index: u16,
+    pub module_flags: u16,
+    pub module_version_index: u16,
+    pub requires: Vec<ModuleRequires>,
+    pub exports: Vec<ModuleExports>,
+    pub opens: Vec<ModuleExports>,
+    pub uses_index: Vec<u16>,
+    pub provides: Vec<ModuleProvides>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModuleRequires {
+    pub requires_index: u16,
+    pub requires_flags: u16,
+    pub requires_version_index: u16,
+}
+
+/// An `exports` or `opens` entry, both share the same layout.
+#[derive(Clone, Debug)]
+pub struct ModuleExports {
+    pub package_index: u16,
+    pub flags: u16,
+    pub to_index: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModuleProvides {
+    pub provides_index: u16,
+    pub provides_with_index: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModulePackagesAttribute {
+    pub package_index: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ModuleMainClassAttribute {
+    pub main_class_index: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct NestHostAttribute {
+    pub host_class_index: u16,
+}
+
+#[derive(Clone, Debug)]
+pub struct NestMembersAttribute {
+    pub classes: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordAttribute {
+    pub components: Vec<RecordComponent>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordComponent {
+    pub name_index: u16,
+    pub descriptor_index: u16,
+    pub attributes: Vec<AttributeInfo>,
+    pub attribute_name_indices: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct PermittedSubclassesAttribute {
+    pub classes: Vec<u16>,
+}
+
+#[derive(Clone, Debug)]
+pub struct UnknownAttribute {
+    pub name: String,
+    pub info: Vec<u8>,
+}
+
 impl CodeAttribute {
     pub fn new() -> CodeAttribute {
         CodeAttribute {
@@ -126,111 +324,681 @@ impl CodeAttribute {
             code: vec![],
             exception_table: vec![],
             attribute_table: vec![],
+            attribute_name_indices: vec![],
         }
     }
 }
 
-pub fn read_exception_table(stream: &mut ClassFileStream) -> Vec<ExceptionTableEntry> {
+pub fn read_exception_table(
+    stream: &mut ClassFileStream,
+) -> Result<Vec<ExceptionTableEntry>, ClassFormatError> {
     let mut exceptions: Vec<ExceptionTableEntry> = vec![];
-    let length = stream.read_u16();
-    for _i in 1..length {
+    let length = stream.read_u16()?;
+    for _i in 0..length {
         let exception = ExceptionTableEntry {
-            start_pc: stream.read_u16(),
-            end_pc: stream.read_u16(),
-            handler_pc: stream.read_u16(),
-            catch_type: stream.read_u16(),
+            start_pc: stream.read_u16()?,
+            end_pc: stream.read_u16()?,
+            handler_pc: stream.read_u16()?,
+            catch_type: stream.read_u16()?,
         };
         exceptions.push(exception);
     }
-    exceptions
+    Ok(exceptions)
 }
 
-pub fn read_attributes(stream: &mut ClassFileStream, entries: Vec<CpEntry>) -> Vec<AttributeInfo> {
-    let att_count = stream.read_u16();
+/// Reads an attribute table, along with the constant pool index each name
+/// was read from.
+pub fn read_attributes(
+    stream: &mut ClassFileStream,
+    pool: &ConstantPool,
+) -> Result<(Vec<AttributeInfo>, Vec<u16>), ClassFormatError> {
+    let att_count = stream.read_u16()?;
     let mut attrs: Vec<AttributeInfo> = vec![];
+    let mut name_indices: Vec<u16> = vec![];
     for _j in 0..att_count as usize {
-        let attr = read_attribute_info(stream, entries.clone());
+        let (name_index, attr) = read_attribute_info(stream, pool)?;
+        name_indices.push(name_index);
         attrs.push(attr);
     }
-    attrs
+    Ok((attrs, name_indices))
 }
 
-pub fn read_attribute_info(stream: &mut ClassFileStream, entries: Vec<CpEntry>) -> AttributeInfo {
-    let attr_name_index = stream.read_u16();
-    let _attr_len = stream.read_u32();
-    let mut attr_name: String = String::from("");
-    let entry = entries[attr_name_index as usize].clone();
-    if let CpEntry::Utf8 { val } = entry {
-        attr_name = val;
+pub fn read_attribute_info(
+    stream: &mut ClassFileStream,
+    pool: &ConstantPool,
+) -> Result<(u16, AttributeInfo), ClassFormatError> {
+    let offset = stream.current;
+    let attr_name_index = stream.read_u16()?;
+    let attr_len = stream.read_u32()?;
+    let attr_name = match pool.get_utf8(attr_name_index) {
+        Some(name) => String::from(name),
+        _ => {
+            return Err(ClassFormatError::BadConstantIndex {
+                offset,
+                index: attr_name_index,
+                expected: "Utf8",
+            })
+        }
+    };
+
+    let body_start = stream.current;
+    let attr = read_attribute_body(stream, &attr_name, attr_len, pool)
+        .map_err(|e| ClassFormatError::in_attribute(&attr_name, e))?;
+
+    let actual = stream.current - body_start;
+    if actual != attr_len as usize {
+        let err = ClassFormatError::AttributeLength {
+            offset,
+            declared: attr_len,
+            actual,
+        };
+        return Err(ClassFormatError::in_attribute(&attr_name, err));
     }
+    Ok((attr_name_index, attr))
+}
 
-    match &attr_name[..] {
-        "Code" => {
+fn read_attribute_body(
+    stream: &mut ClassFileStream,
+    attr_name: &str,
+    attr_len: u32,
+    pool: &ConstantPool,
+) -> Result<AttributeInfo, ClassFormatError> {
+    let attr = match attr_name {
+        CONSTANT_VALUE => AttributeInfo::ConstantValue(ConstantValueAttribute {
+            constant_value_index: stream.read_u16()?,
+        }),
+        CODE => {
             let mut attribute = CodeAttribute {
-                max_stack: stream.read_u16(),
-                max_locals: stream.read_u16(),
+                max_stack: stream.read_u16()?,
+                max_locals: stream.read_u16()?,
                 code: vec![],
                 exception_table: vec![],
                 attribute_table: vec![],
+                attribute_name_indices: vec![],
             };
-            let code_length = stream.read_u32();
-            attribute.code = stream.read_to_length(code_length as u16);
-            attribute.exception_table = read_exception_table(stream);
-            attribute.attribute_table = read_attributes(stream, entries);
+            let code_length = stream.read_u32()?;
+            attribute.code = stream.read_to_length(code_length as usize)?;
+            attribute.exception_table = read_exception_table(stream)?;
+            let (attributes, name_indices) = read_attributes(stream, pool)?;
+            attribute.attribute_table = attributes;
+            attribute.attribute_name_indices = name_indices;
             AttributeInfo::Code(attribute)
         }
-        "LineNumberTable" => {
-            let line_attribute = build_line_table(stream);
+        EXCEPTIONS => AttributeInfo::Exceptions(ExceptionsAttribute {
+            exception_index_table: read_u16_table(stream)?,
+        }),
+        SOURCE_FILE => {
+            let source_file = SourceFile {
+                source_file_index: stream.read_u16()?,
+            };
+            AttributeInfo::SourceFile(source_file)
+        }
+        LINE_NUMBER_TABLE => {
+            let line_attribute = build_line_table(stream)?;
             AttributeInfo::LineNumberTable(line_attribute)
         }
-        "LocalVariableTable" => {
-            let local_vars_attr = build_local_vars_table(stream);
+        LOCAL_VARIABLE_TABLE => {
+            let local_vars_attr = build_local_vars_table(stream)?;
             AttributeInfo::LocalVariableTable(local_vars_attr)
         }
-        "SourceFile" => {
-            let source_file = SourceFile {
-                source_file_index: stream.read_u16(),
-            };
-            AttributeInfo::SourceFile(source_file)
+        LOCAL_VARIABLE_TYPE_TABLE => {
+            let local_types_attr = build_local_vars_table(stream)?;
+            AttributeInfo::LocalVariableTypeTable(local_types_attr)
         }
-        _ => {
-            println!("read_attribute_info -> {}", attr_name);
-            AttributeInfo::None()
+        INNER_CLASSES => {
+            let number_of_classes = stream.read_u16()?;
+            let mut classes = Vec::with_capacity(number_of_classes as usize);
+            for _i in 0..number_of_classes {
+                classes.push(InnerClassEntry {
+                    inner_class_info_index: stream.read_u16()?,
+                    outer_class_info_index: stream.read_u16()?,
+                    inner_name_index: stream.read_u16()?,
+                    inner_class_access_flags: stream.read_u16()?,
+                });
+            }
+            AttributeInfo::InnerClasses(InnerClassesAttribute { classes })
+        }
+        SYNTHETIC => AttributeInfo::Synthetic(),
+        DEPRECATED => AttributeInfo::Deprecated(),
+        ENCLOSING_METHOD => AttributeInfo::EnclosingMethod(EnclosingMethodAttribute {
+            class_index: stream.read_u16()?,
+            method_index: stream.read_u16()?,
+        }),
+        SIGNATURE => AttributeInfo::Signature(SignatureAttribute {
+            signature_index: stream.read_u16()?,
+        }),
+        SOURCE_DEBUG_EXTENSION => {
+            AttributeInfo::SourceDebugExtension(SourceDebugExtensionAttribute {
+                debug_extension: stream.read_to_length(attr_len as usize)?,
+            })
+        }
+        RUNTIME_VISIBLE_ANNOTATIONS => {
+            AttributeInfo::RuntimeVisibleAnnotations(read_annotations(stream)?)
+        }
+        RUNTIME_INVISIBLE_ANNOTATIONS => {
+            AttributeInfo::RuntimeInvisibleAnnotations(read_annotations(stream)?)
+        }
+        RUNTIME_VISIBLE_PARAMETER_ANNOTATIONS => {
+            AttributeInfo::RuntimeVisibleParameterAnnotations(read_parameter_annotations(stream)?)
+        }
+        RUNTIME_INVISIBLE_PARAMETER_ANNOTATIONS => {
+            AttributeInfo::RuntimeInvisibleParameterAnnotations(read_parameter_annotations(stream)?)
         }
+        RUNTIME_VISIBLE_TYPE_ANNOTATIONS => {
+            AttributeInfo::RuntimeVisibleTypeAnnotations(read_type_annotations(stream)?)
+        }
+        RUNTIME_INVISIBLE_TYPE_ANNOTATIONS => {
+            AttributeInfo::RuntimeInvisibleTypeAnnotations(read_type_annotations(stream)?)
+        }
+        ANNOTATION_DEFAULT => AttributeInfo::AnnotationDefault(read_element_value(stream)?),
+        STACK_MAP_TABLE => AttributeInfo::StackMapTable(read_stack_map_frames(stream)?),
+        BOOTSTRAP_METHODS => {
+            let num_bootstrap_methods = stream.read_u16()?;
+            let mut bootstrap_methods = Vec::with_capacity(num_bootstrap_methods as usize);
+            for _i in 0..num_bootstrap_methods {
+                bootstrap_methods.push(BootstrapMethod {
+                    bootstrap_method_ref: stream.read_u16()?,
+                    bootstrap_arguments: read_u16_table(stream)?,
+                });
+            }
+            AttributeInfo::BootstrapMethods(BootstrapMethodsAttribute { bootstrap_methods })
+        }
+        METHOD_PARAMETERS => {
+            let parameters_count = stream.read_u8()?;
+            let mut parameters = Vec::with_capacity(parameters_count as usize);
+            for _i in 0..parameters_count {
+                parameters.push(MethodParameter {
+                    name_index: stream.read_u16()?,
+                    access_flags: stream.read_u16()?,
+                });
+            }
+            AttributeInfo::MethodParameters(MethodParametersAttribute { parameters })
+        }
+        MODULE => AttributeInfo::Module(read_module(stream)?),
+        MODULE_PACKAGES => AttributeInfo::ModulePackages(ModulePackagesAttribute {
+            package_index: read_u16_table(stream)?,
+        }),
+        MODULE_MAIN_CLASS => AttributeInfo::ModuleMainClass(ModuleMainClassAttribute {
+            main_class_index: stream.read_u16()?,
+        }),
+        NEST_HOST => AttributeInfo::NestHost(NestHostAttribute {
+            host_class_index: stream.read_u16()?,
+        }),
+        NEST_MEMBERS => AttributeInfo::NestMembers(NestMembersAttribute {
+            classes: read_u16_table(stream)?,
+        }),
+        RECORD => {
+            let components_count = stream.read_u16()?;
+            let mut components = Vec::with_capacity(components_count as usize);
+            for _i in 0..components_count {
+                let name_index = stream.read_u16()?;
+                let descriptor_index = stream.read_u16()?;
+                let (attributes, attribute_name_indices) = read_attributes(stream, pool)?;
+                components.push(RecordComponent {
+                    name_index,
+                    descriptor_index,
+                    attributes,
+                    attribute_name_indices,
+                });
+            }
+            AttributeInfo::Record(RecordAttribute { components })
+        }
+        PERMITTED_SUBCLASSES => AttributeInfo::PermittedSubclasses(PermittedSubclassesAttribute {
+            classes: read_u16_table(stream)?,
+        }),
+        _ => AttributeInfo::Unknown(UnknownAttribute {
+            name: String::from(attr_name),
+            info: stream.read_to_length(attr_len as usize)?,
+        }),
+    };
+    Ok(attr)
+}
+
+/// Reads a u2 count followed by that many u2 values.
+fn read_u16_table(stream: &mut ClassFileStream) -> Result<Vec<u16>, ClassFormatError> {
+    let count = stream.read_u16()?;
+    let mut table = Vec::with_capacity(count as usize);
+    for _i in 0..count {
+        table.push(stream.read_u16()?);
+    }
+    Ok(table)
+}
+
+fn read_module(stream: &mut ClassFileStream) -> Result<ModuleAttribute, ClassFormatError> {
+    let module_name_index = stream.read_u16()?;
+    let module_flags = stream.read_u16()?;
+    let module_version_index = stream.read_u16()?;
+
+    let requires_count = stream.read_u16()?;
+    let mut requires = Vec::with_capacity(requires_count as usize);
+    for _i in 0..requires_count {
+        requires.push(ModuleRequires {
+            requires_index: stream.read_u16()?,
+            requires_flags: stream.read_u16()?,
+            requires_version_index: stream.read_u16()?,
+        });
+    }
+
+    let exports = read_module_exports(stream)?;
+    let opens = read_module_exports(stream)?;
+    let uses_index = read_u16_table(stream)?;
+
+    let provides_count = stream.read_u16()?;
+    let mut provides = Vec::with_capacity(provides_count as usize);
+    for _i in 0..provides_count {
+        provides.push(ModuleProvides {
+            provides_index: stream.read_u16()?,
+            provides_with_index: read_u16_table(stream)?,
+        });
+    }
+
+    Ok(ModuleAttribute {
+        module_name_index,
+        module_flags,
+        module_version_index,
+        requires,
+        exports,
+        opens,
+        uses_index,
+        provides,
+    })
+}
+
+fn read_module_exports(
+    stream: &mut ClassFileStream,
+) -> Result<Vec<ModuleExports>, ClassFormatError> {
+    let count = stream.read_u16()?;
+    let mut exports = Vec::with_capacity(count as usize);
+    for _i in 0..count {
+        exports.push(ModuleExports {
+            package_index: stream.read_u16()?,
+            flags: stream.read_u16()?,
+            to_index: read_u16_table(stream)?,
+        });
     }
+    Ok(exports)
 }
 
-pub fn build_local_vars_table(stream: &mut ClassFileStream) -> LocalVariableTable {
-    let table_length = stream.read_u16();
+pub fn build_local_vars_table(
+    stream: &mut ClassFileStream,
+) -> Result<LocalVariableTable, ClassFormatError> {
+    let table_length = stream.read_u16()?;
     let mut local_vars_table = LocalVariableTable {
         local_variable_table: vec![],
     };
 
     for _i in 0..table_length as usize {
         let entry = LocalVariableTableEntry {
-            start_pc: stream.read_u16(),
-            length: stream.read_u16(),
-            name_index: stream.read_u16(),
-            descriptor_index: stream.read_u16(),
-            index: stream.read_u16(),
+            start_pc: stream.read_u16()?,
+            length: stream.read_u16()?,
+            name_index: stream.read_u16()?,
+            descriptor_index: stream.read_u16()?,
+            index: stream.read_u16()?,
         };
         local_vars_table.local_variable_table.push(entry);
     }
-    local_vars_table
+    Ok(local_vars_table)
 }
 
-pub fn build_line_table(stream: &mut ClassFileStream) -> LineNumberTableAttribute {
-    let table_length = stream.read_u16();
+pub fn build_line_table(
+    stream: &mut ClassFileStream,
+) -> Result<LineNumberTableAttribute, ClassFormatError> {
+    let table_length = stream.read_u16()?;
     let mut line_attribute = LineNumberTableAttribute {
         line_number_table: vec![],
     };
 
     for _i in 0..table_length as usize {
         let entry = LineNumberTableEntry {
-            start_pc: stream.read_u16(),
-            line_number: stream.read_u16(),
+            start_pc: stream.read_u16()?,
+            line_number: stream.read_u16()?,
         };
         line_attribute.line_number_table.push(entry);
     }
-    line_attribute
+    Ok(line_attribute)
+}
+
+/// Writes an attribute table. Each attribute keeps the name index it was read
+/// with in `name_indices` while that entry still holds its name, so a pool
+/// with the same name twice comes out unchanged. Other names are looked up in
+/// `pool`, and appended to it when an attribute was added that the class did
+/// not have before.
+pub fn write_attributes(
+    attributes: &[AttributeInfo],
+    name_indices: &[u16],
+    pool: &mut ConstantPool,
+    writer: &mut ClassFileWriter,
+) -> Result<(), ClassFormatError> {
+    writer.write_u16(attributes.len() as u16);
+    for (i, attr) in attributes.iter().enumerate() {
+        let name_index = match name_indices.get(i) {
+            Some(&index) if pool.get_utf8(index) == Some(attr.name()) => index,
+            _ => pool.utf8_index(attr.name())?,
+        };
+        write_attribute_info(attr, name_index, pool, writer)?;
+    }
+    Ok(())
+}
+
+/// Writes one attribute under the Utf8 entry `name_index`.
+pub fn write_attribute_info(
+    attr: &AttributeInfo,
+    name_index: u16,
+    pool: &mut ConstantPool,
+    writer: &mut ClassFileWriter,
+) -> Result<(), ClassFormatError> {
+    let mut body = ClassFileWriter::new();
+    write_attribute_body(attr, pool, &mut body)?;
+
+    writer.write_u16(name_index);
+    writer.write_u32(body.len() as u32);
+    writer.write_bytes(&body.into_bytes());
+    Ok(())
+}
+
+fn write_attribute_body(
+    attr: &AttributeInfo,
+    pool: &mut ConstantPool,
+    writer: &mut ClassFileWriter,
+) -> Result<(), ClassFormatError> {
+    match attr {
+        AttributeInfo::ConstantValue(value) => writer.write_u16(value.constant_value_index),
+        AttributeInfo::Code(code) => {
+            writer.write_u16(code.max_stack);
+            writer.write_u16(code.max_locals);
+            writer.write_u32(code.code.len() as u32);
+            writer.write_bytes(&code.code);
+            writer.write_u16(code.exception_table.len() as u16);
+            for entry in &code.exception_table {
+                writer.write_u16(entry.start_pc);
+                writer.write_u16(entry.end_pc);
+                writer.write_u16(entry.handler_pc);
+                writer.write_u16(entry.catch_type);
+            }
+            write_attributes(
+                &code.attribute_table,
+                &code.attribute_name_indices,
+                pool,
+                writer,
+            )?;
+        }
+        AttributeInfo::Exceptions(exceptions) => {
+            writer.write_u16_table(&exceptions.exception_index_table)
+        }
+        AttributeInfo::SourceFile(source_file) => writer.write_u16(source_file.source_file_index),
+        AttributeInfo::LineNumberTable(table) => {
+            writer.write_u16(table.line_number_table.len() as u16);
+            for entry in &table.line_number_table {
+                writer.write_u16(entry.start_pc);
+                writer.write_u16(entry.line_number);
+            }
+        }
+        AttributeInfo::LocalVariableTable(table) | AttributeInfo::LocalVariableTypeTable(table) => {
+            writer.write_u16(table.local_variable_table.len() as u16);
+            for entry in &table.local_variable_table {
+                writer.write_u16(entry.start_pc);
+                writer.write_u16(entry.length);
+                writer.write_u16(entry.name_index);
+                writer.write_u16(entry.descriptor_index);
+                writer.write_u16(entry.index);
+            }
+        }
+        AttributeInfo::InnerClasses(inner_classes) => {
+            writer.write_u16(inner_classes.classes.len() as u16);
+            for entry in &inner_classes.classes {
+                writer.write_u16(entry.inner_class_info_index);
+                writer.write_u16(entry.outer_class_info_index);
+                writer.write_u16(entry.inner_name_index);
+                writer.write_u16(entry.inner_class_access_flags);
+            }
+        }
+        AttributeInfo::Synthetic() | AttributeInfo::Deprecated() => {}
+        AttributeInfo::EnclosingMethod(enclosing) => {
+            writer.write_u16(enclosing.class_index);
+            writer.write_u16(enclosing.method_index);
+        }
+        AttributeInfo::Signature(signature) => writer.write_u16(signature.signature_index),
+        AttributeInfo::SourceDebugExtension(extension) => {
+            writer.write_bytes(&extension.debug_extension)
+        }
+        AttributeInfo::RuntimeVisibleAnnotations(annotations)
+        | AttributeInfo::RuntimeInvisibleAnnotations(annotations) => {
+            write_annotations(annotations, writer)
+        }
+        AttributeInfo::RuntimeVisibleParameterAnnotations(parameters)
+        | AttributeInfo::RuntimeInvisibleParameterAnnotations(parameters) => {
+            write_parameter_annotations(parameters, writer)
+        }
+        AttributeInfo::AnnotationDefault(value) => write_element_value(value, writer),
+        AttributeInfo::StackMapTable(frames) => write_stack_map_frames(frames, writer),
+        AttributeInfo::BootstrapMethods(bootstrap) => {
+            writer.write_u16(bootstrap.bootstrap_methods.len() as u16);
+            for method in &bootstrap.bootstrap_methods {
+                writer.write_u16(method.bootstrap_method_ref);
+                writer.write_u16_table(&method.bootstrap_arguments);
+            }
+        }
+        AttributeInfo::RuntimeVisibleTypeAnnotations(annotations)
+        | AttributeInfo::RuntimeInvisibleTypeAnnotations(annotations) => {
+            write_type_annotations(annotations, writer)
+        }
+        AttributeInfo::MethodParameters(method_parameters) => {
+            writer.write_u8(method_parameters.parameters.len() as u8);
+            for parameter in &method_parameters.parameters {
+                writer.write_u16(parameter.name_index);
+                writer.write_u16(parameter.access_flags);
+            }
+        }
+        AttributeInfo::Module(module) => write_module(module, writer),
+        AttributeInfo::ModulePackages(packages) => writer.write_u16_table(&packages.package_index),
+        AttributeInfo::ModuleMainClass(main_class) => writer.write_u16(main_class.main_class_index),
+        AttributeInfo::NestHost(host) => writer.write_u16(host.host_class_index),
+        AttributeInfo::NestMembers(members) => writer.write_u16_table(&members.classes),
+        AttributeInfo::Record(record) => {
+            writer.write_u16(record.components.len() as u16);
+            for component in &record.components {
+                writer.write_u16(component.name_index);
+                writer.write_u16(component.descriptor_index);
+                write_attributes(
+                    &component.attributes,
+                    &component.attribute_name_indices,
+                    pool,
+                    writer,
+                )?;
+            }
+        }
+        AttributeInfo::PermittedSubclasses(permitted) => writer.write_u16_table(&permitted.classes),
+        AttributeInfo::Unknown(unknown) => writer.write_bytes(&unknown.info),
+    }
+    Ok(())
+}
+
+fn write_module(module: &ModuleAttribute, writer: &mut ClassFileWriter) {
+    writer.write_u16(module.module_name_index);
+    writer.write_u16(module.module_flags);
+    writer.write_u16(module.module_version_index);
+
+    writer.write_u16(module.requires.len() as u16);
+    for requires in &module.requires {
+        writer.write_u16(requires.requires_index);
+        writer.write_u16(requires.requires_flags);
+        writer.write_u16(requires.requires_version_index);
+    }
+
+    write_module_exports(&module.exports, writer);
+    write_module_exports(&module.opens, writer);
+    writer.write_u16_table(&module.uses_index);
+
+    writer.write_u16(module.provides.len() as u16);
+    for provides in &module.provides {
+        writer.write_u16(provides.provides_index);
+        writer.write_u16_table(&provides.provides_with_index);
+    }
+}
+
+fn write_module_exports(exports: &[ModuleExports], writer: &mut ClassFileWriter) {
+    writer.write_u16(exports.len() as u16);
+    for export in exports {
+        writer.write_u16(export.package_index);
+        writer.write_u16(export.flags);
+        writer.write_u16_table(&export.to_index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::annotations::ElementValue;
+    use crate::classfile::attribute_info::{read_attribute_info, AttributeInfo};
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::constant_pool::{ConstantPool, CpEntry};
+    use crate::classpath::class_path::ClassPath;
+    use crate::rtda::heap::class_loader::ClassLoader;
+    use crate::rtda::heap::instanced_klass::InstanceKlass;
+    use std::rc::Rc;
+
+    fn load(class_name: &str) -> Rc<InstanceKlass> {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/java17"));
+        let class_loader = ClassLoader::with_class_path(Rc::new(class_path));
+        class_loader.build_user_class(class_name).unwrap()
+    }
+
+    fn names(attributes: &[AttributeInfo]) -> Vec<&str> {
+        attributes.iter().map(|attr| attr.name()).collect()
+    }
+
+    fn find<'a>(attributes: &'a [AttributeInfo], name: &str) -> &'a AttributeInfo {
+        attributes.iter().find(|attr| attr.name() == name).unwrap()
+    }
+
+    #[test]
+    fn should_read_class_level_attributes() {
+        let klass = load("sample/AttributeSample");
+        let attributes = names(&klass.attributes);
+        for name in &[
+            "Signature",
+            "RuntimeVisibleAnnotations",
+            "NestMembers",
+            "InnerClasses",
+            "BootstrapMethods",
+            "SourceFile",
+        ] {
+            assert!(attributes.contains(name), "missing {}", name);
+        }
+
+        match find(&klass.attributes, "RuntimeVisibleAnnotations") {
+            AttributeInfo::RuntimeVisibleAnnotations(annotations) => {
+                let pairs = &annotations[0].element_value_pairs;
+                assert_eq!(3, pairs.len());
+                assert!(matches!(pairs[1].value, ElementValue::Enum { .. }));
+                match &pairs[2].value {
+                    ElementValue::Array(values) => assert_eq!(2, values.len()),
+                    _ => panic!("expected an array of classes"),
+                }
+            }
+            _ => unreachable!(),
+        }
+        match find(&klass.attributes, "NestMembers") {
+            AttributeInfo::NestMembers(members) => assert_eq!(8, members.classes.len()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn should_read_member_attributes() {
+        let klass = load("sample/AttributeSample");
+        match find(&klass.fields[0].attribute_table, "ConstantValue") {
+            AttributeInfo::ConstantValue(value) => assert!(value.constant_value_index > 0),
+            _ => unreachable!(),
+        }
+
+        let sum = &klass.methods[1].method_data.attribute_table;
+        let attributes = names(sum);
+        for name in &[
+            "Code",
+            "Exceptions",
+            "Deprecated",
+            "Signature",
+            "MethodParameters",
+            "RuntimeVisibleAnnotations",
+            "RuntimeVisibleParameterAnnotations",
+            "RuntimeVisibleTypeAnnotations",
+        ] {
+            assert!(attributes.contains(name), "missing {}", name);
+        }
+        match find(sum, "MethodParameters") {
+            AttributeInfo::MethodParameters(params) => assert_eq!(2, params.parameters.len()),
+            _ => unreachable!(),
+        }
+        match find(sum, "Code") {
+            AttributeInfo::Code(code) => {
+                let code_attributes = names(&code.attribute_table);
+                assert!(code_attributes.contains(&"StackMapTable"));
+                assert!(code_attributes.contains(&"LocalVariableTypeTable"));
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn should_read_record_and_sealed_attributes() {
+        let point = load("sample/AttributeSample$Point");
+        match find(&point.attributes, "Record") {
+            AttributeInfo::Record(record) => assert_eq!(2, record.components.len()),
+            _ => unreachable!(),
+        }
+
+        let shape = load("sample/AttributeSample$Shape");
+        match find(&shape.attributes, "PermittedSubclasses") {
+            AttributeInfo::PermittedSubclasses(permitted) => {
+                assert_eq!(2, permitted.classes.len())
+            }
+            _ => unreachable!(),
+        }
+
+        let anonymous = load("sample/AttributeSample$1");
+        let attributes = names(&anonymous.attributes);
+        assert!(attributes.contains(&"EnclosingMethod"));
+        assert!(attributes.contains(&"NestHost"));
+
+        let marker = load("sample/AttributeSample$Marker");
+        let name = &marker.methods[0].method_data.attribute_table;
+        assert!(matches!(
+            find(name, "AnnotationDefault"),
+            AttributeInfo::AnnotationDefault(ElementValue::Const { tag: b's', .. })
+        ));
+    }
+
+    #[test]
+    fn should_read_module_attributes() {
+        let module = load("module-info");
+        match find(&module.attributes, "Module") {
+            AttributeInfo::Module(module) => {
+                assert_eq!(1, module.requires.len());
+                assert_eq!(1, module.exports.len());
+                assert_eq!(1, module.opens.len());
+                assert_eq!(1, module.opens[0].to_index.len());
+                assert_eq!(1, module.uses_index.len());
+                assert_eq!(1, module.provides[0].provides_with_index.len());
+            }
+            _ => unreachable!(),
+        }
+        let attributes = names(&module.attributes);
+        assert!(attributes.contains(&"ModulePackages"));
+        assert!(attributes.contains(&"ModuleMainClass"));
+    }
+
+    #[test]
+    fn should_keep_unknown_attribute_bytes() {
+        let mut pool = ConstantPool::new();
+        pool.push(CpEntry::utf8("com.example.Custom"));
+        let mut stream = ClassFileStream::new(vec![0, 1, 0, 0, 0, 3, 7, 8, 9]);
+        match read_attribute_info(&mut stream, &pool).unwrap() {
+            (1, AttributeInfo::Unknown(unknown)) => {
+                assert_eq!("com.example.Custom", unknown.name);
+                assert_eq!(vec![7, 8, 9], unknown.info);
+            }
+            _ => panic!("expected an unknown attribute"),
+        }
+    }
 }
@@ -1,13 +1,17 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::classfile::parsed_class::ParsedClass;
-use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::rtda::heap::instanced_klass::{InstanceKlass, Supertypes};
+use std::rc::Rc;
 
 pub struct ClassFactory {}
 
 impl ClassFactory {
-    pub fn create_from_stream(stream: ClassFileStream) -> InstanceKlass {
-        let mut parser = ParsedClass::new(stream);
-        let klass = parser.create_instance_klass();
-        klass
+    pub fn create_from_stream(
+        stream: ClassFileStream,
+    ) -> Result<Rc<InstanceKlass>, ClassFormatError> {
+        let mut parser = ParsedClass::new(stream)?;
+        let klass = parser.create_instance_klass(Supertypes::default());
+        Ok(klass)
     }
 }
@@ -1,5 +1,7 @@
 use byteorder::{BigEndian, ByteOrder};
 
+use crate::classfile::class_format_error::ClassFormatError;
+
 #[derive(Debug, Clone)]
 pub struct ClassFileStream {
     source: Vec<u8>,
@@ -14,44 +16,68 @@ impl ClassFileStream {
         }
     }
 
-    pub fn get_u1(&mut self) -> u8 {
-        let mut x = vec![0; 1];
-        x[..1].clone_from_slice(&self.source[self.current..self.current + 1]);
-        self.current += 1;
-        x[0]
+    pub fn remaining(&self) -> usize {
+        self.source.len() - self.current
+    }
+
+    fn take(&mut self, length: usize) -> Result<&[u8], ClassFormatError> {
+        if length > self.remaining() {
+            return Err(ClassFormatError::Truncated {
+                offset: self.current,
+                needed: length,
+            });
+        }
+        let start = self.current;
+        self.current += length;
+        Ok(&self.source[start..self.current])
+    }
+
+    pub fn get_u1(&mut self) -> Result<u8, ClassFormatError> {
+        Ok(self.take(1)?[0])
     }
 
-    pub fn get_u2(&mut self) -> Vec<u8> {
-        let mut x = vec![0; 2];
-        x[..2].clone_from_slice(&self.source[self.current..self.current + 2]);
-        self.current += 2;
-        x
+    pub fn get_u2(&mut self) -> Result<Vec<u8>, ClassFormatError> {
+        Ok(self.take(2)?.to_vec())
     }
 
-    pub fn get_u4(&mut self) -> Vec<u8> {
-        let mut x = vec![0; 4];
-        x[..4].clone_from_slice(&self.source[self.current..self.current + 4]);
-        self.current += 4;
-        x
+    pub fn get_u4(&mut self) -> Result<Vec<u8>, ClassFormatError> {
+        Ok(self.take(4)?.to_vec())
     }
 
-    pub fn read_u8(&mut self) -> u8 {
+    pub fn read_u8(&mut self) -> Result<u8, ClassFormatError> {
         self.get_u1()
     }
 
-    pub fn read_u16(&mut self) -> u16 {
-        BigEndian::read_u16(&self.get_u2())
+    pub fn read_u16(&mut self) -> Result<u16, ClassFormatError> {
+        Ok(BigEndian::read_u16(self.take(2)?))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, ClassFormatError> {
+        Ok(BigEndian::read_u32(self.take(4)?))
     }
 
-    pub fn read_u32(&mut self) -> u32 {
-        BigEndian::read_u32(&self.get_u4())
+    pub fn read_to_length(&mut self, length: usize) -> Result<Vec<u8>, ClassFormatError> {
+        Ok(self.take(length)?.to_vec())
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::class_format_error::ClassFormatError;
 
-    pub fn read_to_length(&mut self, length: u16) -> Vec<u8> {
-        let len = length as usize;
-        let mut x = vec![0; len];
-        x[..len].clone_from_slice(&self.source[self.current..self.current + len]);
-        self.current += len;
-        x
+    #[test]
+    fn should_report_truncated_reads() {
+        let mut stream = ClassFileStream::new(vec![0xca, 0xfe, 0xba]);
+        assert_eq!(Ok(0xcafe), stream.read_u16());
+        assert_eq!(
+            Err(ClassFormatError::Truncated {
+                offset: 2,
+                needed: 4
+            }),
+            stream.read_u32()
+        );
+        assert_eq!(Ok(0xba), stream.read_u8());
+        assert_eq!(0, stream.remaining());
     }
 }
@@ -0,0 +1,71 @@
+/// The write side of `ClassFileStream`: big-endian values appended to a buffer.
+#[derive(Debug, Clone, Default)]
+pub struct ClassFileWriter {
+    buffer: Vec<u8>,
+}
+
+impl ClassFileWriter {
+    pub fn new() -> ClassFileWriter {
+        ClassFileWriter { buffer: vec![] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.buffer.push(value);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.buffer.extend_from_slice(&value.to_be_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Writes a u2 count followed by that many u2 values.
+    pub fn write_u16_table(&mut self, table: &[u16]) {
+        self.write_u16(table.len() as u16);
+        for value in table {
+            self.write_u16(*value);
+        }
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::class_file_writer::ClassFileWriter;
+
+    #[test]
+    fn should_write_what_the_stream_reads() {
+        let mut writer = ClassFileWriter::new();
+        writer.write_u32(0xcafe_babe);
+        writer.write_u16(52);
+        writer.write_u8(7);
+        writer.write_u16_table(&[1, 0x0102]);
+
+        let mut stream = ClassFileStream::new(writer.into_bytes());
+        assert_eq!(Ok(0xcafe_babe), stream.read_u32());
+        assert_eq!(Ok(52), stream.read_u16());
+        assert_eq!(Ok(7), stream.read_u8());
+        assert_eq!(Ok(2), stream.read_u16());
+        assert_eq!(Ok(1), stream.read_u16());
+        assert_eq!(Ok(0x0102), stream.read_u16());
+        assert_eq!(0, stream.remaining());
+    }
+}
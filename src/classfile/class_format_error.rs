@@ -0,0 +1,164 @@
+use std::error::Error;
+use std::fmt;
+
+/// Reasons a byte stream is rejected as a class file, the Rust side of
+/// `java.lang.ClassFormatError`. Offsets are byte positions in the stream.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClassFormatError {
+    Truncated {
+        offset: usize,
+        needed: usize,
+    },
+    BadMagic {
+        magic: u32,
+    },
+    ConstantPoolSize {
+        count: u16,
+    },
+    UnknownConstantTag {
+        offset: usize,
+        tag: u8,
+    },
+    /// an unknown tag byte in a structure other than the constant pool
+    UnknownTag {
+        offset: usize,
+        tag: u8,
+        kind: &'static str,
+    },
+    InvalidUtf8 {
+        offset: usize,
+    },
+    /// a Utf8 constant whose encoding does not fit its u2 length, on writing
+    Utf8TooLong {
+        length: usize,
+    },
+    BadConstantIndex {
+        offset: usize,
+        index: u16,
+        expected: &'static str,
+    },
+    AttributeLength {
+        offset: usize,
+        declared: u32,
+        actual: usize,
+    },
+    ExtraBytes {
+        offset: usize,
+    },
+    /// wraps an error raised while reading the constant pool entry `index`
+    InConstantPool {
+        index: u16,
+        cause: Box<ClassFormatError>,
+    },
+    /// wraps an error raised while reading the attribute called `name`
+    InAttribute {
+        name: String,
+        cause: Box<ClassFormatError>,
+    },
+}
+
+impl ClassFormatError {
+    pub fn in_constant_pool(index: u16, cause: ClassFormatError) -> ClassFormatError {
+        ClassFormatError::InConstantPool {
+            index,
+            cause: Box::new(cause),
+        }
+    }
+
+    pub fn in_attribute(name: &str, cause: ClassFormatError) -> ClassFormatError {
+        ClassFormatError::InAttribute {
+            name: String::from(name),
+            cause: Box::new(cause),
+        }
+    }
+
+    /// byte offset of the innermost error, if it has one
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            ClassFormatError::Truncated { offset, .. }
+            | ClassFormatError::UnknownConstantTag { offset, .. }
+            | ClassFormatError::UnknownTag { offset, .. }
+            | ClassFormatError::InvalidUtf8 { offset }
+            | ClassFormatError::BadConstantIndex { offset, .. }
+            | ClassFormatError::AttributeLength { offset, .. }
+            | ClassFormatError::ExtraBytes { offset } => Some(*offset),
+            ClassFormatError::BadMagic { .. } => Some(0),
+            ClassFormatError::ConstantPoolSize { .. } | ClassFormatError::Utf8TooLong { .. } => {
+                None
+            }
+            ClassFormatError::InConstantPool { cause, .. }
+            | ClassFormatError::InAttribute { cause, .. } => cause.offset(),
+        }
+    }
+}
+
+impl fmt::Display for ClassFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassFormatError::Truncated { offset, needed } => write!(
+                f,
+                "Truncated class file: needed {} more bytes at offset {}",
+                needed, offset
+            ),
+            ClassFormatError::BadMagic { magic } => {
+                write!(f, "Incompatible magic value {:#010x}", magic)
+            }
+            ClassFormatError::ConstantPoolSize { count } => {
+                write!(f, "Illegal constant pool size {}", count)
+            }
+            ClassFormatError::UnknownConstantTag { offset, tag } => {
+                write!(f, "Unknown constant tag {} at offset {}", tag, offset)
+            }
+            ClassFormatError::UnknownTag { offset, tag, kind } => {
+                write!(f, "Unknown {} tag {} at offset {}", kind, tag, offset)
+            }
+            ClassFormatError::InvalidUtf8 { offset } => {
+                write!(f, "Illegal UTF8 string at offset {}", offset)
+            }
+            ClassFormatError::Utf8TooLong { length } => {
+                write!(f, "UTF8 string too long: {} bytes", length)
+            }
+            ClassFormatError::BadConstantIndex {
+                offset,
+                index,
+                expected,
+            } => write!(
+                f,
+                "Invalid constant pool index {} at offset {}, expected {}",
+                index, offset, expected
+            ),
+            ClassFormatError::AttributeLength {
+                offset,
+                declared,
+                actual,
+            } => write!(
+                f,
+                "Attribute at offset {} declares {} bytes but {} were read",
+                offset, declared, actual
+            ),
+            ClassFormatError::ExtraBytes { offset } => {
+                write!(
+                    f,
+                    "Extra bytes at the end of class file at offset {}",
+                    offset
+                )
+            }
+            ClassFormatError::InConstantPool { index, cause } => {
+                write!(f, "{} (constant pool #{})", cause, index)
+            }
+            ClassFormatError::InAttribute { name, cause } => {
+                write!(f, "{} (in {} attribute)", cause, name)
+            }
+        }
+    }
+}
+
+impl Error for ClassFormatError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClassFormatError::InConstantPool { cause, .. }
+            | ClassFormatError::InAttribute { cause, .. } => Some(cause.as_ref()),
+            _ => None,
+        }
+    }
+}
@@ -1,22 +1,21 @@
 use crate::classfile::constant_pool::MemberRef;
 use crate::classfile::parsed_class::ParsedClass;
-use crate::rtda::heap::instanced_klass::InstanceKlass;
 
+/// A field or method reference, by name until it is resolved.
 #[derive(Clone, Debug)]
 pub struct ConstantMemberRef {
-    pub class: Box<InstanceKlass>,
+    /// the class the member is looked up in, e.g. `java/io/PrintStream`
     pub class_name: String,
     pub name: String,
     pub descriptor: String,
 }
 
 impl ConstantMemberRef {
-    pub fn new(class: &InstanceKlass, cf: &ParsedClass, field_ref: MemberRef) -> ConstantMemberRef {
-        let class_name = &class.klass_name;
-        let name_type = cf.get_name_and_type(field_ref.name_type_index);
+    pub fn new(cf: &ParsedClass, member_ref: MemberRef) -> ConstantMemberRef {
+        let class_name = cf.constant_pool().get_class_name(member_ref.class_index);
+        let name_type = cf.get_name_and_type(member_ref.name_type_index);
         ConstantMemberRef {
-            class: Box::new(class.clone()),
-            class_name: String::from(class_name),
+            class_name: String::from(class_name.unwrap_or("")),
             name: name_type.name,
             descriptor: name_type.typ,
         }
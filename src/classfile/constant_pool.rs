@@ -1,8 +1,7 @@
-use std::str;
-
-use byteorder::{BigEndian, ByteOrder};
-
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_file_writer::ClassFileWriter;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::classfile::modified_utf8;
 
 pub const CONSTANT_UTF8: u8 = 1;
 pub const CONSTANT_INTEGER: u8 = 3;
@@ -27,13 +26,13 @@ pub struct ConstantInfo {}
 #[derive(Clone, Debug)]
 pub struct MemberRef {
     pub class_index: u16,
-    pub(crate) name_type_index: u16,
+    pub name_type_index: u16,
 }
 
 #[derive(Clone, Debug)]
 pub enum CpEntry {
     Empty {},
-    Utf8 { val: String },
+    Utf8 { val: String, bytes: Vec<u8> },
     Integer { val: i32 },
     Float { val: f32 },
     Long { val: i64 },
@@ -42,67 +41,610 @@ pub enum CpEntry {
     String { idx: u16 },
     FieldRef(MemberRef),
     MethodRef(MemberRef),
-    InterfaceMethodRef { clz_idx: u16, nt_idx: u16 },
+    InterfaceMethodRef(MemberRef),
     NameAndType { name_idx: u16, type_idx: u16 },
+    MethodHandle { ref_kind: u8, ref_idx: u16 },
+    MethodType { desc_idx: u16 },
+    Dynamic { bsm_idx: u16, nt_idx: u16 },
+    InvokeDynamic { bsm_idx: u16, nt_idx: u16 },
+    Module { name_idx: u16 },
+    Package { name_idx: u16 },
+}
+
+impl CpEntry {
+    /// A Utf8 entry for `text`. Entries read from a class file keep their
+    /// modified UTF-8 `bytes` as they were, unpaired surrogates included,
+    /// while `val` is only their text.
+    pub fn utf8(text: &str) -> CpEntry {
+        CpEntry::Utf8 {
+            val: String::from(text),
+            bytes: modified_utf8::encode(text),
+        }
+    }
+
+    /// Long and Double constants take up two entries in the constant pool,
+    /// the second one is unusable.
+    pub fn is_wide(&self) -> bool {
+        matches!(self, CpEntry::Long { .. } | CpEntry::Double { .. })
+    }
 }
 
 impl ConstantInfo {
-    pub fn from(stream: &mut ClassFileStream) -> CpEntry {
-        let tag = stream.get_u1();
-        match tag {
+    pub fn from(stream: &mut ClassFileStream) -> Result<CpEntry, ClassFormatError> {
+        let offset = stream.current;
+        let tag = stream.get_u1()?;
+        let entry = match tag {
             CONSTANT_UTF8 => {
-                let length_bytes = stream.get_u2();
-                let length = BigEndian::read_i16(&length_bytes);
-
-                let str_bytes = stream.read_to_length(length as u16);
-                let s = match str::from_utf8(&str_bytes) {
-                    Ok(v) => v,
-                    Err(e) => panic!("Invalid UTF-8 sequence: {}", e),
-                };
+                let length = stream.read_u16()?;
+                let start = stream.current;
+                let str_bytes = stream.read_to_length(length as usize)?;
+                let val = modified_utf8::decode(&str_bytes).map_err(|pos| {
+                    ClassFormatError::InvalidUtf8 {
+                        offset: start + pos,
+                    }
+                })?;
                 CpEntry::Utf8 {
-                    val: String::from(s),
+                    val,
+                    bytes: str_bytes,
+                }
+            }
+            CONSTANT_INTEGER => CpEntry::Integer {
+                val: stream.read_u32()? as i32,
+            },
+            CONSTANT_FLOAT => CpEntry::Float {
+                val: f32::from_bits(stream.read_u32()?),
+            },
+            CONSTANT_LONG => {
+                let high = stream.read_u32()? as u64;
+                let low = stream.read_u32()? as u64;
+                CpEntry::Long {
+                    val: ((high << 32) | low) as i64,
+                }
+            }
+            CONSTANT_DOUBLE => {
+                let high = stream.read_u32()? as u64;
+                let low = stream.read_u32()? as u64;
+                CpEntry::Double {
+                    val: f64::from_bits((high << 32) | low),
                 }
             }
-            // CONSTANT_INTEGER => {}
-            // CONSTANT_FLOAT => {}
-            // CONSTANT_LONG => {}
-            // CONSTANT_DOUBLE => {}
             CONSTANT_CLASS => CpEntry::Class {
-                idx: stream.read_u16(),
+                idx: stream.read_u16()?,
             },
             CONSTANT_STRING => CpEntry::String {
-                idx: stream.read_u16(),
+                idx: stream.read_u16()?,
             },
             CONSTANT_FIELD_REF => {
                 let field_ref = MemberRef {
-                    class_index: stream.read_u16(),
-                    name_type_index: stream.read_u16(),
+                    class_index: stream.read_u16()?,
+                    name_type_index: stream.read_u16()?,
                 };
                 CpEntry::FieldRef(field_ref)
             }
             CONSTANT_METHOD_REF => CpEntry::MethodRef(MemberRef {
-                class_index: stream.read_u16(),
-                name_type_index: stream.read_u16(),
+                class_index: stream.read_u16()?,
+                name_type_index: stream.read_u16()?,
+            }),
+            CONSTANT_INTERFACE_METHOD_REF => CpEntry::InterfaceMethodRef(MemberRef {
+                class_index: stream.read_u16()?,
+                name_type_index: stream.read_u16()?,
             }),
-            // CONSTANT_INTERFACE_METHOD_REF => {}
             CONSTANT_NAME_AND_TYPE => CpEntry::NameAndType {
-                name_idx: stream.read_u16(),
-                type_idx: stream.read_u16(),
+                name_idx: stream.read_u16()?,
+                type_idx: stream.read_u16()?,
             },
-            // CONSTANT_METHOD_HANDLE => {}
-            // CONSTANT_METHOD_TYPE => {}
-            // CONSTANT_INVOKE_DYNAMIC => {}
-            // CONSTANT_MODULE => {}
-            // CONSTANT_PACKAGE => {}
-            // CONSTANT_DYNAMIC => {}
-            _ => panic!(
-                "Unsupported Constant Pool type {} at {}",
-                tag, stream.current
-            ),
+            CONSTANT_METHOD_HANDLE => CpEntry::MethodHandle {
+                ref_kind: stream.read_u8()?,
+                ref_idx: stream.read_u16()?,
+            },
+            CONSTANT_METHOD_TYPE => CpEntry::MethodType {
+                desc_idx: stream.read_u16()?,
+            },
+            CONSTANT_DYNAMIC => CpEntry::Dynamic {
+                bsm_idx: stream.read_u16()?,
+                nt_idx: stream.read_u16()?,
+            },
+            CONSTANT_INVOKE_DYNAMIC => CpEntry::InvokeDynamic {
+                bsm_idx: stream.read_u16()?,
+                nt_idx: stream.read_u16()?,
+            },
+            CONSTANT_MODULE => CpEntry::Module {
+                name_idx: stream.read_u16()?,
+            },
+            CONSTANT_PACKAGE => CpEntry::Package {
+                name_idx: stream.read_u16()?,
+            },
+            _ => return Err(ClassFormatError::UnknownConstantTag { offset, tag }),
+        };
+        Ok(entry)
+    }
+
+    /// Writes one entry, the inverse of `from`. Unusable slots write nothing.
+    /// A Utf8 entry whose text was not changed writes the bytes it was read
+    /// from, so lone surrogates and non-canonical encodings survive.
+    pub fn write(entry: &CpEntry, writer: &mut ClassFileWriter) -> Result<(), ClassFormatError> {
+        match entry {
+            CpEntry::Empty {} => {}
+            CpEntry::Utf8 { val, bytes } => {
+                let encoded;
+                let bytes = match modified_utf8::decode(bytes) {
+                    Ok(read) if read == *val => bytes,
+                    _ => {
+                        encoded = modified_utf8::encode(val);
+                        &encoded
+                    }
+                };
+                if bytes.len() > 0xffff {
+                    return Err(ClassFormatError::Utf8TooLong {
+                        length: bytes.len(),
+                    });
+                }
+                writer.write_u8(CONSTANT_UTF8);
+                writer.write_u16(bytes.len() as u16);
+                writer.write_bytes(bytes);
+            }
+            CpEntry::Integer { val } => {
+                writer.write_u8(CONSTANT_INTEGER);
+                writer.write_u32(*val as u32);
+            }
+            CpEntry::Float { val } => {
+                writer.write_u8(CONSTANT_FLOAT);
+                writer.write_u32(val.to_bits());
+            }
+            CpEntry::Long { val } => {
+                writer.write_u8(CONSTANT_LONG);
+                writer.write_bytes(&val.to_be_bytes());
+            }
+            CpEntry::Double { val } => {
+                writer.write_u8(CONSTANT_DOUBLE);
+                writer.write_bytes(&val.to_bits().to_be_bytes());
+            }
+            CpEntry::Class { idx } => {
+                writer.write_u8(CONSTANT_CLASS);
+                writer.write_u16(*idx);
+            }
+            CpEntry::String { idx } => {
+                writer.write_u8(CONSTANT_STRING);
+                writer.write_u16(*idx);
+            }
+            CpEntry::FieldRef(member_ref) => {
+                writer.write_u8(CONSTANT_FIELD_REF);
+                write_member_ref(member_ref, writer);
+            }
+            CpEntry::MethodRef(member_ref) => {
+                writer.write_u8(CONSTANT_METHOD_REF);
+                write_member_ref(member_ref, writer);
+            }
+            CpEntry::InterfaceMethodRef(member_ref) => {
+                writer.write_u8(CONSTANT_INTERFACE_METHOD_REF);
+                write_member_ref(member_ref, writer);
+            }
+            CpEntry::NameAndType { name_idx, type_idx } => {
+                writer.write_u8(CONSTANT_NAME_AND_TYPE);
+                writer.write_u16(*name_idx);
+                writer.write_u16(*type_idx);
+            }
+            CpEntry::MethodHandle { ref_kind, ref_idx } => {
+                writer.write_u8(CONSTANT_METHOD_HANDLE);
+                writer.write_u8(*ref_kind);
+                writer.write_u16(*ref_idx);
+            }
+            CpEntry::MethodType { desc_idx } => {
+                writer.write_u8(CONSTANT_METHOD_TYPE);
+                writer.write_u16(*desc_idx);
+            }
+            CpEntry::Dynamic { bsm_idx, nt_idx } => {
+                writer.write_u8(CONSTANT_DYNAMIC);
+                writer.write_u16(*bsm_idx);
+                writer.write_u16(*nt_idx);
+            }
+            CpEntry::InvokeDynamic { bsm_idx, nt_idx } => {
+                writer.write_u8(CONSTANT_INVOKE_DYNAMIC);
+                writer.write_u16(*bsm_idx);
+                writer.write_u16(*nt_idx);
+            }
+            CpEntry::Module { name_idx } => {
+                writer.write_u8(CONSTANT_MODULE);
+                writer.write_u16(*name_idx);
+            }
+            CpEntry::Package { name_idx } => {
+                writer.write_u8(CONSTANT_PACKAGE);
+                writer.write_u16(*name_idx);
+            }
         }
+        Ok(())
     }
 }
 
-pub struct ConstantPool {}
+fn write_member_ref(member_ref: &MemberRef, writer: &mut ClassFileWriter) {
+    writer.write_u16(member_ref.class_index);
+    writer.write_u16(member_ref.name_type_index);
+}
+
+/// The constant pool of a class. Slot 0 and the slot following a Long or
+/// Double are never valid, so `count()` is the `constant_pool_count` of the
+/// class file: one more than the highest usable index.
+#[derive(Clone, Debug)]
+pub struct ConstantPool {
+    entries: Vec<CpEntry>,
+}
+
+impl ConstantPool {
+    pub fn new() -> ConstantPool {
+        ConstantPool {
+            entries: vec![CpEntry::Empty {}],
+        }
+    }
+
+    pub fn parse(
+        stream: &mut ClassFileStream,
+        count: u16,
+    ) -> Result<ConstantPool, ClassFormatError> {
+        if count == 0 {
+            return Err(ClassFormatError::ConstantPoolSize { count });
+        }
+
+        let mut pool = ConstantPool::new();
+        pool.entries.reserve(count as usize);
+        // The constant_pool table is indexed from 1
+        let mut index = 1;
+        while index < count {
+            let entry = ConstantInfo::from(stream)
+                .map_err(|e| ClassFormatError::in_constant_pool(index, e))?;
+            // the entry after a Long or Double is valid but unusable
+            if entry.is_wide() && index + 1 == count {
+                let err = ClassFormatError::ConstantPoolSize { count };
+                return Err(ClassFormatError::in_constant_pool(index, err));
+            }
+            let (_, slots) = pool
+                .push(entry)
+                .ok_or(ClassFormatError::ConstantPoolSize { count })?;
+            index += slots;
+        }
+        Ok(pool)
+    }
+
+    /// Appends an entry, returning its index and the number of slots it took,
+    /// or `None` when the pool has no room left for it: `count()` never
+    /// exceeds 65535.
+    pub fn push(&mut self, entry: CpEntry) -> Option<(u16, u16)> {
+        let slots = if entry.is_wide() { 2 } else { 1 };
+        if self.entries.len() + slots > 0xffff {
+            return None;
+        }
+        let index = self.entries.len() as u16;
+        self.entries.push(entry);
+        if slots == 2 {
+            self.entries.push(CpEntry::Empty {});
+        }
+        Some((index, slots as u16))
+    }
+
+    /// Replaces the entry at `index`, returning the one it held, or `None`
+    /// when `index` is not usable or the widths differ. The pool is left
+    /// untouched in that case, so the indices of later entries never move.
+    pub fn set(&mut self, index: u16, entry: CpEntry) -> Option<CpEntry> {
+        match self.get(index) {
+            Some(old) if old.is_wide() == entry.is_wide() => {
+                Some(std::mem::replace(&mut self.entries[index as usize], entry))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn count(&self) -> u16 {
+        self.entries.len() as u16
+    }
 
-impl ConstantPool {}
+    /// Every slot including the unusable ones, so positions match pool indices.
+    pub fn entries(&self) -> &[CpEntry] {
+        &self.entries
+    }
+
+    /// Returns `None` for index 0, out of range indices and unusable slots.
+    pub fn get(&self, index: u16) -> Option<&CpEntry> {
+        match self.entries.get(index as usize) {
+            Some(CpEntry::Empty {}) | None => None,
+            entry => entry,
+        }
+    }
+
+    pub fn get_utf8(&self, index: u16) -> Option<&str> {
+        match self.get(index) {
+            Some(CpEntry::Utf8 { val, .. }) => Some(val),
+            _ => None,
+        }
+    }
+
+    /// The modified UTF-8 of the Utf8 entry `index`, lone surrogates intact.
+    pub fn get_utf8_bytes(&self, index: u16) -> Option<&[u8]> {
+        match self.get(index) {
+            Some(CpEntry::Utf8 { bytes, .. }) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    pub fn get_class_name(&self, index: u16) -> Option<&str> {
+        match self.get(index) {
+            Some(CpEntry::Class { idx }) => self.get_utf8(*idx),
+            _ => None,
+        }
+    }
+
+    /// Index of the first Utf8 entry holding `text`.
+    pub fn find_utf8(&self, text: &str) -> Option<u16> {
+        self.entries
+            .iter()
+            .position(|entry| matches!(entry, CpEntry::Utf8 { val, .. } if val == text))
+            .map(|index| index as u16)
+    }
+
+    /// Like `find_utf8`, appending a new entry when the pool has none. Fails
+    /// when the pool is already full.
+    pub fn utf8_index(&mut self, text: &str) -> Result<u16, ClassFormatError> {
+        if let Some(index) = self.find_utf8(text) {
+            return Ok(index);
+        }
+        match self.push(CpEntry::utf8(text)) {
+            Some((index, _)) => Ok(index),
+            None => Err(ClassFormatError::ConstantPoolSize {
+                count: self.count(),
+            }),
+        }
+    }
+
+    /// Writes `constant_pool_count` followed by the entries.
+    pub fn write(&self, writer: &mut ClassFileWriter) -> Result<(), ClassFormatError> {
+        writer.write_u16(self.count());
+        for (index, entry) in self.entries.iter().enumerate() {
+            ConstantInfo::write(entry, writer)
+                .map_err(|e| ClassFormatError::in_constant_pool(index as u16, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::class_file_writer::ClassFileWriter;
+    use crate::classfile::class_format_error::ClassFormatError;
+    use crate::classfile::constant_pool::{ConstantInfo, ConstantPool, CpEntry};
+
+    fn read_entry(bytes: Vec<u8>) -> CpEntry {
+        let mut stream = ClassFileStream::new(bytes);
+        ConstantInfo::from(&mut stream).unwrap()
+    }
+
+    #[test]
+    fn should_read_numeric_constants() {
+        match read_entry(vec![3, 0xff, 0xff, 0xff, 0xfe]) {
+            CpEntry::Integer { val } => assert_eq!(-2, val),
+            _ => panic!("expected integer"),
+        }
+        match read_entry(vec![4, 0x40, 0x60, 0x00, 0x00]) {
+            CpEntry::Float { val } => assert_eq!(3.5, val),
+            _ => panic!("expected float"),
+        }
+        match read_entry(vec![5, 0, 0, 0x01, 0x1f, 0x71, 0xfb, 0x04, 0xcb]) {
+            CpEntry::Long { val } => assert_eq!(1_234_567_890_123, val),
+            _ => panic!("expected long"),
+        }
+        match read_entry(vec![6, 0xbf, 0xf0, 0, 0, 0, 0, 0, 0]) {
+            CpEntry::Double { val } => assert_eq!(-1.0, val),
+            _ => panic!("expected double"),
+        }
+    }
+
+    #[test]
+    fn should_read_dynamic_constants() {
+        match read_entry(vec![15, 6, 0, 78]) {
+            CpEntry::MethodHandle { ref_kind, ref_idx } => {
+                assert_eq!(6, ref_kind);
+                assert_eq!(78, ref_idx);
+            }
+            _ => panic!("expected method handle"),
+        }
+        match read_entry(vec![16, 0, 36]) {
+            CpEntry::MethodType { desc_idx } => assert_eq!(36, desc_idx),
+            _ => panic!("expected method type"),
+        }
+        match read_entry(vec![17, 0, 1, 0, 25]) {
+            CpEntry::Dynamic { bsm_idx, nt_idx } => assert_eq!((1, 25), (bsm_idx, nt_idx)),
+            _ => panic!("expected dynamic"),
+        }
+        match read_entry(vec![18, 0, 0, 0, 25]) {
+            CpEntry::InvokeDynamic { bsm_idx, nt_idx } => assert_eq!((0, 25), (bsm_idx, nt_idx)),
+            _ => panic!("expected invoke dynamic"),
+        }
+    }
+
+    #[test]
+    fn should_read_module_constants() {
+        match read_entry(vec![19, 0, 3]) {
+            CpEntry::Module { name_idx } => assert_eq!(3, name_idx),
+            _ => panic!("expected module"),
+        }
+        match read_entry(vec![20, 0, 4]) {
+            CpEntry::Package { name_idx } => assert_eq!(4, name_idx),
+            _ => panic!("expected package"),
+        }
+    }
+
+    #[test]
+    fn should_mark_long_and_double_as_wide() {
+        assert!(CpEntry::Long { val: 0 }.is_wide());
+        assert!(CpEntry::Double { val: 0.0 }.is_wide());
+        assert!(!CpEntry::Integer { val: 0 }.is_wide());
+    }
+
+    #[test]
+    fn should_reject_unknown_tag() {
+        let mut stream = ClassFileStream::new(vec![2, 0, 0]);
+        assert_eq!(
+            Err(ClassFormatError::UnknownConstantTag { offset: 0, tag: 2 }),
+            ConstantInfo::from(&mut stream).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn should_reject_truncated_entry() {
+        let mut stream = ClassFileStream::new(vec![10, 0, 1, 0]);
+        assert_eq!(
+            Err(ClassFormatError::Truncated {
+                offset: 3,
+                needed: 2
+            }),
+            ConstantInfo::from(&mut stream).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn should_decode_modified_utf8() {
+        match read_entry(vec![1, 0, 4, 0x61, 0xc0, 0x80, 0x62]) {
+            CpEntry::Utf8 { val, .. } => assert_eq!("a\0b", val),
+            _ => panic!("expected utf8"),
+        }
+
+        let mut stream = ClassFileStream::new(vec![1, 0, 2, 0x61, 0x00]);
+        assert_eq!(
+            Err(ClassFormatError::InvalidUtf8 { offset: 4 }),
+            ConstantInfo::from(&mut stream).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn should_keep_utf8_bytes_with_lone_surrogate() {
+        let bytes = vec![0xed, 0xa0, 0xbd];
+        match read_entry(vec![1, 0, 3, 0xed, 0xa0, 0xbd]) {
+            CpEntry::Utf8 { val, bytes: read } => {
+                assert_eq!("\u{FFFD}", val);
+                assert_eq!(bytes, read);
+            }
+            _ => panic!("expected utf8"),
+        }
+    }
+
+    #[test]
+    fn should_index_pool_from_one() {
+        let mut pool = ConstantPool::new();
+        assert_eq!(Some((1, 1)), pool.push(CpEntry::utf8("A")));
+        assert_eq!(Some((2, 1)), pool.push(CpEntry::Class { idx: 1 }));
+        assert_eq!(Some((3, 2)), pool.push(CpEntry::Long { val: 7 }));
+        assert_eq!(Some((5, 1)), pool.push(CpEntry::Integer { val: 1 }));
+
+        assert_eq!(6, pool.count());
+        assert!(pool.get(0).is_none());
+        assert!(pool.get(4).is_none());
+        assert!(pool.get(6).is_none());
+        assert_eq!(Some("A"), pool.get_class_name(2));
+        assert_eq!(None, pool.get_class_name(1));
+    }
+
+    #[test]
+    fn should_refuse_entries_once_pool_is_full() {
+        let mut pool = ConstantPool::new();
+        while pool.count() < 0xfffe {
+            pool.push(CpEntry::Integer { val: 0 }).unwrap();
+        }
+        assert_eq!(None, pool.push(CpEntry::Long { val: 1 }));
+        assert_eq!(Some((0xfffe, 1)), pool.push(CpEntry::Integer { val: 1 }));
+        assert_eq!(None, pool.push(CpEntry::Integer { val: 2 }));
+
+        assert_eq!(0xffff, pool.count());
+        assert!(matches!(
+            pool.get(0xfffe),
+            Some(CpEntry::Integer { val: 1 })
+        ));
+    }
+
+    #[test]
+    fn should_reject_long_in_last_slot() {
+        let mut stream = ClassFileStream::new(vec![5, 0, 0, 0, 0, 0, 0, 0, 1]);
+        let err = ConstantPool::parse(&mut stream, 2).err().unwrap();
+        assert_eq!(
+            ClassFormatError::in_constant_pool(1, ClassFormatError::ConstantPoolSize { count: 2 }),
+            err
+        );
+    }
+
+    #[test]
+    fn should_write_entries_back() {
+        let entries: Vec<Vec<u8>> = vec![
+            vec![1, 0, 4, 0x61, 0xc0, 0x80, 0x62],
+            vec![1, 0, 3, 0xed, 0xa0, 0xbd],
+            vec![1, 0, 3, 0x61, 0xc1, 0x81],
+            vec![3, 0xff, 0xff, 0xff, 0xfe],
+            vec![4, 0x7f, 0xc0, 0x00, 0x01],
+            vec![5, 0, 0, 0x01, 0x1f, 0x71, 0xfb, 0x04, 0xcb],
+            vec![6, 0xbf, 0xf0, 0, 0, 0, 0, 0, 0],
+            vec![11, 0, 13, 0, 14],
+            vec![15, 6, 0, 78],
+            vec![17, 0, 1, 0, 25],
+            vec![20, 0, 4],
+        ];
+        for bytes in entries {
+            let mut writer = ClassFileWriter::new();
+            ConstantInfo::write(&read_entry(bytes.clone()), &mut writer).unwrap();
+            assert_eq!(bytes, writer.into_bytes());
+        }
+    }
+
+    #[test]
+    fn should_encode_changed_utf8_text() {
+        let mut entry = read_entry(vec![1, 0, 2, 0xc1, 0x81]);
+        if let CpEntry::Utf8 { val, .. } = &mut entry {
+            val.push('\0');
+        }
+        let mut writer = ClassFileWriter::new();
+        ConstantInfo::write(&entry, &mut writer).unwrap();
+        assert_eq!(vec![1, 0, 3, 0x41, 0xc0, 0x80], writer.into_bytes());
+    }
+
+    #[test]
+    fn should_reject_too_long_utf8() {
+        let mut pool = ConstantPool::new();
+        pool.push(CpEntry::utf8("A"));
+        pool.push(CpEntry::utf8(&"\u{800}".repeat(0x5556)));
+        let err = pool.write(&mut ClassFileWriter::new()).err().unwrap();
+        assert_eq!(
+            ClassFormatError::in_constant_pool(
+                2,
+                ClassFormatError::Utf8TooLong { length: 0x10002 }
+            ),
+            err
+        );
+    }
+
+    #[test]
+    fn should_append_missing_utf8() {
+        let mut pool = ConstantPool::new();
+        pool.push(CpEntry::utf8("Code"));
+        assert_eq!(Ok(1), pool.utf8_index("Code"));
+        assert_eq!(None, pool.find_utf8("Signature"));
+        assert_eq!(Ok(2), pool.utf8_index("Signature"));
+        assert_eq!(3, pool.count());
+    }
+
+    #[test]
+    fn should_replace_entries_of_the_same_width() {
+        let mut pool = ConstantPool::new();
+        pool.push(CpEntry::utf8("Code"));
+        pool.push(CpEntry::Long { val: 7 });
+
+        match pool.set(1, CpEntry::Integer { val: 3 }) {
+            Some(CpEntry::Utf8 { val, .. }) => assert_eq!("Code", val),
+            _ => panic!("expected the replaced utf8"),
+        }
+        assert!(pool.set(1, CpEntry::Long { val: 8 }).is_none());
+        assert!(pool.set(2, CpEntry::Integer { val: 8 }).is_none());
+        assert!(pool.set(3, CpEntry::Integer { val: 8 }).is_none());
+        assert!(pool.set(4, CpEntry::Integer { val: 8 }).is_none());
+        assert!(pool.set(2, CpEntry::Double { val: 0.5 }).is_some());
+        match pool.get(1) {
+            Some(CpEntry::Integer { val }) => assert_eq!(3, *val),
+            _ => panic!("expected an integer"),
+        }
+        assert_eq!(4, pool.count());
+    }
+}
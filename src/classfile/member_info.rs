@@ -1,9 +1,57 @@
+use crate::classfile::access_flags::AccessFlags;
 use crate::classfile::attribute_info::AttributeInfo;
 
 #[derive(Clone, Debug)]
 pub struct MemberInfo {
-    pub(crate) access_flags: u16,
+    pub(crate) access_flags: AccessFlags,
     pub(crate) name_index: u16,
     pub(crate) descriptor_index: u16,
     pub(crate) attribute_table: Vec<AttributeInfo>,
+    pub(crate) attribute_name_indices: Vec<u16>,
+}
+
+impl MemberInfo {
+    /// A member without attributes. `name_index` and `descriptor_index` must
+    /// be Utf8 entries of the pool of the class it is added to.
+    pub fn new(access_flags: AccessFlags, name_index: u16, descriptor_index: u16) -> MemberInfo {
+        MemberInfo {
+            access_flags,
+            name_index,
+            descriptor_index,
+            attribute_table: vec![],
+            attribute_name_indices: vec![],
+        }
+    }
+
+    pub fn access_flags(&self) -> AccessFlags {
+        self.access_flags
+    }
+
+    pub fn set_access_flags(&mut self, access_flags: AccessFlags) {
+        self.access_flags = access_flags;
+    }
+
+    pub fn name_index(&self) -> u16 {
+        self.name_index
+    }
+
+    pub fn set_name_index(&mut self, name_index: u16) {
+        self.name_index = name_index;
+    }
+
+    pub fn descriptor_index(&self) -> u16 {
+        self.descriptor_index
+    }
+
+    pub fn set_descriptor_index(&mut self, descriptor_index: u16) {
+        self.descriptor_index = descriptor_index;
+    }
+
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.attribute_table
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<AttributeInfo> {
+        &mut self.attribute_table
+    }
 }
@@ -2,9 +2,14 @@ pub mod access_flags;
 
 pub mod class_factory;
 pub mod class_file_stream;
+pub mod class_file_writer;
+pub mod class_format_error;
 pub mod parsed_class;
 
+pub mod annotations;
 pub mod attribute_info;
 pub mod constant_member_ref;
 pub mod constant_pool;
 pub mod member_info;
+pub mod modified_utf8;
+pub mod stack_map_table;
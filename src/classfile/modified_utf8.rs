@@ -0,0 +1,130 @@
+// JVMS 4.4.7: CONSTANT_Utf8 strings use "modified UTF-8". Compared to standard UTF-8,
+// U+0000 is encoded as the two bytes 0xC0 0x80, and supplementary characters are
+// written as a UTF-16 surrogate pair with three bytes per surrogate.
+
+/// Decodes modified UTF-8 bytes into text. On malformed input returns the
+/// position of the first bad byte. Unpaired surrogates cannot live in a Rust
+/// `String`, they show as U+FFFD; `decode_utf16` keeps them.
+pub fn decode(bytes: &[u8]) -> Result<String, usize> {
+    let units = decode_utf16(bytes)?;
+    Ok(std::char::decode_utf16(units)
+        .map(|c| c.unwrap_or(std::char::REPLACEMENT_CHARACTER))
+        .collect())
+}
+
+/// Decodes modified UTF-8 bytes into the UTF-16 code units of a Java string,
+/// unpaired surrogates included.
+pub fn decode_utf16(bytes: &[u8]) -> Result<Vec<u16>, usize> {
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let x = bytes[i];
+        match x {
+            0x01..=0x7f => {
+                units.push(x as u16);
+                i += 1;
+            }
+            0xc0..=0xdf => {
+                let y = continuation(bytes, i + 1)?;
+                units.push((((x & 0x1f) as u16) << 6) | y);
+                i += 2;
+            }
+            0xe0..=0xef => {
+                let y = continuation(bytes, i + 1)?;
+                let z = continuation(bytes, i + 2)?;
+                units.push((((x & 0x0f) as u16) << 12) | (y << 6) | z);
+                i += 3;
+            }
+            // 0x00 and the four byte forms never appear in modified UTF-8
+            _ => return Err(i),
+        }
+    }
+    Ok(units)
+}
+
+fn continuation(bytes: &[u8], i: usize) -> Result<u16, usize> {
+    match bytes.get(i) {
+        Some(b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+        _ => Err(i),
+    }
+}
+
+/// Encodes a string the way `DataOutput.writeUTF` and javac do.
+pub fn encode(text: &str) -> Vec<u8> {
+    encode_utf16(text.encode_utf16())
+}
+
+/// Encodes UTF-16 code units, unpaired surrogates taking three bytes like
+/// any other.
+pub fn encode_utf16<I: IntoIterator<Item = u16>>(units: I) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec![];
+    for unit in units {
+        match unit {
+            0x0001..=0x007f => bytes.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::modified_utf8::{decode, decode_utf16, encode, encode_utf16};
+
+    #[test]
+    fn should_decode_ascii() {
+        assert_eq!(
+            Ok(String::from("java/lang/Object")),
+            decode(b"java/lang/Object")
+        );
+    }
+
+    #[test]
+    fn should_decode_nul_as_two_bytes() {
+        assert_eq!(Ok(String::from("a\0b")), decode(&[0x61, 0xc0, 0x80, 0x62]));
+        assert_eq!(vec![0x61, 0xc0, 0x80, 0x62], encode("a\0b"));
+    }
+
+    #[test]
+    fn should_decode_surrogate_pairs() {
+        let bytes = vec![0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+        assert_eq!(Ok(String::from("\u{1F600}")), decode(&bytes));
+        assert_eq!(bytes, encode("\u{1F600}"));
+    }
+
+    #[test]
+    fn should_round_trip_bmp_characters() {
+        let text = "accent:é cjk:中";
+        assert_eq!(Ok(String::from(text)), decode(&encode(text)));
+        assert_eq!(text.as_bytes(), &encode(text)[..]);
+    }
+
+    #[test]
+    fn should_reject_raw_nul_and_four_byte_forms() {
+        assert_eq!(Err(1), decode(&[0x61, 0x00]));
+        assert_eq!(Err(0), decode("\u{1F600}".as_bytes()));
+    }
+
+    #[test]
+    fn should_reject_truncated_sequence() {
+        assert_eq!(Err(3), decode(&[0x61, 0xe4, 0xb8]));
+        assert_eq!(Err(1), decode(&[0xc3, 0x41]));
+    }
+
+    #[test]
+    fn should_keep_lone_surrogate_units() {
+        let bytes = [0x61, 0xed, 0xa0, 0xbd];
+        assert_eq!(Ok(vec![0x61, 0xd83d]), decode_utf16(&bytes));
+        assert_eq!(bytes.to_vec(), encode_utf16(vec![0x61, 0xd83d]));
+        // only the text shows it as a replacement character
+        assert_eq!(Ok(String::from("a\u{FFFD}")), decode(&bytes));
+    }
+}
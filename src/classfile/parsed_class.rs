@@ -1,19 +1,21 @@
-use byteorder::{BigEndian, ByteOrder};
-
-use crate::classfile::attribute_info::{read_attribute_info, read_attributes, AttributeInfo};
+use crate::classfile::access_flags::AccessFlags;
+use crate::classfile::attribute_info::{read_attributes, write_attributes, AttributeInfo};
 use crate::classfile::class_file_stream::ClassFileStream;
-use crate::classfile::constant_pool::{ConstantInfo, CpEntry};
+use crate::classfile::class_file_writer::ClassFileWriter;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::classfile::constant_pool::{ConstantPool, CpEntry};
 use crate::classfile::member_info::MemberInfo;
-use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::rtda::heap::instanced_klass::{InstanceKlass, Supertypes};
+use std::rc::{Rc, Weak};
 
 pub struct ParsedClass {
     major_version: Vec<u8>,
     minor_version: Vec<u8>,
 
-    constant_pool_count: u8,
-    constant_pool_entries: Vec<CpEntry>,
+    constant_pool_count: u16,
+    constant_pool: ConstantPool,
 
-    access_flags: Vec<u8>,
+    access_flags: AccessFlags,
     this_class_index: u16,
     super_class_index: u16,
 
@@ -28,6 +30,7 @@ pub struct ParsedClass {
 
     attr_count: u16,
     attributes: Vec<AttributeInfo>,
+    attribute_name_indices: Vec<u16>,
 }
 
 #[derive(Clone, Debug)]
@@ -54,18 +57,16 @@ fn to_u32(slice: &[u8]) -> u32 {
         .0
 }
 
-fn is_klass_magic(clz_read: Vec<u8>) -> bool {
-    clz_read[0] != 0xca || clz_read[1] != 0xfe || clz_read[2] != 0xba || clz_read[3] != 0xbe
-}
+const JAVA_CLASSFILE_MAGIC: u32 = 0xCAFE_BABE;
 
 impl ParsedClass {
-    pub fn new(stream: ClassFileStream) -> ParsedClass {
+    pub fn new(stream: ClassFileStream) -> Result<ParsedClass, ClassFormatError> {
         let mut file_parser = ParsedClass {
             major_version: vec![0; 2],
             minor_version: vec![0; 2],
             constant_pool_count: 0,
-            constant_pool_entries: vec![],
-            access_flags: vec![0; 2],
+            constant_pool: ConstantPool::new(),
+            access_flags: AccessFlags::default(),
             this_class_index: 0,
             super_class_index: 0,
             interface_count: 0,
@@ -76,87 +77,265 @@ impl ParsedClass {
             methods: vec![],
             attr_count: 0,
             attributes: vec![],
+            attribute_name_indices: vec![],
         };
-        file_parser.parse_stream(stream.clone());
+        file_parser.parse_stream(stream)?;
 
-        file_parser
+        Ok(file_parser)
     }
 
-    fn parse_stream(&mut self, mut stream: ClassFileStream) {
-        let magic = stream.get_u4();
-        if is_klass_magic(magic) {
-            panic!("Input file {} does not have correct magic number")
+    fn parse_stream(&mut self, mut stream: ClassFileStream) -> Result<(), ClassFormatError> {
+        let magic = stream.read_u32()?;
+        if magic != JAVA_CLASSFILE_MAGIC {
+            return Err(ClassFormatError::BadMagic { magic });
         }
 
-        self.minor_version = stream.get_u2();
-        self.major_version = stream.get_u2();
+        self.minor_version = stream.get_u2()?;
+        self.major_version = stream.get_u2()?;
 
-        self.constant_pool_count = BigEndian::read_u16(&stream.get_u2()) as u8;
-        self.constant_pool_entries =
-            self.parse_constant_pool(&mut stream, self.constant_pool_count as usize);
+        self.constant_pool_count = stream.read_u16()?;
+        self.constant_pool = ConstantPool::parse(&mut stream, self.constant_pool_count)?;
+
+        self.access_flags = AccessFlags::new(stream.read_u16()?);
+        let offset = stream.current;
+        self.this_class_index = stream.read_u16()?;
+        self.expect_class(offset, self.this_class_index)?;
+        self.super_class_index = stream.read_u16()?;
+        // only java/lang/Object has no super class
+        if self.super_class_index != 0 {
+            self.expect_class(offset + 2, self.super_class_index)?;
+        }
 
-        self.access_flags = stream.get_u2();
-        self.this_class_index = stream.read_u16();
-        self.super_class_index = stream.read_u16();
+        self.interface_count = stream.read_u16()?;
+        self.interfaces = self.parse_interfaces(&mut stream, self.interface_count as usize)?;
 
-        self.interface_count = stream.read_u16();
-        self.interfaces = self.parse_interfaces(&mut stream, self.interface_count as usize);
+        self.field_count = stream.read_u16()?;
+        self.fields = self.parse_fields(&mut stream, self.field_count as usize)?;
 
-        self.field_count = stream.read_u16();
-        self.fields = self.parse_fields(&mut stream, self.field_count as usize);
+        self.method_count = stream.read_u16()?;
+        self.methods = self.parse_fields(&mut stream, self.method_count as usize)?;
 
-        self.method_count = stream.read_u16();
-        self.methods = self.parse_fields(&mut stream, self.method_count as usize);
+        let (attributes, name_indices) = read_attributes(&mut stream, &self.constant_pool)?;
+        self.attributes = attributes;
+        self.attribute_name_indices = name_indices;
 
-        self.attributes = read_attributes(&mut stream, self.constant_pool_entries.clone());
+        if stream.remaining() > 0 {
+            return Err(ClassFormatError::ExtraBytes {
+                offset: stream.current,
+            });
+        }
+        Ok(())
     }
 
-    fn parse_fields(&mut self, stream: &mut ClassFileStream, size: usize) -> Vec<MemberInfo> {
-        let mut members = vec![];
-        for _i in 0..size {
-            let mut member = MemberInfo {
-                access_flags: stream.read_u16(),
-                name_index: stream.read_u16(),
-                descriptor_index: stream.read_u16(),
-                attribute_table: vec![],
-            };
-
-            let att_count = stream.read_u16();
-            for _j in 0..att_count as usize {
-                let attr = read_attribute_info(stream, self.constant_pool_entries.clone());
-                member.attribute_table.push(attr);
-            }
-            members.push(member);
+    pub fn minor_version(&self) -> u16 {
+        u16::from_be_bytes([self.minor_version[0], self.minor_version[1]])
+    }
+
+    pub fn major_version(&self) -> u16 {
+        u16::from_be_bytes([self.major_version[0], self.major_version[1]])
+    }
+
+    pub fn constant_pool(&self) -> &ConstantPool {
+        &self.constant_pool
+    }
+
+    /// Entries can be replaced or appended; `to_bytes` writes the pool as it
+    /// is, so indices used by members and attributes must stay valid.
+    pub fn constant_pool_mut(&mut self) -> &mut ConstantPool {
+        &mut self.constant_pool
+    }
+
+    pub fn access_flags(&self) -> AccessFlags {
+        self.access_flags
+    }
+
+    pub fn this_class_index(&self) -> u16 {
+        self.this_class_index
+    }
+
+    pub fn super_class_index(&self) -> u16 {
+        self.super_class_index
+    }
+
+    pub fn interfaces(&self) -> &[u16] {
+        &self.interfaces
+    }
+
+    /// The binary name of the class, such as `java/lang/String`.
+    pub fn class_name(&self) -> &str {
+        self.constant_pool
+            .get_class_name(self.this_class_index)
+            .unwrap_or("")
+    }
+
+    /// The binary name of the superclass, `None` for `java/lang/Object`.
+    pub fn super_class_name(&self) -> Option<&str> {
+        if self.super_class_index == 0 {
+            return None;
         }
-        members
+        self.constant_pool.get_class_name(self.super_class_index)
     }
 
-    fn parse_interfaces(&mut self, stream: &mut ClassFileStream, size: usize) -> Vec<u16> {
-        let mut results: Vec<u16> = vec![];
-        for _i in 1..size {
-            results.push(stream.read_u16())
+    pub fn interface_names(&self) -> Vec<&str> {
+        self.interfaces
+            .iter()
+            .map(|&index| self.constant_pool.get_class_name(index).unwrap_or(""))
+            .collect()
+    }
+
+    pub fn fields(&self) -> &[MemberInfo] {
+        &self.fields
+    }
+
+    pub fn fields_mut(&mut self) -> &mut Vec<MemberInfo> {
+        &mut self.fields
+    }
+
+    pub fn methods(&self) -> &[MemberInfo] {
+        &self.methods
+    }
+
+    pub fn methods_mut(&mut self) -> &mut Vec<MemberInfo> {
+        &mut self.methods
+    }
+
+    pub fn attributes(&self) -> &[AttributeInfo] {
+        &self.attributes
+    }
+
+    pub fn attributes_mut(&mut self) -> &mut Vec<AttributeInfo> {
+        &mut self.attributes
+    }
+
+    /// Serializes the class back into the class file format. Unmodified input
+    /// comes out byte for byte as it was read. Names of attributes that were
+    /// added after parsing are appended to the constant pool. Fails when a
+    /// Utf8 constant is too long to be written or the pool has no room left
+    /// for a new attribute name.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ClassFormatError> {
+        let mut pool = self.constant_pool.clone();
+        let mut body = ClassFileWriter::new();
+        body.write_u16(self.access_flags.bits());
+        body.write_u16(self.this_class_index);
+        body.write_u16(self.super_class_index);
+        body.write_u16_table(&self.interfaces);
+        Self::write_members(&self.fields, &mut pool, &mut body)?;
+        Self::write_members(&self.methods, &mut pool, &mut body)?;
+        write_attributes(
+            &self.attributes,
+            &self.attribute_name_indices,
+            &mut pool,
+            &mut body,
+        )?;
+
+        // the pool comes first but attribute names may still be added above
+        let mut writer = ClassFileWriter::new();
+        writer.write_u32(JAVA_CLASSFILE_MAGIC);
+        writer.write_bytes(&self.minor_version);
+        writer.write_bytes(&self.major_version);
+        pool.write(&mut writer)?;
+        writer.write_bytes(&body.into_bytes());
+        Ok(writer.into_bytes())
+    }
+
+    fn write_members(
+        members: &[MemberInfo],
+        pool: &mut ConstantPool,
+        writer: &mut ClassFileWriter,
+    ) -> Result<(), ClassFormatError> {
+        writer.write_u16(members.len() as u16);
+        for member in members {
+            writer.write_u16(member.access_flags.bits());
+            writer.write_u16(member.name_index);
+            writer.write_u16(member.descriptor_index);
+            write_attributes(
+                &member.attribute_table,
+                &member.attribute_name_indices,
+                pool,
+                writer,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn expect_class(&self, offset: usize, index: u16) -> Result<(), ClassFormatError> {
+        match self.constant_pool.get(index) {
+            Some(CpEntry::Class { .. }) => Ok(()),
+            _ => Err(ClassFormatError::BadConstantIndex {
+                offset,
+                index,
+                expected: "Class",
+            }),
         }
-        results
     }
 
-    fn parse_constant_pool(&mut self, stream: &mut ClassFileStream, size: usize) -> Vec<CpEntry> {
-        let mut entries: Vec<CpEntry> = vec![];
-        entries.push(CpEntry::Empty {});
-        // The constant_pool table is indexed from 1
-        for _i in 1..size {
-            entries.push(ConstantInfo::from(stream));
+    fn expect_utf8(&self, offset: usize, index: u16) -> Result<(), ClassFormatError> {
+        match self.constant_pool.get_utf8(index) {
+            Some(_) => Ok(()),
+            _ => Err(ClassFormatError::BadConstantIndex {
+                offset,
+                index,
+                expected: "Utf8",
+            }),
         }
-        entries
     }
 
-    pub fn create_instance_klass(&mut self) -> InstanceKlass {
-        let mut klass = InstanceKlass::new();
-        self.fill_instance_klass(&mut klass);
-        klass
+    fn parse_fields(
+        &mut self,
+        stream: &mut ClassFileStream,
+        size: usize,
+    ) -> Result<Vec<MemberInfo>, ClassFormatError> {
+        let mut members = vec![];
+        for _i in 0..size {
+            let access_flags = AccessFlags::new(stream.read_u16()?);
+            let offset = stream.current;
+            let name_index = stream.read_u16()?;
+            let descriptor_index = stream.read_u16()?;
+            self.expect_utf8(offset, name_index)?;
+            self.expect_utf8(offset + 2, descriptor_index)?;
+
+            let (attribute_table, attribute_name_indices) =
+                read_attributes(stream, &self.constant_pool)?;
+            members.push(MemberInfo {
+                access_flags,
+                name_index,
+                descriptor_index,
+                attribute_table,
+                attribute_name_indices,
+            });
+        }
+        Ok(members)
+    }
+
+    fn parse_interfaces(
+        &mut self,
+        stream: &mut ClassFileStream,
+        size: usize,
+    ) -> Result<Vec<u16>, ClassFormatError> {
+        let mut results: Vec<u16> = vec![];
+        for _i in 0..size {
+            let offset = stream.current;
+            let index = stream.read_u16()?;
+            self.expect_class(offset, index)?;
+            results.push(index)
+        }
+        Ok(results)
+    }
+
+    /// The class as the VM shares it, with its methods pointing back at it.
+    pub fn create_instance_klass(&mut self, supertypes: Supertypes) -> Rc<InstanceKlass> {
+        Rc::new_cyclic(|this| {
+            let mut klass = InstanceKlass::new();
+            self.fill_instance_klass(&mut klass, this, supertypes);
+            klass
+        })
     }
 
     pub fn get_constant_info(&self, cp_index: u16) -> CpEntry {
-        self.constant_pool_entries[cp_index as usize].clone()
+        match self.constant_pool.get(cp_index) {
+            Some(entry) => entry.clone(),
+            None => CpEntry::Empty {},
+        }
     }
 
     pub fn get_utf8(&self, cp_index: u16) -> String {
@@ -164,7 +343,7 @@ impl ParsedClass {
             return String::from("");
         }
         match self.get_constant_info(cp_index) {
-            CpEntry::Utf8 { val } => val,
+            CpEntry::Utf8 { val, .. } => val,
             _ => String::from(""),
         }
     }
@@ -186,14 +365,21 @@ impl ParsedClass {
         and_type
     }
 
-    fn fill_instance_klass(&mut self, klass: &mut InstanceKlass) {
-        klass.set_origin_pool_entries(self.constant_pool_entries.clone());
+    fn fill_instance_klass(
+        &mut self,
+        klass: &mut InstanceKlass,
+        this: &Weak<InstanceKlass>,
+        supertypes: Supertypes,
+    ) {
+        klass.set_origin_pool_entries(self.constant_pool.clone());
         klass.set_minor_version(self.minor_version.clone());
         klass.set_major_version(self.major_version.clone());
+        klass.access_flags = self.access_flags;
 
         klass.fill_class_name(self.this_class_index);
         klass.fill_super_name(self.super_class_index);
         klass.fill_interfaces(self.interfaces.clone());
+        klass.fill_supertypes(supertypes);
         klass.fill_fields(self.fields.clone());
         klass.fill_attributes(self.attributes.clone());
 
@@ -201,23 +387,255 @@ impl ParsedClass {
         klass.fill_pool(self);
 
         // latest
-        klass.fill_methods(self.methods.clone());
+        klass.fill_methods(self.methods.clone(), this);
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::classfile::access_flags::AccessFlags;
+    use crate::classfile::attribute_info::AttributeInfo;
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::class_format_error::ClassFormatError;
+    use crate::classfile::constant_pool::CpEntry;
+    use crate::classfile::member_info::MemberInfo;
     use crate::classfile::parsed_class::ParsedClass;
-    use crate::classpath::class_file_entry::ClassFileEntry;
+    use crate::rtda::heap::instanced_klass::Supertypes;
 
     #[test]
     fn should_eq_count_entries_length() {
-        let entry = ClassFileEntry::new();
-        let stream = entry.open_stream(String::from("testdata/java8/HelloWorld.Class"));
-        let parser = ParsedClass::new(stream);
+        let stream = ClassFileStream::new(hello_world_bytes());
+        let parser = ParsedClass::new(stream).unwrap();
+        assert_eq!(parser.constant_pool_count, parser.constant_pool.count());
+    }
+
+    #[test]
+    fn should_parse_full_constant_pool() {
+        let bytes = std::fs::read("testdata/java8/ConstantSample.class").unwrap();
+        let stream = ClassFileStream::new(bytes);
+        let parser = ParsedClass::new(stream).unwrap();
+        assert_eq!(parser.constant_pool_count, parser.constant_pool.count());
+
+        match parser.get_constant_info(30) {
+            CpEntry::Long { val } => assert_eq!(1_234_567_890_123, val),
+            _ => panic!("expected long"),
+        }
+        match parser.get_constant_info(31) {
+            CpEntry::Empty {} => {}
+            _ => panic!("expected the unusable slot after a long"),
+        }
+        match parser.get_constant_info(24) {
+            CpEntry::InvokeDynamic { bsm_idx, nt_idx } => assert_eq!((0, 25), (bsm_idx, nt_idx)),
+            _ => panic!("expected invoke dynamic"),
+        }
+        match parser.get_constant_info(12) {
+            CpEntry::InterfaceMethodRef(member_ref) => assert_eq!(13, member_ref.class_index),
+            _ => panic!("expected interface method ref"),
+        }
+        assert_eq!("ConstantSample", parser.get_utf8(29));
+    }
+
+    fn hello_world_bytes() -> Vec<u8> {
+        std::fs::read("testdata/java8/HelloWorld.class").unwrap()
+    }
+
+    #[test]
+    fn should_reject_bad_magic() {
+        let mut bytes = hello_world_bytes();
+        bytes[0] = 0xde;
+        let result = ParsedClass::new(ClassFileStream::new(bytes));
+        assert_eq!(
+            Some(ClassFormatError::BadMagic { magic: 0xdefe_babe }),
+            result.err()
+        );
+    }
+
+    #[test]
+    fn should_report_pool_index_of_truncated_entry() {
+        let mut bytes = hello_world_bytes();
+        // cut inside the Utf8 entry #7 "<init>"
+        bytes.truncate(0x27);
+        match ParsedClass::new(ClassFileStream::new(bytes)) {
+            Err(ClassFormatError::InConstantPool { index, cause }) => {
+                assert_eq!(7, index);
+                assert_eq!(
+                    ClassFormatError::Truncated {
+                        offset: 0x25,
+                        needed: 6
+                    },
+                    *cause
+                );
+            }
+            _ => panic!("expected a truncated constant pool entry"),
+        }
+    }
+
+    #[test]
+    fn should_report_attribute_of_truncated_code() {
+        let mut bytes = hello_world_bytes();
+        // cut inside the bytecode of <init>
+        bytes.truncate(0x15a);
+        let err = ParsedClass::new(ClassFileStream::new(bytes)).err().unwrap();
+        match &err {
+            ClassFormatError::InAttribute { name, .. } => assert_eq!("Code", name),
+            _ => panic!("expected an error inside the Code attribute"),
+        }
+        assert_eq!(Some(0x158), err.offset());
+    }
+
+    #[test]
+    fn should_reject_trailing_bytes() {
+        let mut bytes = hello_world_bytes();
+        let len = bytes.len();
+        bytes.push(0);
+        let result = ParsedClass::new(ClassFileStream::new(bytes));
+        assert_eq!(
+            Some(ClassFormatError::ExtraBytes { offset: len }),
+            result.err()
+        );
+    }
+
+    fn push_utf8(bytes: &mut Vec<u8>, text: &str) {
+        bytes.push(1);
+        bytes.extend_from_slice(&(text.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(text.as_bytes());
+    }
+
+    /// a class whose constant pool uses every index up to 65534
+    fn large_class_bytes() -> Vec<u8> {
+        let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0, 0, 0, 52, 0xff, 0xff];
+        push_utf8(&mut bytes, "Big");
+        bytes.extend_from_slice(&[7, 0, 1]);
+        push_utf8(&mut bytes, "java/lang/Object");
+        bytes.extend_from_slice(&[7, 0, 3]);
+        // #5 and #6
+        bytes.extend_from_slice(&[5, 0, 0, 0, 0, 0, 0, 0, 42]);
+        for i in 7..65532 {
+            push_utf8(&mut bytes, &format!("c{}", i));
+        }
+        // #65532 and #65533
+        bytes.push(6);
+        bytes.extend_from_slice(&1.5f64.to_bits().to_be_bytes());
+        push_utf8(&mut bytes, "last");
+
+        // access flags, this, super, interfaces, fields, methods, attributes
+        bytes.extend_from_slice(&[0, 0x21, 0, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn should_parse_u16_sized_constant_pool() {
+        let parser = ParsedClass::new(ClassFileStream::new(large_class_bytes())).unwrap();
+        assert_eq!(65535, parser.constant_pool_count);
+        assert_eq!(65535, parser.constant_pool.count());
+
+        assert_eq!("c255", parser.get_utf8(255));
+        assert_eq!("c256", parser.get_utf8(256));
+        assert_eq!("c65531", parser.get_utf8(65531));
+        match parser.get_constant_info(65532) {
+            CpEntry::Double { val } => assert_eq!(1.5, val),
+            _ => panic!("expected double"),
+        }
+        assert!(parser.constant_pool.get(65533).is_none());
+        assert_eq!("last", parser.get_utf8(65534));
+        assert!(parser.constant_pool.get(65535).is_none());
+    }
+
+    #[test]
+    fn should_create_klass_from_large_pool() {
+        let mut parser = ParsedClass::new(ClassFileStream::new(large_class_bytes())).unwrap();
+        let klass = parser.create_instance_klass(Supertypes::default());
+        assert_eq!("Big", klass.klass_name);
+        assert_eq!("java/lang/Object", klass.super_klass_name);
+        assert_eq!(65535, klass.constant_pool.len());
+    }
+
+    fn class_files(dir: &str, found: &mut Vec<std::path::PathBuf>) {
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                class_files(path.to_str().unwrap(), found);
+            } else if path.extension().map_or(false, |ext| ext == "class") {
+                found.push(path);
+            }
+        }
+    }
+
+    #[test]
+    fn should_write_identical_bytes_for_every_fixture() {
+        let mut paths = vec![];
+        class_files("testdata", &mut paths);
+        assert!(paths.len() > 10);
+        for path in paths {
+            let bytes = std::fs::read(&path).unwrap();
+            let parser = ParsedClass::new(ClassFileStream::new(bytes.clone())).unwrap();
+            assert!(bytes == parser.to_bytes().unwrap(), "{:?} changed", path);
+        }
+    }
+
+    #[test]
+    fn should_write_large_constant_pool() {
+        let bytes = large_class_bytes();
+        let parser = ParsedClass::new(ClassFileStream::new(bytes.clone())).unwrap();
+        assert!(bytes == parser.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn should_add_name_of_new_attribute() {
+        let mut parser = ParsedClass::new(ClassFileStream::new(hello_world_bytes())).unwrap();
+        let count = parser.constant_pool_count;
+        parser.attributes.push(AttributeInfo::Synthetic());
+
+        let reparsed = ParsedClass::new(ClassFileStream::new(parser.to_bytes().unwrap())).unwrap();
+        assert_eq!(count + 1, reparsed.constant_pool_count);
+        assert_eq!("Synthetic", reparsed.get_utf8(count));
+        let names: Vec<&str> = reparsed.attributes.iter().map(|a| a.name()).collect();
+        assert_eq!(vec!["SourceFile", "Synthetic"], names);
+    }
+
+    #[test]
+    fn should_keep_attribute_name_index_of_duplicate_utf8() {
+        let mut parser = ParsedClass::new(ClassFileStream::new(hello_world_bytes())).unwrap();
+        let (duplicate, _) = parser
+            .constant_pool
+            .push(CpEntry::utf8("SourceFile"))
+            .unwrap();
+        parser.attribute_name_indices[0] = duplicate;
+        let bytes = parser.to_bytes().unwrap();
+
+        let reparsed = ParsedClass::new(ClassFileStream::new(bytes.clone())).unwrap();
+        assert_eq!(vec![duplicate], reparsed.attribute_name_indices);
+        assert!(bytes == reparsed.to_bytes().unwrap());
+    }
+
+    #[test]
+    fn should_write_members_added_after_parsing() {
+        let mut parser = ParsedClass::new(ClassFileStream::new(hello_world_bytes())).unwrap();
+        let pool = parser.constant_pool_mut();
+        let name = pool.utf8_index("count").unwrap();
+        let descriptor = pool.utf8_index("I").unwrap();
+        let mut field = MemberInfo::new(AccessFlags::new(0x0002), name, descriptor);
+        field.attributes_mut().push(AttributeInfo::Synthetic());
+        parser.fields_mut().push(field);
+        parser.methods_mut()[0].set_access_flags(AccessFlags::new(0x0001 | 0x1000));
+        parser.attributes_mut().clear();
+
+        let reparsed = ParsedClass::new(ClassFileStream::new(parser.to_bytes().unwrap())).unwrap();
+        let field = &reparsed.fields()[0];
+        assert_eq!("count", reparsed.get_utf8(field.name_index()));
+        assert_eq!("I", reparsed.get_utf8(field.descriptor_index()));
+        assert_eq!("Synthetic", field.attributes()[0].name());
+        assert_eq!(0x1001, reparsed.methods()[0].access_flags().bits());
+        assert!(reparsed.attributes().is_empty());
+    }
+
+    #[test]
+    fn should_fail_to_add_attribute_to_full_pool() {
+        let mut parser = ParsedClass::new(ClassFileStream::new(large_class_bytes())).unwrap();
+        parser.attributes.push(AttributeInfo::Synthetic());
         assert_eq!(
-            parser.constant_pool_count,
-            parser.constant_pool_entries.len() as u8
+            Some(ClassFormatError::ConstantPoolSize { count: 65535 }),
+            parser.to_bytes().err()
         );
     }
 }
@@ -0,0 +1,350 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_file_writer::ClassFileWriter;
+use crate::classfile::class_format_error::ClassFormatError;
+
+pub const ITEM_TOP: u8 = 0;
+pub const ITEM_INTEGER: u8 = 1;
+pub const ITEM_FLOAT: u8 = 2;
+pub const ITEM_DOUBLE: u8 = 3;
+pub const ITEM_LONG: u8 = 4;
+pub const ITEM_NULL: u8 = 5;
+pub const ITEM_UNINITIALIZED_THIS: u8 = 6;
+pub const ITEM_OBJECT: u8 = 7;
+pub const ITEM_UNINITIALIZED: u8 = 8;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum VerificationTypeInfo {
+    Top,
+    Integer,
+    Float,
+    Double,
+    Long,
+    Null,
+    UninitializedThis,
+    Object { cpool_index: u16 },
+    Uninitialized { offset: u16 },
+}
+
+/// Frames keep their `frame_type` byte where the encoding allows several,
+/// so that the table can be written back unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StackMapFrame {
+    // 0-63, offset_delta is the frame type
+    Same {
+        frame_type: u8,
+    },
+    // 64-127, offset_delta is frame_type - 64
+    SameLocals1StackItem {
+        frame_type: u8,
+        stack: VerificationTypeInfo,
+    },
+    // 247
+    SameLocals1StackItemExtended {
+        offset_delta: u16,
+        stack: VerificationTypeInfo,
+    },
+    // 248-250, drops 251 - frame_type locals
+    Chop {
+        frame_type: u8,
+        offset_delta: u16,
+    },
+    // 251
+    SameExtended {
+        offset_delta: u16,
+    },
+    // 252-254, adds frame_type - 251 locals
+    Append {
+        frame_type: u8,
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+    },
+    // 255
+    Full {
+        offset_delta: u16,
+        locals: Vec<VerificationTypeInfo>,
+        stack: Vec<VerificationTypeInfo>,
+    },
+}
+
+impl StackMapFrame {
+    pub fn offset_delta(&self) -> u16 {
+        match self {
+            StackMapFrame::Same { frame_type } => *frame_type as u16,
+            StackMapFrame::SameLocals1StackItem { frame_type, .. } => *frame_type as u16 - 64,
+            StackMapFrame::SameLocals1StackItemExtended { offset_delta, .. }
+            | StackMapFrame::Chop { offset_delta, .. }
+            | StackMapFrame::SameExtended { offset_delta }
+            | StackMapFrame::Append { offset_delta, .. }
+            | StackMapFrame::Full { offset_delta, .. } => *offset_delta,
+        }
+    }
+}
+
+pub fn read_stack_map_frames(
+    stream: &mut ClassFileStream,
+) -> Result<Vec<StackMapFrame>, ClassFormatError> {
+    let number_of_entries = stream.read_u16()?;
+    let mut frames = Vec::with_capacity(number_of_entries as usize);
+    for _i in 0..number_of_entries {
+        frames.push(read_stack_map_frame(stream)?);
+    }
+    Ok(frames)
+}
+
+fn read_stack_map_frame(stream: &mut ClassFileStream) -> Result<StackMapFrame, ClassFormatError> {
+    let offset = stream.current;
+    let frame_type = stream.read_u8()?;
+    let frame = match frame_type {
+        0..=63 => StackMapFrame::Same { frame_type },
+        64..=127 => StackMapFrame::SameLocals1StackItem {
+            frame_type,
+            stack: read_verification_type(stream)?,
+        },
+        247 => StackMapFrame::SameLocals1StackItemExtended {
+            offset_delta: stream.read_u16()?,
+            stack: read_verification_type(stream)?,
+        },
+        248..=250 => StackMapFrame::Chop {
+            frame_type,
+            offset_delta: stream.read_u16()?,
+        },
+        251 => StackMapFrame::SameExtended {
+            offset_delta: stream.read_u16()?,
+        },
+        252..=254 => {
+            let offset_delta = stream.read_u16()?;
+            let locals = read_verification_types(stream, (frame_type - 251) as u16)?;
+            StackMapFrame::Append {
+                frame_type,
+                offset_delta,
+                locals,
+            }
+        }
+        255 => {
+            let offset_delta = stream.read_u16()?;
+            let number_of_locals = stream.read_u16()?;
+            let locals = read_verification_types(stream, number_of_locals)?;
+            let number_of_stack_items = stream.read_u16()?;
+            let stack = read_verification_types(stream, number_of_stack_items)?;
+            StackMapFrame::Full {
+                offset_delta,
+                locals,
+                stack,
+            }
+        }
+        // 128-246 are reserved
+        _ => {
+            return Err(ClassFormatError::UnknownTag {
+                offset,
+                tag: frame_type,
+                kind: "stack map frame",
+            })
+        }
+    };
+    Ok(frame)
+}
+
+fn read_verification_types(
+    stream: &mut ClassFileStream,
+    count: u16,
+) -> Result<Vec<VerificationTypeInfo>, ClassFormatError> {
+    let mut types = Vec::with_capacity(count as usize);
+    for _i in 0..count {
+        types.push(read_verification_type(stream)?);
+    }
+    Ok(types)
+}
+
+fn read_verification_type(
+    stream: &mut ClassFileStream,
+) -> Result<VerificationTypeInfo, ClassFormatError> {
+    let offset = stream.current;
+    let tag = stream.read_u8()?;
+    let info = match tag {
+        ITEM_TOP => VerificationTypeInfo::Top,
+        ITEM_INTEGER => VerificationTypeInfo::Integer,
+        ITEM_FLOAT => VerificationTypeInfo::Float,
+        ITEM_DOUBLE => VerificationTypeInfo::Double,
+        ITEM_LONG => VerificationTypeInfo::Long,
+        ITEM_NULL => VerificationTypeInfo::Null,
+        ITEM_UNINITIALIZED_THIS => VerificationTypeInfo::UninitializedThis,
+        ITEM_OBJECT => VerificationTypeInfo::Object {
+            cpool_index: stream.read_u16()?,
+        },
+        ITEM_UNINITIALIZED => VerificationTypeInfo::Uninitialized {
+            offset: stream.read_u16()?,
+        },
+        _ => {
+            return Err(ClassFormatError::UnknownTag {
+                offset,
+                tag,
+                kind: "verification type",
+            })
+        }
+    };
+    Ok(info)
+}
+
+pub fn write_stack_map_frames(frames: &[StackMapFrame], writer: &mut ClassFileWriter) {
+    writer.write_u16(frames.len() as u16);
+    for frame in frames {
+        write_stack_map_frame(frame, writer);
+    }
+}
+
+fn write_stack_map_frame(frame: &StackMapFrame, writer: &mut ClassFileWriter) {
+    match frame {
+        StackMapFrame::Same { frame_type } => writer.write_u8(*frame_type),
+        StackMapFrame::SameLocals1StackItem { frame_type, stack } => {
+            writer.write_u8(*frame_type);
+            write_verification_type(stack, writer);
+        }
+        StackMapFrame::SameLocals1StackItemExtended {
+            offset_delta,
+            stack,
+        } => {
+            writer.write_u8(247);
+            writer.write_u16(*offset_delta);
+            write_verification_type(stack, writer);
+        }
+        StackMapFrame::Chop {
+            frame_type,
+            offset_delta,
+        } => {
+            writer.write_u8(*frame_type);
+            writer.write_u16(*offset_delta);
+        }
+        StackMapFrame::SameExtended { offset_delta } => {
+            writer.write_u8(251);
+            writer.write_u16(*offset_delta);
+        }
+        StackMapFrame::Append {
+            frame_type,
+            offset_delta,
+            locals,
+        } => {
+            writer.write_u8(*frame_type);
+            writer.write_u16(*offset_delta);
+            for local in locals {
+                write_verification_type(local, writer);
+            }
+        }
+        StackMapFrame::Full {
+            offset_delta,
+            locals,
+            stack,
+        } => {
+            writer.write_u8(255);
+            writer.write_u16(*offset_delta);
+            writer.write_u16(locals.len() as u16);
+            for local in locals {
+                write_verification_type(local, writer);
+            }
+            writer.write_u16(stack.len() as u16);
+            for item in stack {
+                write_verification_type(item, writer);
+            }
+        }
+    }
+}
+
+fn write_verification_type(info: &VerificationTypeInfo, writer: &mut ClassFileWriter) {
+    match info {
+        VerificationTypeInfo::Top => writer.write_u8(ITEM_TOP),
+        VerificationTypeInfo::Integer => writer.write_u8(ITEM_INTEGER),
+        VerificationTypeInfo::Float => writer.write_u8(ITEM_FLOAT),
+        VerificationTypeInfo::Double => writer.write_u8(ITEM_DOUBLE),
+        VerificationTypeInfo::Long => writer.write_u8(ITEM_LONG),
+        VerificationTypeInfo::Null => writer.write_u8(ITEM_NULL),
+        VerificationTypeInfo::UninitializedThis => writer.write_u8(ITEM_UNINITIALIZED_THIS),
+        VerificationTypeInfo::Object { cpool_index } => {
+            writer.write_u8(ITEM_OBJECT);
+            writer.write_u16(*cpool_index);
+        }
+        VerificationTypeInfo::Uninitialized { offset } => {
+            writer.write_u8(ITEM_UNINITIALIZED);
+            writer.write_u16(*offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::class_file_writer::ClassFileWriter;
+    use crate::classfile::class_format_error::ClassFormatError;
+    use crate::classfile::stack_map_table::{
+        read_stack_map_frames, write_stack_map_frames, StackMapFrame, VerificationTypeInfo,
+    };
+
+    fn every_frame_kind() -> Vec<u8> {
+        vec![
+            0, 7, // seven frames
+            3, // same
+            65, 1, // same_locals_1_stack_item, int
+            247, 0, 9, 7, 0, 2, // extended, Object #2
+            249, 0, 4, // chop 2
+            251, 1, 0, // same extended
+            253, 0, 5, 4, 8, 0, 3, // append long, uninitialized(3)
+            255, 0, 6, 0, 1, 6, 0, 1, 5, // full
+        ]
+    }
+
+    #[test]
+    fn should_read_every_frame_kind() {
+        let bytes = every_frame_kind();
+        let frames = read_stack_map_frames(&mut ClassFileStream::new(bytes)).unwrap();
+        assert_eq!(7, frames.len());
+        assert_eq!(StackMapFrame::Same { frame_type: 3 }, frames[0]);
+        assert_eq!(1, frames[1].offset_delta());
+        assert_eq!(
+            StackMapFrame::SameLocals1StackItemExtended {
+                offset_delta: 9,
+                stack: VerificationTypeInfo::Object { cpool_index: 2 }
+            },
+            frames[2]
+        );
+        assert_eq!(256, frames[4].offset_delta());
+        assert_eq!(
+            StackMapFrame::Append {
+                frame_type: 253,
+                offset_delta: 5,
+                locals: vec![
+                    VerificationTypeInfo::Long,
+                    VerificationTypeInfo::Uninitialized { offset: 3 }
+                ]
+            },
+            frames[5]
+        );
+        assert_eq!(
+            StackMapFrame::Full {
+                offset_delta: 6,
+                locals: vec![VerificationTypeInfo::UninitializedThis],
+                stack: vec![VerificationTypeInfo::Null]
+            },
+            frames[6]
+        );
+    }
+
+    #[test]
+    fn should_write_every_frame_kind() {
+        let bytes = every_frame_kind();
+        let frames = read_stack_map_frames(&mut ClassFileStream::new(bytes.clone())).unwrap();
+        let mut writer = ClassFileWriter::new();
+        write_stack_map_frames(&frames, &mut writer);
+        assert_eq!(bytes, writer.into_bytes());
+    }
+
+    #[test]
+    fn should_reject_reserved_frame_type() {
+        let mut stream = ClassFileStream::new(vec![0, 1, 200]);
+        assert_eq!(
+            Err(ClassFormatError::UnknownTag {
+                offset: 2,
+                tag: 200,
+                kind: "stack map frame"
+            }),
+            read_stack_map_frames(&mut stream)
+        );
+    }
+}
@@ -1,93 +1,695 @@
 use crate::classpath::directory_entry::DirectoryEntry;
+use crate::classpath::java_home::{JavaHome, JavaHomeError, RuntimeImage};
+use crate::classpath::jimage_entry::JImageEntry;
+use crate::classpath::jmod_entry::JmodEntry;
+use crate::classpath::manifest::Manifest;
 use crate::classpath::zip_entry::ZipEntry;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fmt;
 use std::fs;
-use std::fs::DirEntry;
-use std::path::{Path, PathBuf};
+use std::io;
+use std::path::{Path, PathBuf, MAIN_SEPARATOR};
+
+#[cfg(windows)]
+pub const PATH_SEPARATOR: char = ';';
+#[cfg(not(windows))]
+pub const PATH_SEPARATOR: char = ':';
+
+/// The Java release this VM implements, e.g. for picking the versions of
+/// multi-release jars.
+pub const FEATURE_VERSION: u32 = 17;
 
 pub trait Entry {
-    // className: fully/qualified/ClassName.class
-    fn read_class(&self, class_name: String);
+    /// Reads `fully/qualified/ClassName.class`, returning its bytes and the
+    /// entry they came from, or `None` when this entry does not have it.
+    fn read_class(&self, class_name: &str) -> io::Result<Option<(Vec<u8>, &dyn Entry)>>;
+
+    /// Reads a resource such as `META-INF/services/java.sql.Driver` or
+    /// `app/messages.properties`, the name `ClassLoader.getResource` takes.
+    fn read_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// The packages this entry has classes in, such as `java/lang`, when it
+    /// can list them cheaply. `None` has the entry asked for every class.
+    fn packages(&self) -> io::Result<Option<Vec<String>>> {
+        Ok(None)
+    }
+
+    /// the directory or archive this entry reads from
+    fn path(&self) -> &Path;
+}
+
+/// Which part of the class path served a class, mirroring the bootstrap,
+/// extension and application class loaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    Boot,
+    Ext,
+    User,
+}
+
+/// A class or other resource read from the class path.
+pub struct FoundResource<'a> {
+    pub bytes: Vec<u8>,
+    pub entry: &'a dyn Entry,
+    pub tier: Tier,
+}
+
+/// A class is the resource named `java/lang/String.class`.
+pub type FoundClass<'a> = FoundResource<'a>;
+
+/// Which entries can have a class of a package, so that a lookup skips the
+/// jars that cannot. Entries are numbered in lookup order.
+#[derive(Debug, Default)]
+struct PackageIndex {
+    /// entries covered, the index is rebuilt when more are added
+    entry_count: usize,
+    packages: HashMap<String, Vec<usize>>,
+    /// entries that cannot list their packages, asked for every class
+    unlisted: Vec<usize>,
+}
+
+impl PackageIndex {
+    fn build<'a>(entries: impl Iterator<Item = &'a dyn Entry>) -> PackageIndex {
+        let mut index = PackageIndex::default();
+        for (position, entry) in entries.enumerate() {
+            index.entry_count += 1;
+            match entry.packages() {
+                Ok(Some(packages)) => {
+                    for package in packages {
+                        index.packages.entry(package).or_default().push(position);
+                    }
+                }
+                // a broken jar fails again when it is read
+                Ok(None) | Err(_) => index.unlisted.push(position),
+            }
+        }
+        index
+    }
+
+    /// The entries to ask for a class of `package`, in lookup order.
+    fn candidates(&self, package: &str) -> Vec<usize> {
+        let mut candidates = self.unlisted.clone();
+        if let Some(listed) = self.packages.get(package) {
+            candidates.extend(listed);
+            candidates.sort_unstable();
+        }
+        candidates
+    }
 }
 
 pub struct ClassPath {
-    pub runtime_path: Vec<Box<dyn Entry>>,
+    pub java_home: Option<JavaHome>,
+    pub boot_path: Vec<Box<dyn Entry>>,
+    pub ext_path: Vec<Box<dyn Entry>>,
+    pub user_path: Vec<Box<dyn Entry>>,
+    /// the release user jars serve multi-release classes for, set it before
+    /// adding them
+    pub release: u32,
+    package_index: RefCell<PackageIndex>,
 }
 
 impl ClassPath {
     pub fn new() -> ClassPath {
         ClassPath {
-            runtime_path: vec![],
+            java_home: None,
+            boot_path: vec![],
+            ext_path: vec![],
+            user_path: vec![],
+            release: FEATURE_VERSION,
+            package_index: RefCell::new(PackageIndex::default()),
         }
     }
 
-    pub fn parse(java_home: String, user_path: String) -> ClassPath {
+    /// Boot and extension entries come from `java_home`, or from the runtime
+    /// `JavaHome::find` discovers when it is `None`.
+    pub fn parse(java_home: Option<String>, user_path: String) -> Result<ClassPath, JavaHomeError> {
+        let java_home = JavaHome::find(java_home.as_deref())?;
+        let mut classpaths = ClassPath::with_java_home(java_home);
+
+        classpaths.parse_user_class_path(user_path);
+
+        Ok(classpaths)
+    }
+
+    pub fn with_java_home(java_home: JavaHome) -> ClassPath {
         let mut classpaths = ClassPath::new();
+        match &java_home.image {
+            RuntimeImage::RtJar(_) => {
+                classpaths.parse_boot_path(&java_home.path);
+                classpaths.parse_ext_path(&java_home.path);
+            }
+            RuntimeImage::Modules(modules) => {
+                let entry = JImageEntry::new(modules.clone());
+                classpaths.boot_path.push(Box::new(entry));
+            }
+            RuntimeImage::Jmods(jmods) => {
+                let entries = files_with_extension(jmods, "jmod").into_iter();
+                classpaths
+                    .boot_path
+                    .extend(entries.map(|jmod| Box::new(JmodEntry::new(jmod)) as Box<dyn Entry>));
+            }
+        }
+        classpaths.java_home = Some(java_home);
+        classpaths
+    }
 
-        classpaths.parse_boot_path(java_home.clone());
+    pub fn parse_boot_path(&mut self, java_home: &Path) {
+        let jre_path = java_home.join("lib");
+        self.boot_path.extend(spread_wildcard_entry(jre_path));
+    }
 
-        classpaths.parse_ext_path(java_home.clone());
+    pub fn parse_ext_path(&mut self, java_home: &Path) {
+        let jre_path = java_home.join("lib").join("ext");
+        self.ext_path.extend(spread_wildcard_entry(jre_path));
+    }
 
-        classpaths.parse_user_class_path(user_path.clone());
+    /// Reads `java/lang/String` from the first entry that has it, searching
+    /// the boot entries, then the extension entries, then the user entries.
+    /// Only entries that have the package of the class, or cannot tell, are
+    /// asked. An entry that cannot be read, such as a corrupt jar, does not
+    /// hide the ones after it: its error is returned only when none of them
+    /// has the class.
+    pub fn read_class(&self, class_name: &str) -> io::Result<Option<FoundClass<'_>>> {
+        let file_name = format!("{}.class", class_name);
+        let package = match class_name.rfind('/') {
+            Some(slash) => &class_name[..slash],
+            None => "",
+        };
+        let entries: Vec<(Tier, &dyn Entry)> = self.tiered_entries().collect();
+        let mut error = None;
+        for position in self.candidates(package, entries.len()) {
+            let (tier, entry) = entries[position];
+            match entry.read_class(&file_name) {
+                Ok(Some((bytes, entry))) => return Ok(Some(FoundClass { bytes, entry, tier })),
+                Ok(None) => {}
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        error.map_or(Ok(None), Err)
+    }
 
-        classpaths
+    /// Reads a resource from the first entry that has it, in the order
+    /// `read_class` searches. Resources need not sit in a package with
+    /// classes, so every entry is asked.
+    pub fn read_resource(&self, name: &str) -> io::Result<Option<FoundResource<'_>>> {
+        let mut error = None;
+        for (tier, entry) in self.tiered_entries() {
+            match entry.read_resource(name) {
+                Ok(Some(bytes)) => return Ok(Some(FoundResource { bytes, entry, tier })),
+                Ok(None) => {}
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        error.map_or(Ok(None), Err)
     }
 
-    pub fn parse_boot_path(&mut self, java_home: String) {
-        let jre_path = Path::new(&java_home).join("lib");
-        self.spread_wildcard_entry(jre_path);
+    /// Reads a resource from every entry that has it, in lookup order, as
+    /// `ClassLoader.getResources` enumerates `META-INF/services/*` files.
+    /// Like `read_resource`, entries that cannot be read are skipped and the
+    /// first error is returned only when no entry has the resource.
+    pub fn read_resources(&self, name: &str) -> io::Result<Vec<FoundResource<'_>>> {
+        let mut found = vec![];
+        let mut error = None;
+        for (tier, entry) in self.tiered_entries() {
+            match entry.read_resource(name) {
+                Ok(Some(bytes)) => found.push(FoundResource { bytes, entry, tier }),
+                Ok(None) => {}
+                Err(e) => {
+                    error.get_or_insert(e);
+                }
+            }
+        }
+        match error {
+            Some(e) if found.is_empty() => Err(e),
+            _ => Ok(found),
+        }
     }
 
-    pub fn parse_ext_path(&mut self, java_home: String) {
-        let jre_path = Path::new(&java_home).join("lib").join("ext");
-        self.spread_wildcard_entry(jre_path);
+    fn candidates(&self, package: &str, entry_count: usize) -> Vec<usize> {
+        let mut index = self.package_index.borrow_mut();
+        // entries are only ever added, a count that moved means new ones
+        if index.entry_count != entry_count {
+            *index = PackageIndex::build(self.entries());
+        }
+        index.candidates(package)
     }
 
-    pub fn spread_wildcard_entry(&mut self, path: PathBuf) {
-        let files = fs::read_dir(path).unwrap();
-        files
-            .filter_map(Result::ok)
-            .filter(|d| is_dir_jar(d))
-            .for_each(|f| {
-                let entry = ZipEntry::new(f.path());
-                self.runtime_path.push(Box::from(entry));
-            });
+    fn tiered_entries(&self) -> impl Iterator<Item = (Tier, &dyn Entry)> {
+        let boot = self
+            .boot_path
+            .iter()
+            .map(|entry| (Tier::Boot, entry.as_ref()));
+        let ext = self
+            .ext_path
+            .iter()
+            .map(|entry| (Tier::Ext, entry.as_ref()));
+        let user = self
+            .user_path
+            .iter()
+            .map(|entry| (Tier::User, entry.as_ref()));
+        boot.chain(ext).chain(user)
     }
 
+    /// All entries in lookup order.
+    pub fn entries(&self) -> impl Iterator<Item = &dyn Entry> {
+        self.boot_path
+            .iter()
+            .chain(self.ext_path.iter())
+            .chain(self.user_path.iter())
+            .map(|entry| entry.as_ref())
+    }
+
+    /// Adds a `java -cp` style path: jars, zips, jmods and directories
+    /// separated by `PATH_SEPARATOR`, with `dir/*` standing for every jar in
+    /// `dir`. Like `java`, entries that do not exist are skipped and jars
+    /// bring in what their manifest `Class-Path` names.
     pub fn parse_user_class_path(&mut self, path: String) {
-        let is_jar = path.ends_with(".jar");
-        let is_zip = path.ends_with(".zip");
+        for element in path.split(PATH_SEPARATOR) {
+            // an empty element is the current directory
+            let element = if element.is_empty() { "." } else { element };
+            match wildcard_directory(element) {
+                Some(directory) => {
+                    for jar in jars_in(directory) {
+                        self.add_user_entry(jar);
+                    }
+                }
+                None => self.add_user_entry(PathBuf::from(element)),
+            }
+        }
+    }
 
-        if is_jar || is_zip {
-            let entry = ZipEntry::new(Path::new(&path).to_path_buf());
-            self.runtime_path.push(Box::from(entry));
+    fn add_user_entry(&mut self, path: PathBuf) {
+        if !path.exists() || self.has_user_entry(&path) {
+            return;
+        }
+        if has_extension(&path, "jmod") {
+            self.user_path.push(Box::new(JmodEntry::new(path)));
+        } else if has_extension(&path, "jar") || has_extension(&path, "zip") {
+            let entry = ZipEntry::with_release(path.clone(), self.release);
+            self.user_path.push(Box::new(entry));
+            self.follow_manifest_class_path(&path);
         } else {
-            let dir_entry = DirectoryEntry::new(Path::new(&path).to_path_buf());
-            self.runtime_path.push(Box::from(dir_entry));
+            self.user_path.push(Box::new(DirectoryEntry::new(path)));
         }
     }
+
+    /// Also stops `Class-Path` cycles, the same file can be reached under
+    /// several names.
+    fn has_user_entry(&self, path: &Path) -> bool {
+        let path = fs::canonicalize(path).ok();
+        self.user_path
+            .iter()
+            .any(|entry| fs::canonicalize(entry.path()).ok() == path)
+    }
+
+    /// `Class-Path` holds space separated URLs relative to the jar. Only
+    /// local files are followed.
+    fn follow_manifest_class_path(&mut self, jar: &Path) {
+        let manifest = match Manifest::read_from_jar(jar) {
+            Ok(Some(manifest)) => manifest,
+            _ => return,
+        };
+        let base = jar.parent().unwrap_or_else(|| Path::new(""));
+        for url in manifest.class_path() {
+            let path = url.strip_prefix("file:").unwrap_or(url);
+            if path.contains(':') {
+                continue;
+            }
+            self.add_user_entry(base.join(path));
+        }
+    }
+}
+
+impl fmt::Debug for ClassPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.entries().map(|entry| entry.path()))
+            .finish()
+    }
 }
 
-fn is_dir_jar(d: &DirEntry) -> bool {
-    if let Some(e) = d.path().extension() {
-        e == "jar"
+/// `dir` of a `dir/*` element, `.` for a lone `*`.
+fn wildcard_directory(element: &str) -> Option<&str> {
+    let directory = element.strip_suffix('*')?;
+    if directory.is_empty() {
+        return Some(".");
+    }
+    if directory.ends_with('/') || directory.ends_with(MAIN_SEPARATOR) {
+        Some(directory)
     } else {
-        false
+        None
+    }
+}
+
+/// The `.jar` and `.JAR` files in `directory`, sorted by name.
+fn jars_in(directory: &str) -> Vec<PathBuf> {
+    let mut jars: Vec<PathBuf> = match fs::read_dir(directory) {
+        Ok(files) => files
+            .filter_map(Result::ok)
+            .map(|f| f.path())
+            .filter(|f| f.is_file() && has_extension(f, "jar"))
+            .collect(),
+        Err(_) => return vec![],
+    };
+    jars.sort();
+    jars
+}
+
+fn has_extension(path: &Path, extension: &str) -> bool {
+    match path.extension().and_then(OsStr::to_str) {
+        Some(e) => e.eq_ignore_ascii_case(extension),
+        None => false,
     }
 }
 
+/// Every jar directly inside `path`.
+fn spread_wildcard_entry(path: PathBuf) -> Vec<Box<dyn Entry>> {
+    files_with_extension(&path, "jar")
+        .into_iter()
+        .map(|jar| Box::new(ZipEntry::new(jar)) as Box<dyn Entry>)
+        .collect()
+}
+
+/// The files directly inside `path` ending in `.extension`, sorted by name.
+/// A directory that cannot be read has none, like a missing `lib/ext`.
+fn files_with_extension(path: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(files) => files
+            .filter_map(Result::ok)
+            .map(|f| f.path())
+            .filter(|f| f.extension() == Some(OsStr::new(extension)))
+            .collect(),
+        Err(_) => return vec![],
+    };
+    files.sort();
+    files
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::classpath::class_path::ClassPath;
+    use crate::classpath::class_path::{ClassPath, Entry, Tier, PATH_SEPARATOR};
+    use crate::classpath::directory_entry::DirectoryEntry;
+    use std::cell::Cell;
+    use std::io;
+    use std::path::{Path, PathBuf};
+    use std::rc::Rc;
 
     #[test]
     fn test_load_class() {
-        // todo: change java_home to get from shell
-        // "$(java -XshowSettings:properties -version 2>&1 > /dev/null | grep 'java.home' | awk '{print $3}')"
-        let java_home: String =
-            String::from("/Library/Java/JavaVirtualMachines/jdk1.8.0_202.jdk/Contents/Home/jre");
+        let java_home = Some(String::from("testdata/jre"));
         let user_path: String = String::from("testdata/java8");
-        let class_paths = ClassPath::parse(java_home, user_path);
+        let class_paths = ClassPath::parse(java_home, user_path).unwrap();
+
+        assert_eq!(3, class_paths.entries().count());
+
+        let found = class_paths.read_class("java/lang/Object").unwrap().unwrap();
+        assert_eq!(Tier::Boot, found.tier);
+        assert_eq!(Path::new("testdata/jre/lib/rt.jar"), found.entry.path());
+
+        let found = class_paths.read_class("hello/HelloWorld").unwrap().unwrap();
+        assert_eq!(Tier::Ext, found.tier);
+    }
+
+    #[test]
+    fn should_read_boot_classes_from_modules_image() {
+        let java_home = Some(String::from("testdata/jdk"));
+        let class_paths = ClassPath::parse(java_home, String::from("testdata/java8")).unwrap();
+        assert_eq!(2, class_paths.entries().count());
+
+        let found = class_paths.read_class("java/lang/Number").unwrap().unwrap();
+        assert_eq!(Tier::Boot, found.tier);
+        assert_eq!(Path::new("testdata/jdk/lib/modules"), found.entry.path());
+
+        assert!(ClassPath::parse(Some(String::from("missing")), String::new()).is_err());
+    }
+
+    #[test]
+    fn should_read_class_from_first_entry_that_has_it() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/java8/jar/hello.jar"));
+        class_path.parse_user_class_path(String::from("testdata/java8"));
+
+        let found = class_path.read_class("hello/HelloWorld").unwrap().unwrap();
+        assert_eq!(
+            Path::new("testdata/java8/jar/hello.jar"),
+            found.entry.path()
+        );
+
+        let found = class_path.read_class("HelloWorld").unwrap().unwrap();
+        assert_eq!(426, found.bytes.len());
+        assert_eq!(Path::new("testdata/java8"), found.entry.path());
+        assert_eq!(Tier::User, found.tier);
+
+        assert!(class_path
+            .read_class("java/lang/Missing")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn should_search_past_corrupt_jar() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/classpath/broken.jar"));
+        class_path.parse_user_class_path(String::from("testdata/java8/jar/hello.jar"));
+
+        let found = class_path.read_class("hello/HelloWorld").unwrap().unwrap();
+        assert_eq!(
+            Path::new("testdata/java8/jar/hello.jar"),
+            found.entry.path()
+        );
+        let found = class_path
+            .read_resource("META-INF/MANIFEST.MF")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Path::new("testdata/java8/jar/hello.jar"),
+            found.entry.path()
+        );
+
+        assert!(class_path.read_class("hello/Missing").is_err());
+        assert!(class_path.read_resource("hello/missing.txt").is_err());
+    }
+
+    #[test]
+    fn should_search_boot_then_ext_then_user_entries() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/java8"));
+        class_path
+            .ext_path
+            .push(Box::new(DirectoryEntry::new(PathBuf::from(
+                "testdata/java17",
+            ))));
+        class_path
+            .boot_path
+            .push(Box::new(DirectoryEntry::new(PathBuf::from(
+                "testdata/java8",
+            ))));
+
+        let found = class_path.read_class("HelloWorld").unwrap().unwrap();
+        assert_eq!(Tier::Boot, found.tier);
+
+        let found = class_path
+            .read_class("sample/AttributeSample")
+            .unwrap()
+            .unwrap();
+        assert_eq!(Tier::Ext, found.tier);
+        assert_eq!(Path::new("testdata/java17"), found.entry.path());
+    }
+
+    #[test]
+    fn should_read_boot_classes_from_jmods() {
+        let java_home = Some(String::from("testdata/jdk-jmods"));
+        let class_path = ClassPath::parse(java_home, String::from("testdata/java8")).unwrap();
+
+        let found = class_path.read_class("sample/Task").unwrap().unwrap();
+        assert_eq!(Tier::Boot, found.tier);
+        assert_eq!(
+            Path::new("testdata/jdk-jmods/jmods/sample.jmod"),
+            found.entry.path()
+        );
+    }
+
+    fn user_paths(class_path: &ClassPath) -> Vec<String> {
+        class_path
+            .user_path
+            .iter()
+            .map(|entry| entry.path().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn should_split_class_path_and_skip_missing_entries() {
+        let mut class_path = ClassPath::new();
+        let path = format!(
+            "testdata/java8{}missing.jar{}testdata/java8/jar/hello.jar{}testdata/java8",
+            PATH_SEPARATOR, PATH_SEPARATOR, PATH_SEPARATOR
+        );
+        class_path.parse_user_class_path(path);
+
+        assert_eq!(
+            vec!["testdata/java8", "testdata/java8/jar/hello.jar"],
+            user_paths(&class_path)
+        );
+    }
+
+    #[test]
+    fn should_follow_manifest_class_path() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/classpath/app.jar"));
+
+        assert_eq!(
+            vec![
+                "testdata/classpath/app.jar",
+                "testdata/classpath/lib/dep.JAR",
+                "testdata/classpath/lib/task.jar",
+                "testdata/classpath/classes/",
+            ],
+            user_paths(&class_path)
+        );
+        let found = class_path.read_class("sample/Task").unwrap().unwrap();
+        assert_eq!(
+            Path::new("testdata/classpath/lib/task.jar"),
+            found.entry.path()
+        );
+    }
+
+    #[test]
+    fn should_expand_wildcard_to_jars() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/classpath/lib/*"));
+
+        assert_eq!(
+            vec![
+                "testdata/classpath/lib/dep.JAR",
+                "testdata/classpath/lib/task.jar",
+                "testdata/classpath/lib/../app.jar",
+                "testdata/classpath/lib/../classes/",
+                "testdata/classpath/lib/hello.jar",
+            ],
+            user_paths(&class_path)
+        );
+        assert!(class_path.read_class("hello/HelloWorld").unwrap().is_some());
+    }
+
+    #[test]
+    fn should_create_user_jars_for_release() {
+        let mut class_path = ClassPath::new();
+        class_path.release = 11;
+        class_path.parse_user_class_path(String::from("testdata/java8/jar/multi-release.jar"));
+
+        let found = class_path.read_class("mr/Version").unwrap().unwrap();
+        let text = String::from_utf8_lossy(&found.bytes).to_string();
+        assert!(text.contains("release 11"));
+    }
+
+    #[test]
+    fn should_read_resources_in_lookup_order() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/classpath/app.jar"));
+
+        let found = class_path
+            .read_resource("sample/task.properties")
+            .unwrap()
+            .unwrap();
+        assert_eq!(b"name=task\n", &found.bytes[..]);
+        assert_eq!(Tier::User, found.tier);
+        assert!(class_path
+            .read_resource("sample/missing.properties")
+            .unwrap()
+            .is_none());
+
+        let services = class_path
+            .read_resources("META-INF/services/sample.Service")
+            .unwrap();
+        let sources: Vec<&Path> = services.iter().map(|found| found.entry.path()).collect();
+        assert_eq!(
+            vec![
+                Path::new("testdata/classpath/lib/task.jar"),
+                Path::new("testdata/classpath/classes/"),
+            ],
+            sources
+        );
+        assert_eq!(b"sample.TaskService\n", &services[0].bytes[..]);
+    }
+
+    #[test]
+    fn should_list_resources_past_corrupt_jar() {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from("testdata/classpath/broken.jar"));
+        class_path.parse_user_class_path(String::from("testdata/classpath/app.jar"));
+
+        let services = class_path
+            .read_resources("META-INF/services/sample.Service")
+            .unwrap();
+        let sources: Vec<&Path> = services.iter().map(|found| found.entry.path()).collect();
+        assert_eq!(
+            vec![
+                Path::new("testdata/classpath/lib/task.jar"),
+                Path::new("testdata/classpath/classes/"),
+            ],
+            sources
+        );
+        assert!(class_path
+            .read_resources("META-INF/services/missing.Service")
+            .is_err());
+    }
+
+    /// lists one package and counts the lookups it gets
+    struct CountingEntry {
+        path: PathBuf,
+        package: Option<&'static str>,
+        reads: Rc<Cell<usize>>,
+    }
+
+    impl Entry for CountingEntry {
+        fn read_class(&self, _: &str) -> io::Result<Option<(Vec<u8>, &dyn Entry)>> {
+            self.reads.set(self.reads.get() + 1);
+            Ok(None)
+        }
+
+        fn read_resource(&self, _: &str) -> io::Result<Option<Vec<u8>>> {
+            Ok(None)
+        }
+
+        fn packages(&self) -> io::Result<Option<Vec<String>>> {
+            Ok(self.package.map(|package| vec![String::from(package)]))
+        }
+
+        fn path(&self) -> &Path {
+            &self.path
+        }
+    }
+
+    #[test]
+    fn should_only_ask_entries_that_have_the_package() {
+        let mut class_path = ClassPath::new();
+        let mut counting = |path: &str, package: Option<&'static str>| {
+            let reads = Rc::new(Cell::new(0));
+            class_path.user_path.push(Box::new(CountingEntry {
+                path: PathBuf::from(path),
+                package,
+                reads: reads.clone(),
+            }));
+            reads
+        };
+        let other_reads = counting("other", Some("other"));
+        let unlisted_reads = counting("unlisted", None);
+        class_path.parse_user_class_path(String::from("testdata/classpath/lib/*"));
+
+        let found = class_path.read_class("sample/Task").unwrap().unwrap();
+        assert_eq!(
+            Path::new("testdata/classpath/lib/task.jar"),
+            found.entry.path()
+        );
+        assert!(class_path.read_class("other/Missing").unwrap().is_none());
 
-        assert_eq!(21, class_paths.runtime_path.len());
+        assert_eq!(1, other_reads.get());
+        assert_eq!(2, unlisted_reads.get());
     }
 }
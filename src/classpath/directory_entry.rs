@@ -1,5 +1,7 @@
 use crate::classpath::class_path::Entry;
-use std::path::PathBuf;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
 
 #[derive(Clone)]
 pub struct DirectoryEntry {
@@ -13,7 +15,59 @@ impl DirectoryEntry {
 }
 
 impl Entry for DirectoryEntry {
-    fn read_class(&self, _class_name: String) {
-        unimplemented!()
+    fn read_class(&self, class_name: &str) -> io::Result<Option<(Vec<u8>, &dyn Entry)>> {
+        let bytes = self.read_resource(class_name)?;
+        Ok(bytes.map(|bytes| (bytes, self as &dyn Entry)))
+    }
+
+    /// Names that would leave the directory, `../secret` or `/etc/passwd`,
+    /// and subdirectories are not resources.
+    fn read_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let inside = Path::new(name)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        let path = self.path.join(name);
+        if !inside || path.is_dir() {
+            return Ok(None);
+        }
+        match fs::read(path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::class_path::Entry;
+    use crate::classpath::directory_entry::DirectoryEntry;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_read_class_from_directory() {
+        let entry = DirectoryEntry::new(PathBuf::from("testdata/java17"));
+        let (bytes, source) = entry.read_class("sample/Task.class").unwrap().unwrap();
+        assert_eq!(&[0xca, 0xfe, 0xba, 0xbe], &bytes[..4]);
+        assert_eq!(entry.path(), source.path());
+
+        assert!(entry.read_class("sample/Missing.class").unwrap().is_none());
+    }
+
+    #[test]
+    fn should_only_read_resources_inside_directory() {
+        let entry = DirectoryEntry::new(PathBuf::from("testdata/classpath/classes"));
+        assert!(entry
+            .read_resource("META-INF/services/sample.Service")
+            .unwrap()
+            .is_some());
+
+        assert!(entry.read_resource("META-INF/services").unwrap().is_none());
+        assert!(entry.read_resource("../app.jar").unwrap().is_none());
+        assert!(entry.read_resource("/etc/hostname").unwrap().is_none());
     }
 }
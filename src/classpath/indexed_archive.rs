@@ -0,0 +1,191 @@
+use crate::classpath::manifest::Manifest;
+use std::cell::{RefCell, RefMut};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use zip::ZipArchive;
+
+/// The first release with multi-release jars, anything older reads only
+/// the base entries.
+pub const FIRST_VERSIONED_RELEASE: u32 = 9;
+
+const VERSIONS_PREFIX: &str = "META-INF/versions/";
+
+/// An open zip with its files indexed by the name a class path lookup asks
+/// for, `java/lang/String.class`, once and for all.
+pub struct IndexedArchive {
+    archive: ZipArchive<File>,
+    /// lookup name to the name in the archive that serves it
+    files: HashMap<String, String>,
+}
+
+impl IndexedArchive {
+    /// Indexes the files under `prefix`. In a `Multi-Release: true` jar the
+    /// highest `META-INF/versions/N/` copy with `N` up to `release` stands in
+    /// for the base file. Other archives keep `META-INF/versions/` files
+    /// under their own names, like any resource.
+    pub fn new(
+        mut archive: ZipArchive<File>,
+        prefix: &str,
+        release: u32,
+    ) -> io::Result<IndexedArchive> {
+        let multi_release = release >= FIRST_VERSIONED_RELEASE && is_multi_release(&mut archive)?;
+
+        // lookup name to (version, archive name), the base version being 0
+        let mut best: HashMap<&str, (u32, &str)> = HashMap::new();
+        for name in archive.file_names() {
+            if name.ends_with('/') {
+                continue;
+            }
+            let (version, lookup) = match versioned(name) {
+                Some((version, lookup)) if multi_release => {
+                    if version > release {
+                        continue;
+                    }
+                    (version, lookup)
+                }
+                _ => match name.strip_prefix(prefix) {
+                    Some(lookup) => (0, lookup),
+                    None => continue,
+                },
+            };
+            match best.get(lookup) {
+                Some((current, _)) if *current >= version => {}
+                _ => {
+                    best.insert(lookup, (version, name));
+                }
+            }
+        }
+
+        let files = best
+            .into_iter()
+            .map(|(lookup, (_, name))| (String::from(lookup), String::from(name)))
+            .collect();
+        Ok(IndexedArchive { archive, files })
+    }
+
+    pub fn read(&mut self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let name = match self.files.get(name) {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let mut file = self.archive.by_name(name)?;
+        let mut bytes = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// Every package with a class in it, `java/lang`, or `""` for the
+    /// unnamed package.
+    pub fn packages(&self) -> Vec<String> {
+        let packages: BTreeSet<&str> = self
+            .files
+            .keys()
+            .filter(|name| name.ends_with(".class"))
+            .map(|name| match name.rfind('/') {
+                Some(slash) => &name[..slash],
+                None => "",
+            })
+            .collect();
+        packages.into_iter().map(String::from).collect()
+    }
+}
+
+fn is_multi_release(archive: &mut ZipArchive<File>) -> io::Result<bool> {
+    let manifest = Manifest::read_from_archive(archive)?;
+    Ok(matches!(manifest, Some(manifest) if manifest.is_multi_release()))
+}
+
+/// `META-INF/versions/11/a/B.class` is version 11 of `a/B.class`.
+fn versioned(name: &str) -> Option<(u32, &str)> {
+    let rest = name.strip_prefix(VERSIONS_PREFIX)?;
+    let slash = rest.find('/')?;
+    let version = rest[..slash].parse().ok()?;
+    if version < FIRST_VERSIONED_RELEASE {
+        return None;
+    }
+    Some((version, &rest[slash + 1..]))
+}
+
+/// An `IndexedArchive` opened on first use and kept, for entries that are
+/// looked up over and over.
+#[derive(Default)]
+pub struct LazyArchive {
+    archive: RefCell<Option<IndexedArchive>>,
+}
+
+impl LazyArchive {
+    pub fn new() -> LazyArchive {
+        LazyArchive::default()
+    }
+
+    /// The archive, opened with `open` unless that already happened.
+    pub fn get<F>(&self, open: F) -> io::Result<RefMut<'_, IndexedArchive>>
+    where
+        F: FnOnce() -> io::Result<IndexedArchive>,
+    {
+        let mut archive = self.archive.borrow_mut();
+        if archive.is_none() {
+            *archive = Some(open()?);
+        }
+        Ok(RefMut::map(archive, |archive| archive.as_mut().unwrap()))
+    }
+}
+
+pub fn open_zip(path: &Path) -> io::Result<ZipArchive<File>> {
+    Ok(ZipArchive::new(File::open(path)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::indexed_archive::{open_zip, versioned, IndexedArchive};
+    use std::path::Path;
+
+    #[test]
+    fn should_parse_versioned_names() {
+        assert_eq!(
+            Some((11, "mr/Version.class")),
+            versioned("META-INF/versions/11/mr/Version.class")
+        );
+        assert_eq!(None, versioned("META-INF/versions/8/mr/Version.class"));
+        assert_eq!(None, versioned("META-INF/versions/next/mr/Version.class"));
+        assert_eq!(None, versioned("mr/Version.class"));
+    }
+
+    #[test]
+    fn should_index_versioned_files_by_name_without_multi_release() {
+        let versioned = "META-INF/versions/11/mr/Version.class";
+        let archive = open_zip(Path::new("testdata/java8/jar/not-multi-release.jar")).unwrap();
+        let mut archive = IndexedArchive::new(archive, "", 17).unwrap();
+        assert_eq!(270, archive.read(versioned).unwrap().unwrap().len());
+        assert_eq!(
+            269,
+            archive.read("mr/Version.class").unwrap().unwrap().len()
+        );
+
+        // a release 8 VM does not know multi-release jars either
+        let archive = open_zip(Path::new("testdata/java8/jar/multi-release.jar")).unwrap();
+        let mut archive = IndexedArchive::new(archive, "", 8).unwrap();
+        assert!(archive.read(versioned).unwrap().is_some());
+
+        let archive = open_zip(Path::new("testdata/java8/jar/multi-release.jar")).unwrap();
+        let mut archive = IndexedArchive::new(archive, "", 17).unwrap();
+        assert!(archive.read(versioned).unwrap().is_none());
+    }
+
+    #[test]
+    fn should_list_packages() {
+        let archive = open_zip(Path::new("testdata/java8/jar/multi-release.jar")).unwrap();
+        let archive = IndexedArchive::new(archive, "", 17).unwrap();
+        assert_eq!(vec![String::from("mr")], archive.packages());
+
+        let archive = open_zip(Path::new("testdata/jdk-jmods/jmods/sample.jmod")).unwrap();
+        let archive = IndexedArchive::new(archive, "classes/", 17).unwrap();
+        assert_eq!(
+            vec![String::from(""), String::from("sample")],
+            archive.packages()
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::env;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories holding one JDK per sub directory, searched when neither an
+/// explicit java home nor `JAVA_HOME` is given.
+const JVM_DIRECTORIES: [&str; 3] = [
+    "/usr/lib/jvm",
+    "/usr/java",
+    "/Library/Java/JavaVirtualMachines",
+];
+
+/// Links distributions point at their preferred JDK, tried before the
+/// others in `JVM_DIRECTORIES`.
+const DEFAULT_JAVA_HOMES: [&str; 4] = [
+    "/usr/lib/jvm/default-java",
+    "/usr/lib/jvm/default",
+    "/usr/java/default",
+    "/usr/java/latest",
+];
+
+/// How the runtime ships its classes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeImage {
+    /// `lib/rt.jar`, Java 8 and earlier
+    RtJar(PathBuf),
+    /// `lib/modules`, the jimage of Java 9 and later
+    Modules(PathBuf),
+    /// `jmods/`, a JDK 9+ that was never linked into an image
+    Jmods(PathBuf),
+}
+
+/// A java home with a runtime image in it, e.g. `/usr/lib/jvm/java-8/jre`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JavaHome {
+    pub path: PathBuf,
+    pub image: RuntimeImage,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JavaHomeError {
+    /// an explicit or `JAVA_HOME` directory without runtime classes
+    NoRuntimeImage { origin: String, path: PathBuf },
+    /// nothing given and no well-known location has a runtime image
+    NotFound { searched: Vec<PathBuf> },
+}
+
+impl fmt::Display for JavaHomeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JavaHomeError::NoRuntimeImage { origin, path } => write!(
+                f,
+                "{} {} has no lib/rt.jar, lib/modules or jmods",
+                origin,
+                path.display()
+            ),
+            JavaHomeError::NotFound { searched } => {
+                write!(f, "no Java runtime found, set JAVA_HOME; searched")?;
+                for path in searched {
+                    write!(f, " {}", path.display())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for JavaHomeError {}
+
+impl JavaHome {
+    /// Looks at `java_home` when given, then `JAVA_HOME`, then the usual
+    /// install locations.
+    pub fn find(java_home: Option<&str>) -> Result<JavaHome, JavaHomeError> {
+        let env_home = env::var("JAVA_HOME").ok();
+        JavaHome::resolve(java_home, env_home.as_deref())
+    }
+
+    /// `find` with the value of `JAVA_HOME` passed in.
+    pub fn resolve(
+        java_home: Option<&str>,
+        env_home: Option<&str>,
+    ) -> Result<JavaHome, JavaHomeError> {
+        let given = match (java_home, env_home) {
+            (Some(path), _) => Some(("java home", path)),
+            (None, Some(path)) if !path.is_empty() => Some(("JAVA_HOME", path)),
+            _ => None,
+        };
+        if let Some((origin, path)) = given {
+            return JavaHome::open(Path::new(path)).ok_or_else(|| JavaHomeError::NoRuntimeImage {
+                origin: String::from(origin),
+                path: PathBuf::from(path),
+            });
+        }
+
+        let searched = well_known_homes();
+        for path in &searched {
+            if let Some(java_home) = JavaHome::open(path) {
+                return Ok(java_home);
+            }
+        }
+        Err(JavaHomeError::NotFound { searched })
+    }
+
+    /// Accepts a JRE, a JDK whose runtime sits in `jre/`, a JDK 9+ image, or
+    /// a directory of jmods.
+    pub fn open(path: &Path) -> Option<JavaHome> {
+        for home in [path.to_path_buf(), path.join("jre")].iter() {
+            let rt_jar = home.join("lib").join("rt.jar");
+            if rt_jar.is_file() {
+                return Some(JavaHome {
+                    path: home.clone(),
+                    image: RuntimeImage::RtJar(rt_jar),
+                });
+            }
+        }
+
+        let modules = path.join("lib").join("modules");
+        if modules.is_file() {
+            return Some(JavaHome {
+                path: path.to_path_buf(),
+                image: RuntimeImage::Modules(modules),
+            });
+        }
+
+        let jmods = path.join("jmods");
+        let has_jmods = match fs::read_dir(&jmods) {
+            Ok(files) => files
+                .filter_map(Result::ok)
+                .any(|file| file.path().extension() == Some(OsStr::new("jmod"))),
+            Err(_) => false,
+        };
+        if has_jmods {
+            return Some(JavaHome {
+                path: path.to_path_buf(),
+                image: RuntimeImage::Jmods(jmods),
+            });
+        }
+        None
+    }
+}
+
+/// The default links first, then every JDK in `JVM_DIRECTORIES`, newest
+/// name first.
+fn well_known_homes() -> Vec<PathBuf> {
+    let mut homes: Vec<PathBuf> = DEFAULT_JAVA_HOMES.iter().map(PathBuf::from).collect();
+    for directory in JVM_DIRECTORIES.iter() {
+        let mut jdks: Vec<PathBuf> = match fs::read_dir(directory) {
+            Ok(entries) => entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .collect(),
+            Err(_) => continue,
+        };
+        jdks.sort();
+        for jdk in jdks.into_iter().rev() {
+            // macOS bundles keep the home inside `Contents/Home`
+            let bundle_home = jdk.join("Contents").join("Home");
+            homes.push(if bundle_home.is_dir() {
+                bundle_home
+            } else {
+                jdk
+            });
+        }
+    }
+    homes
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::java_home::{JavaHome, JavaHomeError, RuntimeImage};
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_prefer_explicit_java_home() {
+        let java_home = JavaHome::resolve(Some("testdata/jre"), Some("testdata/jdk")).unwrap();
+        assert_eq!(PathBuf::from("testdata/jre"), java_home.path);
+        assert_eq!(
+            RuntimeImage::RtJar(PathBuf::from("testdata/jre/lib/rt.jar")),
+            java_home.image
+        );
+
+        let java_home = JavaHome::resolve(None, Some("testdata/jdk")).unwrap();
+        assert_eq!(
+            RuntimeImage::Modules(PathBuf::from("testdata/jdk/lib/modules")),
+            java_home.image
+        );
+    }
+
+    #[test]
+    fn should_accept_jdk_with_only_jmods() {
+        let java_home = JavaHome::resolve(Some("testdata/jdk-jmods"), None).unwrap();
+        assert_eq!(
+            RuntimeImage::Jmods(PathBuf::from("testdata/jdk-jmods/jmods")),
+            java_home.image
+        );
+    }
+
+    #[test]
+    fn should_use_jre_inside_jdk() {
+        let java_home = JavaHome::resolve(Some("testdata"), None).unwrap();
+        assert_eq!(PathBuf::from("testdata/jre"), java_home.path);
+    }
+
+    #[test]
+    fn should_report_java_home_without_runtime() {
+        let err = JavaHome::resolve(None, Some("testdata/java8")).unwrap_err();
+        assert_eq!(
+            JavaHomeError::NoRuntimeImage {
+                origin: String::from("JAVA_HOME"),
+                path: PathBuf::from("testdata/java8"),
+            },
+            err
+        );
+        assert_eq!(
+            "JAVA_HOME testdata/java8 has no lib/rt.jar, lib/modules or jmods",
+            err.to_string()
+        );
+    }
+}
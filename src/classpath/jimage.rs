@@ -0,0 +1,621 @@
+//! Reader for the jimage container JDK 9 and later ship their classes in,
+//! `lib/modules`. Layout, in the byte order of the magic:
+//!
+//! ```text
+//! header     u4 magic, u4 version, u4 flags, u4 resource_count,
+//!            u4 table_length, u4 locations_size, u4 strings_size
+//! redirect   s4[table_length]   perfect hash of the resource names
+//! offsets    u4[table_length]   location of each resource
+//! locations  u1[locations_size] packed location attributes
+//! strings    u1[strings_size]   NUL terminated modified UTF-8
+//! resources  everything after the index, may be compressed
+//! ```
+
+use crate::classfile::constant_pool::{
+    CONSTANT_CLASS, CONSTANT_DOUBLE, CONSTANT_DYNAMIC, CONSTANT_FIELD_REF, CONSTANT_FLOAT,
+    CONSTANT_INTEGER, CONSTANT_INTERFACE_METHOD_REF, CONSTANT_INVOKE_DYNAMIC, CONSTANT_LONG,
+    CONSTANT_METHOD_HANDLE, CONSTANT_METHOD_REF, CONSTANT_METHOD_TYPE, CONSTANT_MODULE,
+    CONSTANT_NAME_AND_TYPE, CONSTANT_PACKAGE, CONSTANT_STRING, CONSTANT_UTF8,
+};
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use flate2::read::ZlibDecoder;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+pub const IMAGE_MAGIC: u32 = 0xCAFE_DADA;
+pub const MAJOR_VERSION: u16 = 1;
+pub const MINOR_VERSION: u16 = 0;
+
+const HEADER_SIZE: usize = 7 * 4;
+const HASH_MULTIPLIER: u32 = 0x0100_0193;
+
+const ATTRIBUTE_END: usize = 0;
+const ATTRIBUTE_MODULE: usize = 1;
+const ATTRIBUTE_PARENT: usize = 2;
+const ATTRIBUTE_BASE: usize = 3;
+const ATTRIBUTE_EXTENSION: usize = 4;
+const ATTRIBUTE_OFFSET: usize = 5;
+const ATTRIBUTE_COMPRESSED: usize = 6;
+const ATTRIBUTE_UNCOMPRESSED: usize = 7;
+const ATTRIBUTE_COUNT: usize = 8;
+
+/// Every compressed resource starts with this header, one per compression
+/// applied to it: u4 magic, u8 compressed size, u8 uncompressed size,
+/// u4 decompressor name, u4 decompressor config, u1 is terminal.
+const RESOURCE_MAGIC: u32 = 0xCAFE_FAFA;
+const RESOURCE_HEADER_SIZE: usize = 4 + 8 + 8 + 4 + 4 + 1;
+
+// constant pool tags the `compact-cp` plugin adds
+const EXTERNALIZED_STRING: u8 = 23;
+const EXTERNALIZED_STRING_DESCRIPTOR: u8 = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    fn u32(self, bytes: &[u8]) -> u32 {
+        match self {
+            Endian::Little => LittleEndian::read_u32(bytes),
+            Endian::Big => BigEndian::read_u32(bytes),
+        }
+    }
+
+    fn u64(self, bytes: &[u8]) -> u64 {
+        match self {
+            Endian::Little => LittleEndian::read_u64(bytes),
+            Endian::Big => BigEndian::read_u64(bytes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageHeader {
+    pub endian: Endian,
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub flags: u32,
+    pub resource_count: u32,
+    pub table_length: u32,
+    pub locations_size: u32,
+    pub strings_size: u32,
+}
+
+impl ImageHeader {
+    pub fn parse(bytes: &[u8]) -> io::Result<ImageHeader> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(invalid_data("truncated jimage header"));
+        }
+        let endian = if LittleEndian::read_u32(bytes) == IMAGE_MAGIC {
+            Endian::Little
+        } else if BigEndian::read_u32(bytes) == IMAGE_MAGIC {
+            Endian::Big
+        } else {
+            return Err(invalid_data("not a jimage, bad magic"));
+        };
+        let version = endian.u32(&bytes[4..]);
+        let header = ImageHeader {
+            endian,
+            major_version: (version >> 16) as u16,
+            minor_version: version as u16,
+            flags: endian.u32(&bytes[8..]),
+            resource_count: endian.u32(&bytes[12..]),
+            table_length: endian.u32(&bytes[16..]),
+            locations_size: endian.u32(&bytes[20..]),
+            strings_size: endian.u32(&bytes[24..]),
+        };
+        if header.major_version != MAJOR_VERSION || header.minor_version != MINOR_VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported jimage version {}.{}",
+                header.major_version, header.minor_version
+            )));
+        }
+        Ok(header)
+    }
+
+    fn redirect_start(&self) -> usize {
+        HEADER_SIZE
+    }
+
+    fn offsets_start(&self) -> usize {
+        self.redirect_start() + self.table_length as usize * 4
+    }
+
+    fn locations_start(&self) -> usize {
+        self.offsets_start() + self.table_length as usize * 4
+    }
+
+    fn strings_start(&self) -> usize {
+        self.locations_start() + self.locations_size as usize
+    }
+
+    /// Size of everything before the resources.
+    pub fn index_size(&self) -> usize {
+        self.strings_start() + self.strings_size as usize
+    }
+}
+
+/// The attributes of one resource: the four parts of its name as string
+/// offsets, and where its content is.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ImageLocation {
+    attributes: [u64; ATTRIBUTE_COUNT],
+}
+
+impl ImageLocation {
+    /// Each attribute is one byte of `kind << 3 | (length - 1)` followed by
+    /// `length` big-endian bytes of value, up to `ATTRIBUTE_END`.
+    fn decode(bytes: &[u8]) -> io::Result<ImageLocation> {
+        let mut attributes = [0; ATTRIBUTE_COUNT];
+        let mut offset = 0;
+        while offset < bytes.len() {
+            let data = bytes[offset] as usize;
+            offset += 1;
+            let kind = data >> 3;
+            if kind == ATTRIBUTE_END {
+                break;
+            }
+            if kind >= ATTRIBUTE_COUNT {
+                return Err(invalid_data("bad jimage location attribute"));
+            }
+            let length = (data & 0x7) + 1;
+            let value = match bytes.get(offset..offset + length) {
+                Some(value) => value,
+                None => return Err(invalid_data("truncated jimage location")),
+            };
+            attributes[kind] = value.iter().fold(0, |acc, b| acc << 8 | *b as u64);
+            offset += length;
+        }
+        Ok(ImageLocation { attributes })
+    }
+
+    /// offset of the content from the end of the index
+    pub fn content_offset(&self) -> u64 {
+        self.attributes[ATTRIBUTE_OFFSET]
+    }
+
+    /// 0 when the content is stored as is
+    pub fn compressed_size(&self) -> u64 {
+        self.attributes[ATTRIBUTE_COMPRESSED]
+    }
+
+    pub fn uncompressed_size(&self) -> u64 {
+        self.attributes[ATTRIBUTE_UNCOMPRESSED]
+    }
+}
+
+/// An opened `lib/modules`. The index is read once; resource content is
+/// read from the file when asked for.
+pub struct JImage {
+    path: PathBuf,
+    header: ImageHeader,
+    index: Vec<u8>,
+}
+
+impl JImage {
+    pub fn open(path: &Path) -> io::Result<JImage> {
+        let mut file = File::open(path)?;
+        let mut bytes = vec![0; HEADER_SIZE];
+        file.read_exact(&mut bytes)?;
+        let header = ImageHeader::parse(&bytes)?;
+
+        bytes.resize(header.index_size(), 0);
+        file.read_exact(&mut bytes[HEADER_SIZE..])?;
+        Ok(JImage {
+            path: path.to_path_buf(),
+            header,
+            index: bytes,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn header(&self) -> &ImageHeader {
+        &self.header
+    }
+
+    fn redirect(&self, index: u32) -> i32 {
+        let start = self.header.redirect_start() + index as usize * 4;
+        self.header.endian.u32(&self.index[start..]) as i32
+    }
+
+    fn location_offset(&self, index: u32) -> usize {
+        let start = self.header.offsets_start() + index as usize * 4;
+        self.header.endian.u32(&self.index[start..]) as usize
+    }
+
+    /// The NUL terminated string at `offset` in the strings table.
+    fn string(&self, offset: u64) -> io::Result<&[u8]> {
+        let start = self.header.strings_start();
+        let strings = &self.index[start..self.header.index_size()];
+        let tail = match strings.get(offset as usize..) {
+            Some(tail) => tail,
+            None => return Err(invalid_data("jimage string out of range")),
+        };
+        match tail.iter().position(|b| *b == 0) {
+            Some(end) => Ok(&tail[..end]),
+            None => Err(invalid_data("unterminated jimage string")),
+        }
+    }
+
+    /// Looks up a resource such as `/java.base/java/lang/String.class`.
+    pub fn find_location(&self, name: &str) -> io::Result<Option<ImageLocation>> {
+        let length = self.header.table_length;
+        if length == 0 {
+            return Ok(None);
+        }
+        let name = name.as_bytes();
+        let redirect = self.redirect(hash_code(name, HASH_MULTIPLIER) % length);
+        let index = match redirect {
+            0 => return Ok(None),
+            // a negative redirect is the index itself
+            r if r < 0 => (-1 - r) as u32,
+            // a positive one is the seed for a second hash
+            r => hash_code(name, r as u32) % length,
+        };
+
+        let start = self.header.locations_start() + self.location_offset(index);
+        let end = self.header.strings_start();
+        let location = match self.index.get(start..end) {
+            Some(bytes) => ImageLocation::decode(bytes)?,
+            None => return Err(invalid_data("jimage location out of range")),
+        };
+        // the hash only says where the name would be
+        if self.location_name(&location)? == name {
+            Ok(Some(location))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// `/module/parent/base.extension`, leaving out the empty parts.
+    fn location_name(&self, location: &ImageLocation) -> io::Result<Vec<u8>> {
+        let mut name = vec![];
+        let module = self.string(location.attributes[ATTRIBUTE_MODULE])?;
+        if !module.is_empty() {
+            name.push(b'/');
+            name.extend_from_slice(module);
+            name.push(b'/');
+        }
+        let parent = self.string(location.attributes[ATTRIBUTE_PARENT])?;
+        if !parent.is_empty() {
+            name.extend_from_slice(parent);
+            name.push(b'/');
+        }
+        name.extend_from_slice(self.string(location.attributes[ATTRIBUTE_BASE])?);
+        let extension = self.string(location.attributes[ATTRIBUTE_EXTENSION])?;
+        if !extension.is_empty() {
+            name.push(b'.');
+            name.extend_from_slice(extension);
+        }
+        Ok(name)
+    }
+
+    /// The content of a resource, decompressed.
+    pub fn read_resource(&self, location: &ImageLocation) -> io::Result<Vec<u8>> {
+        let compressed_size = location.compressed_size();
+        let size = if compressed_size == 0 {
+            location.uncompressed_size()
+        } else {
+            compressed_size
+        };
+
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(
+            self.header.index_size() as u64 + location.content_offset(),
+        ))?;
+        let mut content = vec![0; size as usize];
+        file.read_exact(&mut content)?;
+
+        if compressed_size != 0 {
+            content = self.decompress(content)?;
+        }
+        if content.len() as u64 != location.uncompressed_size() {
+            return Err(invalid_data("jimage resource has the wrong size"));
+        }
+        Ok(content)
+    }
+
+    pub fn find_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.find_location(name)? {
+            Some(location) => Ok(Some(self.read_resource(&location)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The module a package such as `java/lang` belongs to, read from the
+    /// `/packages/java.lang` resource: pairs of u4 is-empty and u4 module
+    /// name, the first module that is not empty owns the package.
+    pub fn package_module(&self, package: &str) -> io::Result<Option<String>> {
+        let name = format!("/packages/{}", package.replace('/', "."));
+        let content = match self.find_resource(&name)? {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+        for pair in content.chunks_exact(8) {
+            let endian = self.header.endian;
+            if endian.u32(pair) == 0 {
+                let module = self.string(endian.u32(&pair[4..]) as u64)?;
+                return Ok(Some(String::from_utf8_lossy(module).to_string()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Undoes the compressions applied to a resource, outermost first.
+    fn decompress(&self, mut content: Vec<u8>) -> io::Result<Vec<u8>> {
+        let endian = self.header.endian;
+        while content.len() >= RESOURCE_HEADER_SIZE && endian.u32(&content) == RESOURCE_MAGIC {
+            let compressed_size = endian.u64(&content[4..]) as usize;
+            let uncompressed_size = endian.u64(&content[12..]) as usize;
+            let decompressor = self.string(endian.u32(&content[20..]) as u64)?;
+            let end = RESOURCE_HEADER_SIZE + compressed_size;
+            let compressed = match content.get(RESOURCE_HEADER_SIZE..end) {
+                Some(compressed) => compressed,
+                None => return Err(invalid_data("truncated jimage resource")),
+            };
+            content = match decompressor {
+                b"zip" => {
+                    let mut inflated = Vec::with_capacity(uncompressed_size);
+                    ZlibDecoder::new(compressed).read_to_end(&mut inflated)?;
+                    inflated
+                }
+                b"compact-cp" => self.expand_strings(compressed, uncompressed_size)?,
+                name => {
+                    return Err(invalid_data(&format!(
+                        "unknown jimage decompressor {}",
+                        String::from_utf8_lossy(name)
+                    )))
+                }
+            };
+        }
+        Ok(content)
+    }
+
+    /// Puts back the constant pool strings `compact-cp` moved into the
+    /// strings table of the image.
+    fn expand_strings(&self, class: &[u8], size: usize) -> io::Result<Vec<u8>> {
+        let mut reader = ClassReader {
+            bytes: class,
+            at: 0,
+        };
+        let mut out = Vec::with_capacity(size);
+        // magic, minor and major version
+        out.extend_from_slice(reader.take(8)?);
+        let count = reader.take(2)?;
+        out.extend_from_slice(count);
+        let count = BigEndian::read_u16(count);
+
+        let mut i = 1;
+        while i < count {
+            let tag = reader.take(1)?[0];
+            match tag {
+                CONSTANT_UTF8 => {
+                    let length = reader.take(2)?;
+                    let length_value = BigEndian::read_u16(length) as usize;
+                    out.push(tag);
+                    out.extend_from_slice(length);
+                    out.extend_from_slice(reader.take(length_value)?);
+                }
+                EXTERNALIZED_STRING => {
+                    let string = self.string(reader.compressed_int()? as u64)?;
+                    push_utf8(&mut out, string)?;
+                }
+                EXTERNALIZED_STRING_DESCRIPTOR => {
+                    let descriptor = self.expand_descriptor(&mut reader)?;
+                    push_utf8(&mut out, &descriptor)?;
+                }
+                _ => {
+                    let size = match constant_size(tag) {
+                        Some(size) => size,
+                        None => return Err(invalid_data("bad constant pool tag in jimage class")),
+                    };
+                    out.push(tag);
+                    out.extend_from_slice(reader.take(size)?);
+                    if tag == CONSTANT_LONG || tag == CONSTANT_DOUBLE {
+                        i += 1;
+                    }
+                }
+            }
+            i += 1;
+        }
+        out.extend_from_slice(&class[reader.at..]);
+        Ok(out)
+    }
+
+    /// A descriptor stored as its skeleton, e.g. `(L;I)V`, followed by a
+    /// package and a simple name for every `L`.
+    fn expand_descriptor(&self, reader: &mut ClassReader) -> io::Result<Vec<u8>> {
+        let skeleton = self.string(reader.compressed_int()? as u64)?;
+        let length = reader.compressed_int()? as usize;
+        let mut indexes = reader.take(length)?;
+        let mut names = vec![];
+        while !indexes.is_empty() {
+            let size = compressed_int_size(indexes[0]);
+            if indexes.len() < size {
+                return Err(invalid_data("truncated jimage descriptor"));
+            }
+            names.push(compressed_int(&indexes[..size]));
+            indexes = &indexes[size..];
+        }
+
+        let mut names = names.into_iter();
+        let mut descriptor = vec![];
+        for b in skeleton {
+            descriptor.push(*b);
+            if *b != b'L' {
+                continue;
+            }
+            let (package, class) = match (names.next(), names.next()) {
+                (Some(package), Some(class)) => (package, class),
+                _ => return Err(invalid_data("jimage descriptor is missing a name")),
+            };
+            let package = self.string(package as u64)?;
+            if !package.is_empty() {
+                descriptor.extend_from_slice(package);
+                descriptor.push(b'/');
+            }
+            descriptor.extend_from_slice(self.string(class as u64)?);
+        }
+        Ok(descriptor)
+    }
+}
+
+/// The hash `jlink` uses for the redirect table, over the UTF-8 bytes.
+fn hash_code(name: &[u8], seed: u32) -> u32 {
+    let hash = name.iter().fold(seed, |hash, b| {
+        hash.wrapping_mul(HASH_MULTIPLIER) ^ *b as u32
+    });
+    hash & 0x7FFF_FFFF
+}
+
+struct ClassReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl<'a> ClassReader<'a> {
+    fn take(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        match self.bytes.get(self.at..self.at + length) {
+            Some(bytes) => {
+                self.at += length;
+                Ok(bytes)
+            }
+            None => Err(invalid_data("truncated jimage class")),
+        }
+    }
+
+    fn compressed_int(&mut self) -> io::Result<u32> {
+        let size = match self.bytes.get(self.at) {
+            Some(header) => compressed_int_size(*header),
+            None => return Err(invalid_data("truncated jimage class")),
+        };
+        Ok(compressed_int(self.take(size)?))
+    }
+}
+
+/// Ints in `compact-cp` classes take 1 to 3 bytes when the top bit of the
+/// first byte is set, the next two bits being the length, 4 otherwise.
+fn compressed_int_size(header: u8) -> usize {
+    if header & 0x80 != 0 {
+        ((header >> 5) & 0x3) as usize
+    } else {
+        4
+    }
+}
+
+fn compressed_int(bytes: &[u8]) -> u32 {
+    let first = if bytes[0] & 0x80 != 0 {
+        bytes[0] & 0x1F
+    } else {
+        bytes[0]
+    };
+    bytes[1..]
+        .iter()
+        .fold(first as u32, |acc, b| acc << 8 | *b as u32)
+}
+
+fn push_utf8(out: &mut Vec<u8>, string: &[u8]) -> io::Result<()> {
+    if string.len() > u16::MAX as usize {
+        return Err(invalid_data("jimage string too long for a class"));
+    }
+    out.push(CONSTANT_UTF8);
+    out.extend_from_slice(&(string.len() as u16).to_be_bytes());
+    out.extend_from_slice(string);
+    Ok(())
+}
+
+/// Size of the constants that are copied as is.
+fn constant_size(tag: u8) -> Option<usize> {
+    match tag {
+        CONSTANT_INTEGER | CONSTANT_FLOAT => Some(4),
+        CONSTANT_LONG | CONSTANT_DOUBLE => Some(8),
+        CONSTANT_CLASS | CONSTANT_STRING | CONSTANT_METHOD_TYPE => Some(2),
+        CONSTANT_MODULE | CONSTANT_PACKAGE => Some(2),
+        CONSTANT_FIELD_REF | CONSTANT_METHOD_REF | CONSTANT_INTERFACE_METHOD_REF => Some(4),
+        CONSTANT_NAME_AND_TYPE | CONSTANT_DYNAMIC | CONSTANT_INVOKE_DYNAMIC => Some(4),
+        CONSTANT_METHOD_HANDLE => Some(3),
+        _ => None,
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::parsed_class::ParsedClass;
+    use crate::classpath::jimage::{Endian, JImage};
+    use crate::rtda::heap::instanced_klass::Supertypes;
+    use std::path::Path;
+
+    fn image() -> JImage {
+        JImage::open(Path::new("testdata/jdk/lib/modules")).unwrap()
+    }
+
+    #[test]
+    fn should_read_header() {
+        let image = image();
+        let header = image.header();
+        assert_eq!(Endian::Little, header.endian);
+        assert_eq!((1, 0), (header.major_version, header.minor_version));
+        assert_eq!(3, header.resource_count);
+        assert_eq!(3, header.table_length);
+    }
+
+    #[test]
+    fn should_read_stored_resource() {
+        let image = image();
+        let location = image
+            .find_location("/java.base/java/lang/Object.class")
+            .unwrap()
+            .unwrap();
+        assert_eq!(0, location.compressed_size());
+
+        let bytes = image.read_resource(&location).unwrap();
+        assert_eq!(1895, bytes.len());
+        assert_eq!(&[0xCA, 0xFE, 0xBA, 0xBE], &bytes[..4]);
+    }
+
+    #[test]
+    fn should_undo_compact_cp_and_zip() {
+        let image = image();
+        let bytes = image
+            .find_resource("/java.base/java/lang/Number.class")
+            .unwrap()
+            .unwrap();
+        assert_eq!(632, bytes.len());
+
+        let mut class = ParsedClass::new(ClassFileStream::new(bytes.clone())).unwrap();
+        assert_eq!(bytes, class.to_bytes().unwrap());
+        let klass = class.create_instance_klass(Supertypes::default());
+        assert_eq!("java/lang/Number", klass.klass_name);
+    }
+
+    #[test]
+    fn should_find_package_module() {
+        let image = image();
+        assert_eq!(
+            Some(String::from("java.base")),
+            image.package_module("java/lang").unwrap()
+        );
+        assert_eq!(None, image.package_module("java/util").unwrap());
+        assert!(image
+            .find_location("/java.base/java/lang/String.class")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn should_reject_other_files() {
+        assert!(JImage::open(Path::new("testdata/java8/HelloWorld.class")).is_err());
+    }
+}
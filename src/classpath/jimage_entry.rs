@@ -0,0 +1,88 @@
+use crate::classpath::class_path::Entry;
+use crate::classpath::jimage::JImage;
+use std::cell::RefCell;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// The `lib/modules` image of a JDK 9+ runtime. Its index is read on the
+/// first lookup and kept.
+pub struct JImageEntry {
+    pub path: PathBuf,
+    image: RefCell<Option<Rc<JImage>>>,
+}
+
+impl JImageEntry {
+    pub fn new(path: PathBuf) -> JImageEntry {
+        JImageEntry {
+            path,
+            image: RefCell::new(None),
+        }
+    }
+
+    fn image(&self) -> io::Result<Rc<JImage>> {
+        let mut image = self.image.borrow_mut();
+        if let Some(image) = image.as_ref() {
+            return Ok(image.clone());
+        }
+        let opened = Rc::new(JImage::open(&self.path)?);
+        *image = Some(opened.clone());
+        Ok(opened)
+    }
+}
+
+impl Entry for JImageEntry {
+    fn read_class(&self, class_name: &str) -> io::Result<Option<(Vec<u8>, &dyn Entry)>> {
+        let bytes = self.read_resource(class_name)?;
+        Ok(bytes.map(|bytes| (bytes, self as &dyn Entry)))
+    }
+
+    /// Classes and resources sit under the module of their package, e.g.
+    /// `/java.base/java/lang/String.class`. Modules do not open resources
+    /// outside their packages, such as `META-INF/`, to the class path.
+    fn read_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let package = match name.rfind('/') {
+            Some(slash) => &name[..slash],
+            // the image has no classes in the unnamed package
+            None => return Ok(None),
+        };
+        let image = self.image()?;
+        let module = match image.package_module(package)? {
+            Some(module) => module,
+            None => return Ok(None),
+        };
+        image.find_resource(&format!("/{}/{}", module, name))
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::class_path::Entry;
+    use crate::classpath::jimage_entry::JImageEntry;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_read_class_from_module_of_its_package() {
+        let entry = JImageEntry::new(PathBuf::from("testdata/jdk/lib/modules"));
+        let (bytes, source) = entry.read_class("java/lang/Object.class").unwrap().unwrap();
+        assert_eq!(1895, bytes.len());
+        assert_eq!(entry.path(), source.path());
+
+        assert!(entry
+            .read_class("java/lang/String.class")
+            .unwrap()
+            .is_none());
+        assert!(entry.read_class("java/util/List.class").unwrap().is_none());
+        assert!(entry.read_class("HelloWorld.class").unwrap().is_none());
+    }
+
+    #[test]
+    fn should_fail_on_missing_image() {
+        let entry = JImageEntry::new(PathBuf::from("testdata/jdk/lib/missing"));
+        assert!(entry.read_class("java/lang/Object.class").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use crate::classpath::class_path::Entry;
+use crate::classpath::indexed_archive::{IndexedArchive, LazyArchive};
+use std::cell::RefMut;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// `JM` and version 1.0, written before the zip part of a jmod
+pub const JMOD_MAGIC: [u8; 4] = [0x4A, 0x4D, 0x01, 0x00];
+
+/// A `jmods/*.jmod` file. Past its header it is a zip keeping classes under
+/// `classes/`, next to `bin/`, `conf/`, `lib/` and the like. Opened on the
+/// first lookup, like `ZipEntry`.
+pub struct JmodEntry {
+    pub path: PathBuf,
+    archive: LazyArchive,
+}
+
+impl JmodEntry {
+    pub fn new(path: PathBuf) -> JmodEntry {
+        JmodEntry {
+            path,
+            archive: LazyArchive::new(),
+        }
+    }
+
+    fn archive(&self) -> io::Result<RefMut<'_, IndexedArchive>> {
+        self.archive.get(|| {
+            let mut file = File::open(&self.path)?;
+            let mut magic = [0; 4];
+            file.read_exact(&mut magic)?;
+            if magic != JMOD_MAGIC {
+                let message = format!("{} is not a jmod file", self.path.display());
+                return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+            }
+
+            // zip skips data before the archive, the header included
+            IndexedArchive::new(ZipArchive::new(file)?, "classes/", 0)
+        })
+    }
+}
+
+impl Entry for JmodEntry {
+    fn read_class(&self, class_name: &str) -> io::Result<Option<(Vec<u8>, &dyn Entry)>> {
+        let bytes = self.read_resource(class_name)?;
+        Ok(bytes.map(|bytes| (bytes, self as &dyn Entry)))
+    }
+
+    /// Only what is under `classes/`, the rest of a jmod is not on the
+    /// class path.
+    fn read_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        self.archive()?.read(name)
+    }
+
+    fn packages(&self) -> io::Result<Option<Vec<String>>> {
+        Ok(Some(self.archive()?.packages()))
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::class_path::Entry;
+    use crate::classpath::jmod_entry::JmodEntry;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_read_class_from_jmod() {
+        let entry = JmodEntry::new(PathBuf::from("testdata/jdk-jmods/jmods/sample.jmod"));
+        let (bytes, source) = entry.read_class("sample/Task.class").unwrap().unwrap();
+        assert_eq!(340, bytes.len());
+        assert_eq!(entry.path(), source.path());
+
+        assert!(entry.read_class("sample/Missing.class").unwrap().is_none());
+    }
+
+    #[test]
+    fn should_reject_plain_jar() {
+        let entry = JmodEntry::new(PathBuf::from("testdata/java8/jar/hello.jar"));
+        assert!(entry.read_class("hello/HelloWorld.class").is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+pub const MANIFEST_NAME: &str = "META-INF/MANIFEST.MF";
+
+/// The main section of a jar manifest. Per-entry sections are not kept,
+/// nothing on the class path looks at them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Manifest {
+    attributes: HashMap<String, String>,
+}
+
+impl Manifest {
+    /// Parses `Name: value` lines up to the first blank line. A line starting
+    /// with a single space continues the previous value.
+    pub fn parse(text: &str) -> Manifest {
+        let mut attributes: HashMap<String, String> = HashMap::new();
+        let mut last: Option<String> = None;
+        for line in text.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                break;
+            }
+            if let Some(rest) = line.strip_prefix(' ') {
+                if let Some(name) = &last {
+                    if let Some(value) = attributes.get_mut(name) {
+                        value.push_str(rest);
+                    }
+                }
+                continue;
+            }
+            if let Some(colon) = line.find(':') {
+                // names are case-insensitive, store them lowercased
+                let name = line[..colon].trim().to_ascii_lowercase();
+                let value = line[colon + 1..].trim_start();
+                attributes.insert(name.clone(), String::from(value));
+                last = Some(name);
+            }
+        }
+        Manifest { attributes }
+    }
+
+    /// Reads the manifest of a jar, `None` when it has no manifest.
+    pub fn read_from_jar(path: &Path) -> io::Result<Option<Manifest>> {
+        let mut archive = ZipArchive::new(File::open(path)?)?;
+        Manifest::read_from_archive(&mut archive)
+    }
+
+    pub fn read_from_archive<R: Read + Seek>(
+        archive: &mut ZipArchive<R>,
+    ) -> io::Result<Option<Manifest>> {
+        let mut file = match archive.by_name(MANIFEST_NAME) {
+            Ok(file) => file,
+            Err(ZipError::FileNotFound) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut bytes = vec![];
+        file.read_to_end(&mut bytes)?;
+        Ok(Some(Manifest::parse(&String::from_utf8_lossy(&bytes))))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .get(&name.to_ascii_lowercase())
+            .map(|value| value.as_str())
+    }
+
+    /// The space separated relative URLs of `Class-Path`
+    pub fn class_path(&self) -> Vec<&str> {
+        match self.get("Class-Path") {
+            Some(value) => value.split_whitespace().collect(),
+            None => vec![],
+        }
+    }
+
+    /// `Multi-Release: true`, the jar has `META-INF/versions/N/` overrides
+    pub fn is_multi_release(&self) -> bool {
+        match self.get("Multi-Release") {
+            Some(value) => value.trim().eq_ignore_ascii_case("true"),
+            None => false,
+        }
+    }
+
+    /// `Main-Class`, as written, e.g. `hello.HelloWorld`
+    pub fn main_class(&self) -> Option<&str> {
+        self.get("Main-Class").filter(|name| !name.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::manifest::Manifest;
+    use std::path::Path;
+
+    #[test]
+    fn should_parse_main_section() {
+        let text = "Manifest-Version: 1.0\r\nMain-Class: a.very.long.package\r\n .Main\r\nclass-path: lib/a.jar\r\n\r\nName: a/B.class\r\nMain-Class: other\r\n";
+        let manifest = Manifest::parse(text);
+
+        assert_eq!(Some("1.0"), manifest.get("Manifest-Version"));
+        assert_eq!(Some("a.very.long.package.Main"), manifest.main_class());
+        assert_eq!(Some("lib/a.jar"), manifest.get("Class-Path"));
+        assert_eq!(vec!["lib/a.jar"], manifest.class_path());
+        assert_eq!(None, manifest.get("Name"));
+    }
+
+    #[test]
+    fn should_read_manifest_from_jar() {
+        let manifest = Manifest::read_from_jar(Path::new("testdata/java8/jar/main.jar"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some("hello.HelloWorld"), manifest.main_class());
+
+        let manifest = Manifest::read_from_jar(Path::new("testdata/java8/jar/hello.jar"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(None, manifest.main_class());
+        assert!(!manifest.is_multi_release());
+
+        let manifest = Manifest::read_from_jar(Path::new("testdata/java8/jar/multi-release.jar"))
+            .unwrap()
+            .unwrap();
+        assert!(manifest.is_multi_release());
+    }
+}
@@ -1,4 +1,9 @@
-pub mod class_file_entry;
 pub mod class_path;
 pub mod directory_entry;
+pub mod indexed_archive;
+pub mod java_home;
+pub mod jimage;
+pub mod jimage_entry;
+pub mod jmod_entry;
+pub mod manifest;
 pub mod zip_entry;
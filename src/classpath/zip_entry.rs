@@ -1,19 +1,107 @@
-use crate::classpath::class_path::Entry;
-use std::path::PathBuf;
+use crate::classpath::class_path::{Entry, FEATURE_VERSION};
+use crate::classpath::indexed_archive::{open_zip, IndexedArchive, LazyArchive};
+use std::cell::RefMut;
+use std::io;
+use std::path::{Path, PathBuf};
 
-#[derive(Clone)]
+/// A jar or zip. The archive is opened and indexed on the first lookup and
+/// kept open after that.
 pub struct ZipEntry {
     pub path: PathBuf,
+    /// the Java release `META-INF/versions/N/` overrides are picked for
+    pub release: u32,
+    archive: LazyArchive,
 }
 
 impl ZipEntry {
     pub fn new(path: PathBuf) -> ZipEntry {
-        ZipEntry { path }
+        ZipEntry::with_release(path, FEATURE_VERSION)
+    }
+
+    pub fn with_release(path: PathBuf, release: u32) -> ZipEntry {
+        ZipEntry {
+            path,
+            release,
+            archive: LazyArchive::new(),
+        }
+    }
+
+    fn archive(&self) -> io::Result<RefMut<'_, IndexedArchive>> {
+        self.archive
+            .get(|| IndexedArchive::new(open_zip(&self.path)?, "", self.release))
     }
 }
 
 impl Entry for ZipEntry {
-    fn read_class(&self, _class_name: String) {
-        unimplemented!()
+    fn read_class(&self, class_name: &str) -> io::Result<Option<(Vec<u8>, &dyn Entry)>> {
+        let bytes = self.read_resource(class_name)?;
+        Ok(bytes.map(|bytes| (bytes, self as &dyn Entry)))
+    }
+
+    /// In a `Multi-Release: true` jar the highest `META-INF/versions/N/name`
+    /// with `N` up to `release` wins over the base entry, for classes and
+    /// other resources alike.
+    fn read_resource(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        self.archive()?.read(name)
+    }
+
+    fn packages(&self) -> io::Result<Option<Vec<String>>> {
+        Ok(Some(self.archive()?.packages()))
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::class_path::Entry;
+    use crate::classpath::zip_entry::ZipEntry;
+    use std::path::PathBuf;
+
+    #[test]
+    fn should_read_class_from_jar() {
+        let entry = ZipEntry::new(PathBuf::from("testdata/java8/jar/hello.jar"));
+        let (bytes, source) = entry.read_class("hello/HelloWorld.class").unwrap().unwrap();
+        assert_eq!(546, bytes.len());
+        assert_eq!(entry.path(), source.path());
+
+        assert!(entry.read_class("HelloWorld.class").unwrap().is_none());
+    }
+
+    #[test]
+    fn should_fail_on_missing_archive() {
+        let entry = ZipEntry::new(PathBuf::from("testdata/java8/jar/missing.jar"));
+        assert!(entry.read_class("hello/HelloWorld.class").is_err());
+    }
+
+    /// the `release N` constant of the `mr/Version` served
+    fn served_release(entry: &ZipEntry) -> String {
+        let (bytes, _) = entry.read_class("mr/Version.class").unwrap().unwrap();
+        let text = String::from_utf8_lossy(&bytes).to_string();
+        let releases = ["release 8", "release 11", "release 17", "release 21"];
+        let release = releases.iter().find(|release| text.contains(*release));
+        String::from(*release.unwrap())
+    }
+
+    #[test]
+    fn should_prefer_highest_version_up_to_release() {
+        let path = PathBuf::from("testdata/java8/jar/multi-release.jar");
+        assert_eq!("release 17", served_release(&ZipEntry::new(path.clone())));
+        assert_eq!(
+            "release 11",
+            served_release(&ZipEntry::with_release(path.clone(), 16))
+        );
+        assert_eq!(
+            "release 8",
+            served_release(&ZipEntry::with_release(path, 8))
+        );
+    }
+
+    #[test]
+    fn should_ignore_versions_without_multi_release_manifest() {
+        let path = PathBuf::from("testdata/java8/jar/not-multi-release.jar");
+        assert_eq!("release 8", served_release(&ZipEntry::new(path)));
     }
 }
@@ -1,4 +1,5 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
 use crate::rtda::frame::Frame;
 use crate::rtda::heap::slot::{EmptySlot, IntSlot, Slot};
@@ -9,9 +10,13 @@ pub struct IConst {
 }
 
 impl InstructionExec for IConst {
-    fn execute(&mut self, _frame: &mut Frame) {}
+    fn execute(&mut self, frame: &mut Frame) {
+        frame.operand_stack.push(self.k.clone());
+    }
 
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {}
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
 }
 
 pub struct ConstNull {}
@@ -0,0 +1,51 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::slot::IntSlot;
+
+/// `bipush`, pushes a byte operand as an `int`.
+#[derive(Clone, Default)]
+pub struct BIPush {
+    val: i8,
+}
+
+impl BIPush {
+    pub fn new() -> BIPush {
+        BIPush { val: 0 }
+    }
+}
+
+impl InstructionExec for BIPush {
+    fn execute(&mut self, frame: &mut Frame) {
+        frame.operand_stack.push(IntSlot::new(self.val as i32));
+    }
+
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.val = reader.read_u8()? as i8;
+        Ok(())
+    }
+}
+
+/// `sipush`, pushes a short operand as an `int`.
+#[derive(Clone, Default)]
+pub struct SIPush {
+    val: i16,
+}
+
+impl SIPush {
+    pub fn new() -> SIPush {
+        SIPush { val: 0 }
+    }
+}
+
+impl InstructionExec for SIPush {
+    fn execute(&mut self, frame: &mut Frame) {
+        frame.operand_stack.push(IntSlot::new(self.val as i32));
+    }
+
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.val = reader.read_u16()? as i16;
+        Ok(())
+    }
+}
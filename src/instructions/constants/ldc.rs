@@ -1,7 +1,9 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
 use crate::rtda::frame::Frame;
 use crate::rtda::heap::j_constant::JConstant;
+use crate::rtda::heap::slot::{EmptySlot, IntSlot, Slot};
 
 #[derive(Clone)]
 pub struct LDC {
@@ -16,24 +18,17 @@ impl LDC {
 
 impl InstructionExec for LDC {
     fn execute(&mut self, frame: &mut Frame) {
-        let cp = frame.clone().get_constant_pool();
-        let option = cp.get(self.index);
-        if let Some(entry) = option {
-            match entry {
-                JConstant::String(str) => {
-                    println!("String -> {:?}", str.go_str);
-                }
-                JConstant::ConstantInfo(entry) => {
-                    println!("{:?}", entry);
-                }
-                _ => {
-                    println!("{:?}", entry);
-                }
-            }
-        }
+        let value = match frame.constant(self.index) {
+            Some(JConstant::Integer { val }) => IntSlot::new(val),
+            Some(JConstant::Float { val }) => Slot::from_float(val),
+            // there are no `String` or `Class` objects yet, they load as `null`
+            _ => EmptySlot::new(),
+        };
+        frame.operand_stack.push(value);
     }
 
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {
-        self.index = _reader.read_u8() as usize;
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = _reader.read_u8()? as usize;
+        Ok(())
     }
 }
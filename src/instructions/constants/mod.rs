@@ -1,2 +1,3 @@
 pub mod i_const;
+pub mod ipush;
 pub mod ldc;
@@ -1,4 +1,5 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
 use crate::rtda::frame::Frame;
 
@@ -13,5 +14,7 @@ impl IReturn {
 
 impl InstructionExec for IReturn {
     fn execute(&mut self, _frame: &mut Frame) {}
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {}
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
 }
@@ -1,21 +1,23 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
 use crate::instructions::instruction_factory::{get_instruction, NullOperandsInstruction};
+use crate::instructions::opcode;
 
 #[derive(Clone)]
 pub struct Decode {
     pub ins: Box<dyn InstructionExec>,
 }
 
-pub fn decode_instruction(reader: &mut ClassFileStream) -> Decode {
-    let ins = reader.read_u8();
+pub fn decode_instruction(reader: &mut ClassFileStream) -> Result<Decode, ClassFormatError> {
+    let ins = reader.read_u8()?;
     let mut instruction = get_instruction(ins);
-    instruction.fetch_operands(reader);
+    instruction.fetch_operands(reader)?;
 
-    Decode { ins: instruction }
+    Ok(Decode { ins: instruction })
 }
 
-pub fn decoder(code: Vec<u8>) -> Vec<Decode> {
+pub fn decoder(code: Vec<u8>) -> Result<Vec<Decode>, ClassFormatError> {
     let mut vec: Vec<Decode> = Vec::with_capacity(code.len());
     let mut reader = ClassFileStream::new(code.clone());
 
@@ -25,11 +27,250 @@ pub fn decoder(code: Vec<u8>) -> Vec<Decode> {
         });
     }
 
-    while reader.current.clone() < code.len() {
-        let current = reader.current.clone();
-        let instruction = decode_instruction(&mut reader);
-        vec[current] = instruction;
+    // instructions not executed yet still have their operands skipped
+    for instruction in decode_bytecode(&code)? {
+        let current = instruction.pc as usize;
+        reader.current = current;
+        vec[current] = decode_instruction(&mut reader)?;
     }
 
-    vec
+    Ok(vec)
+}
+
+/// Operands of an instruction as stored in the code array. Branch and switch
+/// targets are already resolved to absolute pcs.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operands {
+    None,
+    Byte(i8),
+    Short(i16),
+    Local(u16),
+    ConstantIndex(u16),
+    InvokeInterface {
+        index: u16,
+        count: u8,
+    },
+    InvokeDynamic {
+        index: u16,
+    },
+    MultiANewArray {
+        index: u16,
+        dimensions: u8,
+    },
+    IInc {
+        index: u16,
+        value: i16,
+    },
+    Branch(u32),
+    NewArray(u8),
+    TableSwitch {
+        default: u32,
+        low: i32,
+        high: i32,
+        targets: Vec<u32>,
+    },
+    LookupSwitch {
+        default: u32,
+        pairs: Vec<(i32, u32)>,
+    },
+}
+
+/// One instruction of a code array, for printing rather than executing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BytecodeInstruction {
+    pub pc: u32,
+    pub opcode: u8,
+    // set for the instruction following a `wide` prefix
+    pub wide: bool,
+    pub operands: Operands,
+}
+
+/// Splits a code array into instructions with their operands, unlike
+/// `decoder` this understands the layout of every opcode.
+pub fn decode_bytecode(code: &[u8]) -> Result<Vec<BytecodeInstruction>, ClassFormatError> {
+    let mut reader = ClassFileStream::new(code.to_vec());
+    let mut instructions = vec![];
+    while reader.current < code.len() {
+        instructions.push(decode_bytecode_instruction(&mut reader)?);
+    }
+    Ok(instructions)
+}
+
+fn decode_bytecode_instruction(
+    reader: &mut ClassFileStream,
+) -> Result<BytecodeInstruction, ClassFormatError> {
+    let pc = reader.current as u32;
+    let mut opcode = reader.read_u8()?;
+    let wide = opcode == opcode::OpWide;
+    if wide {
+        opcode = reader.read_u8()?;
+    }
+    let branch = |offset: i32| (pc as i64 + offset as i64) as u32;
+
+    let operands = match opcode {
+        opcode::OpBIPush => Operands::Byte(reader.read_u8()? as i8),
+        opcode::OpSIPush => Operands::Short(reader.read_u16()? as i16),
+        opcode::OpLDC => Operands::ConstantIndex(reader.read_u8()? as u16),
+        opcode::OpLDCw
+        | opcode::OpLDC2w
+        | opcode::OpGetStatic
+        | opcode::OpPupStatic
+        | opcode::OpGetField
+        | opcode::OpPutField
+        | opcode::OpInvokeVirtual
+        | opcode::OpInvokeSpecial
+        | opcode::OpInvokeStatic
+        | opcode::OpNew
+        | opcode::OpANewArray
+        | opcode::OpCheckCast
+        | opcode::OpInstanceOf => Operands::ConstantIndex(reader.read_u16()?),
+        opcode::OpILoad..=opcode::OpALoad | opcode::OpIStore..=opcode::OpAStore | opcode::OpRET => {
+            if wide {
+                Operands::Local(reader.read_u16()?)
+            } else {
+                Operands::Local(reader.read_u8()? as u16)
+            }
+        }
+        opcode::OpIInc => {
+            if wide {
+                Operands::IInc {
+                    index: reader.read_u16()?,
+                    value: reader.read_u16()? as i16,
+                }
+            } else {
+                Operands::IInc {
+                    index: reader.read_u8()? as u16,
+                    value: reader.read_u8()? as i8 as i16,
+                }
+            }
+        }
+        opcode::OpIfEQ..=opcode::OpJSR | opcode::OpIfNull | opcode::OpIfNonNull => {
+            Operands::Branch(branch(reader.read_u16()? as i16 as i32))
+        }
+        opcode::OpGotoW | opcode::OpJSRw => Operands::Branch(branch(reader.read_u32()? as i32)),
+        opcode::OpTableSwitch => {
+            skip_switch_padding(reader)?;
+            let default = branch(reader.read_u32()? as i32);
+            let low = reader.read_u32()? as i32;
+            let high = reader.read_u32()? as i32;
+            let count = (high as i64 - low as i64 + 1).max(0);
+            let mut targets = vec![];
+            for _i in 0..count {
+                targets.push(branch(reader.read_u32()? as i32));
+            }
+            Operands::TableSwitch {
+                default,
+                low,
+                high,
+                targets,
+            }
+        }
+        opcode::OpLookupSwitch => {
+            skip_switch_padding(reader)?;
+            let default = branch(reader.read_u32()? as i32);
+            let npairs = reader.read_u32()?;
+            let mut pairs = vec![];
+            for _i in 0..npairs {
+                let key = reader.read_u32()? as i32;
+                pairs.push((key, branch(reader.read_u32()? as i32)));
+            }
+            Operands::LookupSwitch { default, pairs }
+        }
+        opcode::OpInvokeInterface => {
+            let index = reader.read_u16()?;
+            let count = reader.read_u8()?;
+            reader.read_u8()?;
+            Operands::InvokeInterface { index, count }
+        }
+        opcode::OpInvokeDynamic => {
+            let index = reader.read_u16()?;
+            reader.read_u16()?;
+            Operands::InvokeDynamic { index }
+        }
+        opcode::OpNewArray => Operands::NewArray(reader.read_u8()?),
+        opcode::OpMultiANewArray => Operands::MultiANewArray {
+            index: reader.read_u16()?,
+            dimensions: reader.read_u8()?,
+        },
+        _ => Operands::None,
+    };
+
+    Ok(BytecodeInstruction {
+        pc,
+        opcode,
+        wide,
+        operands,
+    })
+}
+
+// switch operands start at the next multiple of four from the code start
+fn skip_switch_padding(reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+    while reader.current & 3 != 0 {
+        reader.read_u8()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::instructions::decoder::{decode_bytecode, Operands};
+    use crate::instructions::opcode;
+
+    #[test]
+    fn should_decode_operands_of_every_layout() {
+        let code = vec![
+            0x10, 0xfe, // bipush -2
+            0x84, 0x01, 0xff, // iinc 1, -1
+            0xc4, 0x84, 0x01, 0x2c, 0x00, 0x05, // iinc_w 300, 5
+            0x99, 0xff, 0xf5, // ifeq 0
+            0xb9, 0x00, 0x07, 0x02, 0x00, // invokeinterface #7, 2
+            0xbc, 0x0a, // newarray int
+        ];
+        let instructions = decode_bytecode(&code).unwrap();
+        let operands: Vec<Operands> = instructions.iter().map(|i| i.operands.clone()).collect();
+        assert_eq!(
+            vec![
+                Operands::Byte(-2),
+                Operands::IInc {
+                    index: 1,
+                    value: -1
+                },
+                Operands::IInc {
+                    index: 300,
+                    value: 5
+                },
+                Operands::Branch(0),
+                Operands::InvokeInterface { index: 7, count: 2 },
+                Operands::NewArray(10),
+            ],
+            operands
+        );
+        assert!(instructions[2].wide);
+        assert_eq!(opcode::OpIInc, instructions[2].opcode);
+        assert_eq!(19, instructions[5].pc);
+    }
+
+    #[test]
+    fn should_align_switch_operands() {
+        let code = vec![
+            0x1a, // iload_0
+            0xaa, 0, 0, // tableswitch, padded to pc 4
+            0, 0, 0, 20, // default
+            0, 0, 0, 1, // low
+            0, 0, 0, 2, // high
+            0, 0, 0, 23, 0, 0, 0, 24, // targets
+            0xac, 0x04, 0xac, 0x05, 0xac, 0x03, 0xac,
+        ];
+        let instructions = decode_bytecode(&code).unwrap();
+        assert_eq!(
+            Operands::TableSwitch {
+                default: 21,
+                low: 1,
+                high: 2,
+                targets: vec![24, 25]
+            },
+            instructions[1].operands
+        );
+        assert_eq!(24, instructions[2].pc);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::rtda::frame::Frame;
 
 pub trait InstructionExecClone {
@@ -23,5 +24,5 @@ impl Clone for Box<dyn InstructionExec> {
 
 pub trait InstructionExec: InstructionExecClone {
     fn execute(&mut self, frame: &mut Frame);
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream);
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError>;
 }
@@ -1,13 +1,22 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::constants::i_const::{ConstInt, ConstNull};
+use crate::instructions::constants::ipush::{BIPush, SIPush};
 use crate::instructions::constants::ldc::LDC;
 use crate::instructions::control::i_return::IReturn;
 use crate::instructions::exec::InstructionExec;
 use crate::instructions::loads::load_n::LoadN;
 use crate::instructions::opcode;
+use crate::instructions::refs::array_length::ArrayLength;
 use crate::instructions::refs::get_static::GetStatic;
+use crate::instructions::refs::invoke_interface::InvokeInterface;
 use crate::instructions::refs::invoke_special::InvokeSpecial;
+use crate::instructions::refs::invoke_static::InvokeStatic;
 use crate::instructions::refs::invoke_virtual::InvokeVirtual;
+use crate::instructions::refs::new::NewObject;
+use crate::instructions::refs::put_static::PutStatic;
+use crate::instructions::stack::dup::Dup;
+use crate::instructions::stack::pop::Pop;
 use crate::rtda::frame::Frame;
 
 #[derive(Clone, Debug)]
@@ -17,18 +26,30 @@ pub enum Instruction {
     OpIConstM1(),
     OpIConst0(),
     OpIConst1(),
+    OpIConst2(),
+    OpIConst3(),
+    OpIConst4(),
+    OpIConst5(),
+    OpBIPush(),
+    OpSIPush(),
     OpLDC(),
     OpALoad0(),
     OpInvokeSpecial(),
     OpReturn(),
     OpGetStatic(),
+    OpPutStatic(),
+    OpInvokeStatic(),
+    OpNew(),
+    OpInvokeInterface(),
 }
 
 #[derive(Clone)]
 pub struct NullOperandsInstruction {}
 impl InstructionExec for NullOperandsInstruction {
     fn execute(&mut self, _frame: &mut Frame) {}
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {}
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -44,7 +65,9 @@ impl InstructionExec for NoOperandsInstruction {
     fn execute(&mut self, _frame: &mut Frame) {
         println!("NoOperandsInstruction");
     }
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {}
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
 }
 
 pub fn get_instruction(ins: u8) -> Box<dyn InstructionExec> {
@@ -54,12 +77,38 @@ pub fn get_instruction(ins: u8) -> Box<dyn InstructionExec> {
         opcode::OpIConstM1 => Box::new(ConstInt::new(-1)),
         opcode::OpIConst0 => Box::new(ConstInt::new(0)),
         opcode::OpIConst1 => Box::new(ConstInt::new(1)),
+        opcode::OpIConst2 => Box::new(ConstInt::new(2)),
+        opcode::OpIConst3 => Box::new(ConstInt::new(3)),
+        opcode::OpIConst4 => Box::new(ConstInt::new(4)),
+        opcode::OpIConst5 => Box::new(ConstInt::new(5)),
+        opcode::OpBIPush => Box::new(BIPush::new()),
+        opcode::OpSIPush => Box::new(SIPush::new()),
         opcode::OpLDC => Box::new(LDC::new()),
-        opcode::OpALoad0 => Box::new(LoadN::new(0, false)),
+        opcode::OpILoad0 | opcode::OpFLoad0 | opcode::OpALoad0 => Box::new(LoadN::new(0, false)),
+        opcode::OpILoad1 | opcode::OpFLoad1 | opcode::OpALoad1 => Box::new(LoadN::new(1, false)),
+        opcode::OpILoad2 | opcode::OpFLoad2 | opcode::OpALoad2 => Box::new(LoadN::new(2, false)),
+        opcode::OpILoad3 | opcode::OpFLoad3 | opcode::OpALoad3 => Box::new(LoadN::new(3, false)),
+        opcode::OpLLoad0 | opcode::OpDLoad0 => Box::new(LoadN::new(0, true)),
+        opcode::OpLLoad1 | opcode::OpDLoad1 => Box::new(LoadN::new(1, true)),
+        opcode::OpLLoad2 | opcode::OpDLoad2 => Box::new(LoadN::new(2, true)),
+        opcode::OpLLoad3 | opcode::OpDLoad3 => Box::new(LoadN::new(3, true)),
+        opcode::OpPop => Box::new(Pop::new()),
+        opcode::OpDup => Box::new(Dup::new()),
         opcode::OpInvokeSpecial => Box::new(InvokeSpecial::new()),
         opcode::OpInvokeVirtual => Box::new(InvokeVirtual::new()),
-        opcode::OpReturn => Box::new(IReturn::new()),
+        // the return value, if any, is left on top of the operand stack
+        opcode::OpIReturn
+        | opcode::OpLReturn
+        | opcode::OpFReturn
+        | opcode::OpDReturn
+        | opcode::OpAReturn
+        | opcode::OpReturn => Box::new(IReturn::new()),
         opcode::OpGetStatic => Box::new(GetStatic::new()),
+        opcode::OpPupStatic => Box::new(PutStatic::new()),
+        opcode::OpInvokeStatic => Box::new(InvokeStatic::new()),
+        opcode::OpNew => Box::new(NewObject::new()),
+        opcode::OpInvokeInterface => Box::new(InvokeInterface::new()),
+        opcode::OpArrayLength => Box::new(ArrayLength::new()),
         _ => Box::new(NoOperandsInstruction::new()),
     }
 }
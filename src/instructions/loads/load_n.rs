@@ -1,10 +1,12 @@
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
 use crate::rtda::frame::Frame;
 
 #[derive(Clone)]
 pub struct LoadN {
     n: usize,
+    /// the local is a `long` or `double`, held by slots `n` and `n + 1`
     d: bool,
 }
 
@@ -15,7 +17,15 @@ impl LoadN {
 }
 
 impl InstructionExec for LoadN {
-    fn execute(&mut self, _frame: &mut Frame) {}
+    fn execute(&mut self, frame: &mut Frame) {
+        frame.operand_stack.push(frame.local_vars.get(self.n));
+        // the second slot of a `long` or `double`
+        if self.d {
+            frame.operand_stack.push(frame.local_vars.get(self.n + 1));
+        }
+    }
 
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {}
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
 }
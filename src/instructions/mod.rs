@@ -6,3 +6,4 @@ pub mod instruction_factory;
 pub mod loads;
 pub mod opcode;
 pub mod refs;
+pub mod stack;
@@ -0,0 +1,30 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::instructions::refs::invoke::NULL_POINTER_EXCEPTION;
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::slot::IntSlot;
+
+#[derive(Clone, Default)]
+pub struct ArrayLength {}
+
+impl ArrayLength {
+    pub fn new() -> ArrayLength {
+        ArrayLength {}
+    }
+}
+
+impl InstructionExec for ArrayLength {
+    fn execute(&mut self, frame: &mut Frame) {
+        let array = frame.operand_stack.pop().and_then(|slot| slot.reference());
+        // the verifier makes sure anything else is an array
+        match array.and_then(|array| array.array_length()) {
+            Some(length) => frame.operand_stack.push(IntSlot::new(length as i32)),
+            None => frame.throw(String::from(NULL_POINTER_EXCEPTION)),
+        }
+    }
+
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
+}
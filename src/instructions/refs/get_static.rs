@@ -1,46 +1,72 @@
+use std::rc::Rc;
+
 use crate::instructions::exec::InstructionExec;
 use crate::rtda::frame::Frame;
 
 use crate::classfile::class_file_stream::ClassFileStream;
-use crate::classfile::constant_member_ref::ConstantMemberRef;
-use crate::rtda::heap::instanced_klass::InstanceKlass;
-use crate::rtda::heap::j_constant::{JConstant, JField};
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::rtda::heap::class_loader::{ClassLoader, INCOMPATIBLE_CLASS_CHANGE_ERROR};
+use crate::rtda::heap::instanced_klass::{InstanceKlass, ResolvedField};
+use crate::rtda::heap::slot::EmptySlot;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct GetStatic {
     pub index: usize,
-    pub field: Box<JField>,
 }
 
 impl GetStatic {
     pub fn new() -> GetStatic {
-        let member_ref = ConstantMemberRef {
-            class: Box::new(InstanceKlass::new()),
-            class_name: "".to_string(),
-            name: "".to_string(),
-            descriptor: "".to_string(),
-        };
-        GetStatic {
-            index: 0,
-            field: Box::new(JField { member_ref }),
-        }
+        GetStatic { index: 0 }
     }
 }
 
 impl InstructionExec for GetStatic {
     fn execute(&mut self, frame: &mut Frame) {
-        let cp = frame.clone().get_constant_pool();
-        let option = cp.get(self.index);
-        match option {
-            None => {}
-            Some(entry) => match entry {
-                JConstant::ConstantField(field) => self.field = Box::from(field.clone()),
-                _ => {}
-            },
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        let initialized =
+            resolve_static_field(&frame.class_loader(), &klass, self.index).and_then(|field| {
+                frame.initialize_class(&field.klass)?;
+                Ok(field)
+            });
+        match initialized {
+            Ok(field) => {
+                let value = field.klass.static_vars.borrow()[field.field().slot].clone();
+                frame.operand_stack.push(value);
+                // the second slot of a `long` or `double`
+                if field.field().is_wide() {
+                    frame.operand_stack.push(EmptySlot::new());
+                }
+            }
+            Err(exception) => frame.throw(exception),
         }
     }
 
-    fn fetch_operands(&mut self, reader: &mut ClassFileStream) {
-        self.index = reader.read_u16() as usize;
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        Ok(())
+    }
+}
+
+/// Resolves the field reference `index` of `from`, which has to name a
+/// static field. A failure is the exception to throw.
+pub(crate) fn resolve_static_field(
+    loader: &ClassLoader,
+    from: &Rc<InstanceKlass>,
+    index: usize,
+) -> Result<ResolvedField, String> {
+    let field = loader
+        .resolve_field(from, index)
+        .map_err(|cause| cause.exception())?;
+    if !field.field().is_static() {
+        return Err(format!(
+            "{}: Expected static field {}.{}",
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            field.klass.klass_name.replace('/', "."),
+            field.field().name
+        ));
     }
+    Ok(field)
 }
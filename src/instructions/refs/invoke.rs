@@ -0,0 +1,181 @@
+//! Method invocation shared by the `invoke*` instructions: the arguments
+//! move from the operand stack of the caller to the local variables of a
+//! new frame, and the return value moves back.
+
+use std::rc::Rc;
+
+use crate::instructions::refs::invoke_static::method_name;
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::class_loader::{ABSTRACT_METHOD_ERROR, INCOMPATIBLE_CLASS_CHANGE_ERROR};
+use crate::rtda::heap::j_method::JMethod;
+use crate::rtda::heap::j_object::JObject;
+use crate::rtda::heap::slot::Slot;
+use crate::rtda::native::find_native_method;
+use crate::rtda::thread::{execute_method, VmExit};
+
+pub(crate) const NULL_POINTER_EXCEPTION: &str = "java.lang.NullPointerException";
+
+/// Pops the arguments of `method` off the operand stack of `frame`, in
+/// the order they were pushed, the receiver of an instance method first.
+pub(crate) fn pop_args(frame: &mut Frame, method: &JMethod) -> Result<Vec<Slot>, String> {
+    let count = method.arg_slot_count();
+    let mut args = Vec::with_capacity(count);
+    for _i in 0..count {
+        match frame.operand_stack.pop() {
+            Some(arg) => args.push(arg),
+            None => {
+                return Err(format!(
+                    "java.lang.InternalError: the arguments of {} are not on the operand stack",
+                    method_name(method)
+                ))
+            }
+        }
+    }
+    args.reverse();
+    Ok(args)
+}
+
+/// The object `args` start with, which instance methods are invoked on.
+pub(crate) fn receiver(args: &[Slot]) -> Result<Rc<JObject>, String> {
+    match args.first().and_then(|receiver| receiver.reference()) {
+        Some(receiver) => Ok(receiver),
+        None => Err(String::from(NULL_POINTER_EXCEPTION)),
+    }
+}
+
+/// The method selected for `resolved` (JVMS 5.4.6), which has to be one
+/// that can run.
+pub(crate) fn expect_selected(
+    selected: Option<Rc<JMethod>>,
+    resolved: &JMethod,
+) -> Result<Rc<JMethod>, String> {
+    let selected = match selected {
+        Some(selected) => selected,
+        None => {
+            return Err(format!(
+                "{}: {}",
+                ABSTRACT_METHOD_ERROR,
+                method_name(resolved)
+            ))
+        }
+    };
+    if let Some(conflict) = &selected.conflict {
+        return Err(format!("{}: {}", INCOMPATIBLE_CLASS_CHANGE_ERROR, conflict));
+    }
+    if selected.access_flags.is_abstract() {
+        return Err(format!(
+            "{}: {}",
+            ABSTRACT_METHOD_ERROR,
+            method_name(&selected)
+        ));
+    }
+    Ok(selected)
+}
+
+/// Runs `method` in a new frame on the thread of `frame`, its local
+/// variables starting with `args`. The return value is pushed onto the
+/// operand stack of `frame`, and an exit or exception the method ends with
+/// carries over to the thread of `frame`. Methods the VM implements, see
+/// `find_native_method`, run in `frame` instead.
+pub(crate) fn invoke_method(frame: &mut Frame, method: &Rc<JMethod>, args: Vec<Slot>) {
+    let native = method.klass().and_then(|klass| {
+        find_native_method(&klass.klass_name, &method.name, &method.descriptor.text)
+    });
+    if let Some(native) = native {
+        return native(frame, args);
+    }
+    if method.access_flags.is_native() {
+        frame.throw(format!(
+            "java.lang.UnsatisfiedLinkError: {}",
+            method_name(method)
+        ));
+        return;
+    }
+    let mut callee = frame.thread.borrow().new_frame(Rc::clone(method));
+    for (index, arg) in args.into_iter().enumerate() {
+        callee.local_vars.set(index, arg);
+    }
+    if let Err(cause) = execute_method(&mut callee, method.method_data.code.clone()) {
+        frame.throw(format!("java.lang.ClassFormatError: {}", cause));
+        return;
+    }
+
+    let exit = callee.thread.borrow_mut().exit.take();
+    match exit {
+        Some(VmExit::Exit(status)) => frame.exit(status),
+        Some(VmExit::UncaughtException(exception)) => frame.throw(exception),
+        Some(VmExit::Normal) | None => {
+            let mut value = vec![];
+            for _i in 0..method.descriptor.return_type.slot_count() {
+                match callee.operand_stack.pop() {
+                    Some(slot) => value.push(slot),
+                    None => {
+                        frame.throw(format!(
+                            "java.lang.InternalError: {} left no return value",
+                            method_name(method)
+                        ));
+                        return;
+                    }
+                }
+            }
+            for slot in value.into_iter().rev() {
+                frame.operand_stack.push(slot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::class_path::ClassPath;
+    use crate::rtda::heap::runtime::Runtime;
+    use crate::rtda::thread::{execute_method, Thread, VmExit};
+
+    // runs the static method `name` of `Calls`, which returns an `int`
+    fn call(name: &str) -> Result<i32, String> {
+        let class_path = ClassPath::parse(
+            Some(String::from("testdata/jre")),
+            String::from("testdata/methods"),
+        );
+        let thread = Thread::new(Runtime::new(class_path.unwrap()));
+        let calls = thread.runtime.boot_loader.load_class("Calls").unwrap();
+        thread.runtime.boot_loader.link_class(&calls).unwrap();
+        let method = calls.find_declared_method(name, "()I").unwrap();
+
+        let mut frame = thread.new_frame(method.clone());
+        execute_method(&mut frame, method.method_data.code.clone()).unwrap();
+        let exit = frame.thread.borrow_mut().exit.take();
+        match exit {
+            Some(VmExit::UncaughtException(exception)) => Err(exception),
+            None => Ok(frame.operand_stack.pop().unwrap().int()),
+            exit => panic!("unexpected {:?}", exit),
+        }
+    }
+
+    #[test]
+    fn should_dispatch_on_the_class_of_the_receiver() {
+        // Host implements Greeter and LoudGreeter, which overrides greet()
+        assert_eq!(Ok(2), call("virtualGreet"));
+        assert_eq!(Ok(2), call("interfaceGreet"));
+    }
+
+    #[test]
+    fn should_throw_when_no_method_can_be_selected() {
+        assert_eq!(
+            Err(String::from("java.lang.NullPointerException")),
+            call("nullGreet")
+        );
+        assert_eq!(
+            Err(String::from(
+                "java.lang.AbstractMethodError: Quiet.greet()I"
+            )),
+            call("mutedGreet")
+        );
+        assert_eq!(
+            Err(String::from(
+                "java.lang.IncompatibleClassChangeError: Conflicting default methods: Polite.hello Rude.hello"
+            )),
+            call("mixedHello")
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use std::rc::Rc;
+
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::instructions::refs::invoke::{expect_selected, invoke_method, pop_args, receiver};
+use crate::instructions::refs::invoke_virtual::resolve_instance_method;
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::class_loader::INCOMPATIBLE_CLASS_CHANGE_ERROR;
+
+#[derive(Clone, Default)]
+pub struct InvokeInterface {
+    pub index: usize,
+    /// the slots the arguments and the receiver take
+    pub count: u8,
+}
+
+impl InvokeInterface {
+    pub fn new() -> InvokeInterface {
+        InvokeInterface { index: 0, count: 0 }
+    }
+}
+
+impl InstructionExec for InvokeInterface {
+    fn execute(&mut self, frame: &mut Frame) {
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        // the method to run is selected from the itable of the receiver's
+        // class, which has to implement the interface
+        let selected =
+            resolve_instance_method(&frame.class_loader(), &klass, self.index).and_then(|method| {
+                let args = pop_args(frame, &method)?;
+                let receiver = receiver(&args)?;
+                let class = receiver.klass();
+                let interface = method
+                    .klass()
+                    .filter(|owner| owner.access_flags.is_interface());
+                if let Some(interface) = interface {
+                    let implemented = class.superinterfaces();
+                    if !implemented
+                        .iter()
+                        .any(|found| Rc::ptr_eq(found, &interface))
+                    {
+                        return Err(format!(
+                            "{}: Class {} does not implement the requested interface {}",
+                            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+                            class.klass_name.replace('/', "."),
+                            interface.klass_name.replace('/', ".")
+                        ));
+                    }
+                }
+                let selected = class.select_interface_method(&method);
+                Ok((expect_selected(selected, &method)?, args))
+            });
+        match selected {
+            Ok((method, args)) => invoke_method(frame, &method, args),
+            Err(exception) => frame.throw(exception),
+        }
+    }
+
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        self.count = reader.read_u8()?;
+        // always zero
+        reader.read_u8()?;
+        Ok(())
+    }
+}
@@ -1,9 +1,15 @@
+use std::rc::Rc;
+
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
+use crate::instructions::refs::invoke::{expect_selected, invoke_method, pop_args, receiver};
+use crate::instructions::refs::invoke_virtual::resolve_instance_method;
 use crate::rtda::frame::Frame;
-use crate::rtda::heap::j_constant::JConstant;
+use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::rtda::heap::j_method::JMethod;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct InvokeSpecial {
     pub index: usize,
 }
@@ -16,22 +22,44 @@ impl InvokeSpecial {
 
 impl InstructionExec for InvokeSpecial {
     fn execute(&mut self, frame: &mut Frame) {
-        let cp = frame.clone().get_constant_pool();
-        let option = cp.get(self.index);
-        if let Some(entry) = option {
-            match entry {
-                JConstant::ConstantMethodRef(str) => {
-                    println!("descriptor -> {:?}", str.member_ref.descriptor);
-                    println!("name -> {:?}", str.member_ref.name);
-                }
-                _ => {
-                    println!("{:?}", entry);
-                }
-            }
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        let selected =
+            resolve_instance_method(&frame.class_loader(), &klass, self.index).and_then(|method| {
+                let args = pop_args(frame, &method)?;
+                receiver(&args)?;
+                let selected = select_special_method(&klass, &method);
+                Ok((expect_selected(selected, &method)?, args))
+            });
+        match selected {
+            Ok((method, args)) => invoke_method(frame, &method, args),
+            Err(exception) => frame.throw(exception),
         }
     }
 
-    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) {
-        self.index = _reader.read_u16() as usize;
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        Ok(())
+    }
+}
+
+// `super.m()` runs the method the superclass of the calling class has
+// (JVMS 6.5 invokespecial), constructors and private methods the
+// resolved one
+fn select_special_method(from: &Rc<InstanceKlass>, method: &Rc<JMethod>) -> Option<Rc<JMethod>> {
+    let owner = match method.klass() {
+        Some(owner) => owner,
+        None => return Some(Rc::clone(method)),
+    };
+    let super_call = method.is_virtual()
+        && !owner.access_flags.is_interface()
+        && !Rc::ptr_eq(&owner, from)
+        && from.is_subclass_of(&owner);
+    if !super_call {
+        return Some(Rc::clone(method));
     }
+    from.super_klass()?
+        .find_method(&method.name, &method.descriptor.text)
 }
@@ -0,0 +1,73 @@
+use std::rc::Rc;
+
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::instructions::refs::invoke::{invoke_method, pop_args};
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::class_loader::INCOMPATIBLE_CLASS_CHANGE_ERROR;
+use crate::rtda::heap::j_method::JMethod;
+
+#[derive(Clone, Default)]
+pub struct InvokeStatic {
+    pub index: usize,
+}
+
+impl InvokeStatic {
+    pub fn new() -> InvokeStatic {
+        InvokeStatic { index: 0 }
+    }
+}
+
+impl InstructionExec for InvokeStatic {
+    fn execute(&mut self, frame: &mut Frame) {
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        // the class declaring the method is initialized before the call
+        let initialized = frame
+            .class_loader()
+            .resolve_method(&klass, self.index)
+            .map_err(|cause| cause.exception())
+            .and_then(expect_static_method)
+            .and_then(|method| {
+                if let Some(owner) = method.klass() {
+                    frame.initialize_class(&owner)?;
+                }
+                Ok(method)
+            });
+        match initialized {
+            Ok(method) => match pop_args(frame, &method) {
+                Ok(args) => invoke_method(frame, &method, args),
+                Err(exception) => frame.throw(exception),
+            },
+            Err(exception) => frame.throw(exception),
+        }
+    }
+
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        Ok(())
+    }
+}
+
+fn expect_static_method(method: Rc<JMethod>) -> Result<Rc<JMethod>, String> {
+    if method.access_flags.is_static() {
+        return Ok(method);
+    }
+    Err(format!(
+        "{}: Expected static method {}",
+        INCOMPATIBLE_CLASS_CHANGE_ERROR,
+        method_name(&method)
+    ))
+}
+
+/// `java.lang.String.valueOf(I)Ljava/lang/String;` for messages.
+pub(crate) fn method_name(method: &JMethod) -> String {
+    let klass_name = match method.klass() {
+        Some(klass) => klass.klass_name.replace('/', "."),
+        None => String::new(),
+    };
+    format!("{}.{}{}", klass_name, method.name, method.descriptor.text)
+}
@@ -1,42 +1,68 @@
+use std::rc::Rc;
+
 use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::exec::InstructionExec;
+use crate::instructions::refs::invoke::{expect_selected, invoke_method, pop_args, receiver};
+use crate::instructions::refs::invoke_static::method_name;
 use crate::rtda::frame::Frame;
-use crate::rtda::heap::j_constant::{JConstant, JMethodRef};
+use crate::rtda::heap::class_loader::{ClassLoader, INCOMPATIBLE_CLASS_CHANGE_ERROR};
+use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::rtda::heap::j_method::JMethod;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct InvokeVirtual {
     pub index: usize,
-    pub method_ref: Option<JMethodRef>,
 }
 
 impl InvokeVirtual {
     pub fn new() -> InvokeVirtual {
-        InvokeVirtual {
-            index: 0,
-            method_ref: None,
-        }
+        InvokeVirtual { index: 0 }
     }
 }
 
 impl InstructionExec for InvokeVirtual {
     fn execute(&mut self, frame: &mut Frame) {
-        let cp = frame.clone().get_constant_pool();
-        let option = cp.get(self.index);
-        if let Some(entry) = option {
-            match entry {
-                JConstant::ConstantMethodRef(method) => {
-                    self.method_ref = Some(method.clone());
-                    println!("descriptor -> {:?}", method.member_ref.descriptor);
-                    println!("name -> {:?}", method.member_ref.name);
-                }
-                _ => {
-                    println!("{:?}", entry);
-                }
-            }
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        // the method to run is selected by the class of the receiver
+        let selected =
+            resolve_instance_method(&frame.class_loader(), &klass, self.index).and_then(|method| {
+                let args = pop_args(frame, &method)?;
+                let receiver = receiver(&args)?;
+                let selected = receiver.klass().select_virtual_method(&method);
+                Ok((expect_selected(selected, &method)?, args))
+            });
+        match selected {
+            Ok((method, args)) => invoke_method(frame, &method, args),
+            Err(exception) => frame.throw(exception),
         }
     }
 
-    fn fetch_operands(&mut self, reader: &mut ClassFileStream) {
-        self.index = reader.read_u16() as usize;
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        Ok(())
+    }
+}
+
+/// Resolves the method reference `index` of `from`, which has to name an
+/// instance method. A failure is the exception to throw.
+pub(crate) fn resolve_instance_method(
+    loader: &ClassLoader,
+    from: &Rc<InstanceKlass>,
+    index: usize,
+) -> Result<Rc<JMethod>, String> {
+    let method = loader
+        .resolve_method(from, index)
+        .map_err(|cause| cause.exception())?;
+    if method.access_flags.is_static() {
+        return Err(format!(
+            "{}: Expecting non-static method {}",
+            INCOMPATIBLE_CLASS_CHANGE_ERROR,
+            method_name(&method)
+        ));
     }
+    Ok(method)
 }
@@ -1,3 +1,9 @@
+pub mod array_length;
 pub mod get_static;
+pub mod invoke;
+pub mod invoke_interface;
 pub mod invoke_special;
+pub mod invoke_static;
 pub mod invoke_virtual;
+pub mod new;
+pub mod put_static;
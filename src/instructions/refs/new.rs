@@ -0,0 +1,57 @@
+use std::rc::Rc;
+
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::j_object::JObject;
+use crate::rtda::heap::slot::Slot;
+
+#[derive(Clone, Default)]
+pub struct NewObject {
+    pub index: usize,
+}
+
+impl NewObject {
+    pub fn new() -> NewObject {
+        NewObject { index: 0 }
+    }
+}
+
+impl InstructionExec for NewObject {
+    fn execute(&mut self, frame: &mut Frame) {
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        // the class is initialized before its first instance is created
+        let initialized = frame
+            .class_loader()
+            .resolve_class(&klass, self.index)
+            .map_err(|cause| cause.exception())
+            .and_then(|class| {
+                if class.access_flags.is_interface() || class.access_flags.is_abstract() {
+                    return Err(format!(
+                        "java.lang.InstantiationError: {}",
+                        class.klass_name.replace('/', ".")
+                    ));
+                }
+                frame.initialize_class(&class)?;
+                Ok(class)
+            });
+        match initialized {
+            Ok(class) => {
+                let object = JObject::new(&class);
+                frame
+                    .operand_stack
+                    .push(Slot::from_reference(Rc::new(object)));
+            }
+            Err(exception) => frame.throw(exception),
+        }
+    }
+
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        Ok(())
+    }
+}
@@ -0,0 +1,65 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::instructions::refs::get_static::resolve_static_field;
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::class_loader::ILLEGAL_ACCESS_ERROR;
+
+#[derive(Clone, Default)]
+pub struct PutStatic {
+    pub index: usize,
+}
+
+impl PutStatic {
+    pub fn new() -> PutStatic {
+        PutStatic { index: 0 }
+    }
+}
+
+impl InstructionExec for PutStatic {
+    fn execute(&mut self, frame: &mut Frame) {
+        let klass = match frame.klass() {
+            Some(klass) => klass,
+            None => return,
+        };
+        let initialized = resolve_static_field(&frame.class_loader(), &klass, self.index)
+            .and_then(|field| {
+                // only the declaring class may set its final fields
+                if field.field().access_flags.is_final() && field.klass.klass_name != klass.klass_name {
+                    return Err(format!(
+                        "{}: Update to static final field {}.{} attempted from a different class ({}) than the field's declaring class",
+                        ILLEGAL_ACCESS_ERROR,
+                        field.klass.klass_name.replace('/', "."),
+                        field.field().name,
+                        klass.klass_name.replace('/', ".")
+                    ));
+                }
+                Ok(field)
+            })
+            .and_then(|field| {
+                frame.initialize_class(&field.klass)?;
+                Ok(field)
+            });
+        let field = match initialized {
+            Ok(field) => field,
+            Err(exception) => return frame.throw(exception),
+        };
+        // a `long` or `double` is held by the first of its two slots
+        if field.field().is_wide() {
+            frame.operand_stack.pop();
+        }
+        match frame.operand_stack.pop() {
+            Some(value) => field.klass.static_vars.borrow_mut()[field.field().slot] = value,
+            None => frame.throw(format!(
+                "java.lang.InternalError: the value of {}.{} is not on the operand stack",
+                field.klass.klass_name.replace('/', "."),
+                field.field().name
+            )),
+        }
+    }
+
+    fn fetch_operands(&mut self, reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        self.index = reader.read_u16()? as usize;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::rtda::frame::Frame;
+
+/// Pushes the top slot again.
+#[derive(Clone, Default)]
+pub struct Dup {}
+
+impl Dup {
+    pub fn new() -> Dup {
+        Dup {}
+    }
+}
+
+impl InstructionExec for Dup {
+    fn execute(&mut self, frame: &mut Frame) {
+        match frame.operand_stack.pop() {
+            Some(slot) => {
+                frame.operand_stack.push(slot.clone());
+                frame.operand_stack.push(slot);
+            }
+            None => frame.throw(String::from(
+                "java.lang.InternalError: dup on an empty operand stack",
+            )),
+        }
+    }
+
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::instructions::exec::InstructionExec;
+use crate::rtda::frame::Frame;
+
+/// Discards the top slot.
+#[derive(Clone, Default)]
+pub struct Pop {}
+
+impl Pop {
+    pub fn new() -> Pop {
+        Pop {}
+    }
+}
+
+impl InstructionExec for Pop {
+    fn execute(&mut self, frame: &mut Frame) {
+        frame.operand_stack.pop();
+    }
+
+    fn fetch_operands(&mut self, _reader: &mut ClassFileStream) -> Result<(), ClassFormatError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,1678 @@
+//! A text dump of a parsed class in the layout of `javap -c -v -p`, so that
+//! the two outputs can be diffed. Declarations are rendered from descriptors:
+//! generic signatures only appear in the `Signature` lines.
+
+use crate::classfile::access_flags::*;
+use crate::classfile::annotations::{Annotation, ElementValue, TargetInfo, TypeAnnotation};
+use crate::classfile::attribute_info::{AttributeInfo, CodeAttribute, ModuleAttribute};
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::classfile::constant_pool::{ConstantPool, CpEntry};
+use crate::classfile::member_info::MemberInfo;
+use crate::classfile::parsed_class::ParsedClass;
+use crate::classfile::stack_map_table::{StackMapFrame, VerificationTypeInfo};
+use crate::instructions::decoder::{decode_bytecode, BytecodeInstruction, Operands};
+use crate::instructions::opcode;
+
+// javap starts comments 40 columns after the indentation of the line
+const COMMENT_COLUMN: usize = 40;
+
+const CLASS_FLAGS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "ACC_PUBLIC"),
+    (ACC_FINAL, "ACC_FINAL"),
+    (ACC_SUPER, "ACC_SUPER"),
+    (ACC_INTERFACE, "ACC_INTERFACE"),
+    (ACC_ABSTRACT, "ACC_ABSTRACT"),
+    (ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+    (ACC_ANNOTATION, "ACC_ANNOTATION"),
+    (ACC_ENUM, "ACC_ENUM"),
+    (ACC_MODULE, "ACC_MODULE"),
+];
+
+const FIELD_FLAGS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "ACC_PUBLIC"),
+    (ACC_PRIVATE, "ACC_PRIVATE"),
+    (ACC_PROTECTED, "ACC_PROTECTED"),
+    (ACC_STATIC, "ACC_STATIC"),
+    (ACC_FINAL, "ACC_FINAL"),
+    (ACC_VOLATILE, "ACC_VOLATILE"),
+    (ACC_TRANSIENT, "ACC_TRANSIENT"),
+    (ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+    (ACC_ENUM, "ACC_ENUM"),
+];
+
+const METHOD_FLAGS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "ACC_PUBLIC"),
+    (ACC_PRIVATE, "ACC_PRIVATE"),
+    (ACC_PROTECTED, "ACC_PROTECTED"),
+    (ACC_STATIC, "ACC_STATIC"),
+    (ACC_FINAL, "ACC_FINAL"),
+    (ACC_SYNCHRONIZED, "ACC_SYNCHRONIZED"),
+    (ACC_BRIDGE, "ACC_BRIDGE"),
+    (ACC_VARARGS, "ACC_VARARGS"),
+    (ACC_NATIVE, "ACC_NATIVE"),
+    (ACC_ABSTRACT, "ACC_ABSTRACT"),
+    (ACC_STRICT, "ACC_STRICT"),
+    (ACC_SYNTHETIC, "ACC_SYNTHETIC"),
+];
+
+const CLASS_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_FINAL, "final"),
+    (ACC_ABSTRACT, "abstract"),
+];
+
+const FIELD_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_PRIVATE, "private"),
+    (ACC_PROTECTED, "protected"),
+    (ACC_STATIC, "static"),
+    (ACC_FINAL, "final"),
+    (ACC_VOLATILE, "volatile"),
+    (ACC_TRANSIENT, "transient"),
+];
+
+const METHOD_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_PRIVATE, "private"),
+    (ACC_PROTECTED, "protected"),
+    (ACC_STATIC, "static"),
+    (ACC_FINAL, "final"),
+    (ACC_SYNCHRONIZED, "synchronized"),
+    (ACC_NATIVE, "native"),
+    (ACC_ABSTRACT, "abstract"),
+    (ACC_STRICT, "strictfp"),
+];
+
+const INNER_CLASS_MODIFIERS: &[(u16, &str)] = &[
+    (ACC_PUBLIC, "public"),
+    (ACC_PRIVATE, "private"),
+    (ACC_PROTECTED, "protected"),
+    (ACC_STATIC, "static"),
+    (ACC_FINAL, "final"),
+    (ACC_ABSTRACT, "abstract"),
+];
+
+const REFERENCE_KINDS: &[&str] = &[
+    "",
+    "REF_getField",
+    "REF_getStatic",
+    "REF_putField",
+    "REF_putStatic",
+    "REF_invokeVirtual",
+    "REF_invokeStatic",
+    "REF_invokeSpecial",
+    "REF_newInvokeSpecial",
+    "REF_invokeInterface",
+];
+
+/// Prints `class` the way `javap -c -v -p` does, without the leading
+/// `Classfile`, `Last modified` and checksum lines which describe the file
+/// rather than the class.
+pub fn disassemble(class: &ParsedClass) -> Result<String, ClassFormatError> {
+    let mut javap = Javap {
+        class,
+        pool: class.constant_pool(),
+        out: String::new(),
+    };
+    javap.print_class()?;
+    Ok(javap.out)
+}
+
+struct Javap<'a> {
+    class: &'a ParsedClass,
+    pool: &'a ConstantPool,
+    out: String,
+}
+
+impl<'a> Javap<'a> {
+    fn line(&mut self, indent: usize, text: &str) {
+        for _i in 0..indent {
+            self.out.push(' ');
+        }
+        // javap trims trailing spaces, e.g. of strings that end with one
+        self.out.push_str(text.trim_end_matches(' '));
+        self.out.push('\n');
+    }
+
+    fn line_with_comment(&mut self, indent: usize, text: &str, comment: &str) {
+        let mut text = String::from(text);
+        while text.len() < COMMENT_COLUMN {
+            text.push(' ');
+        }
+        if !text.ends_with(' ') {
+            text.push(' ');
+        }
+        text.push_str("// ");
+        text.push_str(comment);
+        self.line(indent, &text);
+    }
+
+    fn print_class(&mut self) -> Result<(), ClassFormatError> {
+        let class = self.class;
+        let flags = class.access_flags();
+        for attr in class.attributes() {
+            if let AttributeInfo::SourceFile(source_file) = attr {
+                let text = format!(
+                    "Compiled from \"{}\"",
+                    self.utf8(source_file.source_file_index)
+                );
+                self.line(2, &text);
+            }
+        }
+
+        let declaration = self.class_declaration();
+        self.line(0, &declaration);
+        self.line(2, &format!("minor version: {}", class.minor_version()));
+        self.line(2, &format!("major version: {}", class.major_version()));
+        self.line(2, &format!("flags: {}", flag_names(flags, CLASS_FLAGS)));
+        let this_class = class.this_class_index();
+        let comment = self.quoted_name(this_class);
+        self.line_with_comment(2, &format!("this_class: #{}", this_class), &comment);
+        let super_class = class.super_class_index();
+        if super_class == 0 {
+            self.line(2, "super_class: #0");
+        } else {
+            let comment = self.quoted_name(super_class);
+            self.line_with_comment(2, &format!("super_class: #{}", super_class), &comment);
+        }
+        self.line(
+            2,
+            &format!(
+                "interfaces: {}, fields: {}, methods: {}, attributes: {}",
+                class.interfaces().len(),
+                class.fields().len(),
+                class.methods().len(),
+                class.attributes().len()
+            ),
+        );
+
+        self.print_constant_pool();
+
+        self.line(0, "{");
+        let mut first = true;
+        for field in class.fields() {
+            if !first {
+                self.line(0, "");
+            }
+            first = false;
+            self.print_field(field)?;
+        }
+        for method in class.methods() {
+            if !first {
+                self.line(0, "");
+            }
+            first = false;
+            self.print_method(method)?;
+        }
+        self.line(0, "}");
+
+        self.print_attributes(0, class.attributes(), None)
+    }
+
+    fn class_declaration(&self) -> String {
+        let class = self.class;
+        let flags = class.access_flags();
+        if flags.is_module() {
+            for attr in class.attributes() {
+                if let AttributeInfo::Module(module) = attr {
+                    return format!("module {}", self.module_name(module.module_name_index));
+                }
+            }
+        }
+
+        let mut words: Vec<String> = vec![];
+        for (flag, modifier) in CLASS_MODIFIERS {
+            if flags.contains(*flag) && !(flags.is_interface() && *flag == ACC_ABSTRACT) {
+                words.push(String::from(*modifier));
+            }
+        }
+        words.push(String::from(if flags.is_interface() {
+            "interface"
+        } else {
+            "class"
+        }));
+        words.push(java_name(&self.class_name(class.this_class_index())));
+        let mut declaration = words.join(" ");
+
+        if let Some(signature) = self.signature(class.attributes()) {
+            let mut reader = SignatureReader::new(&signature);
+            declaration.push_str(&type_parameters(&reader.type_parameters()));
+            let super_class = reader.java_type();
+            let mut interfaces = vec![];
+            while !reader.at_end() {
+                interfaces.push(reader.java_type());
+            }
+            if flags.is_interface() {
+                if !interfaces.is_empty() {
+                    declaration.push_str(&format!(" extends {}", interfaces.join(", ")));
+                }
+            } else {
+                declaration.push_str(&format!(" extends {}", super_class));
+                if !interfaces.is_empty() {
+                    declaration.push_str(&format!(" implements {}", interfaces.join(", ")));
+                }
+            }
+            return declaration;
+        }
+
+        let super_name = self.class_name(class.super_class_index());
+        if !flags.is_interface() && !super_name.is_empty() && super_name != "java/lang/Object" {
+            declaration.push_str(&format!(" extends {}", java_name(&super_name)));
+        }
+        if !class.interfaces().is_empty() {
+            let names: Vec<String> = class
+                .interfaces()
+                .iter()
+                .map(|index| java_name(&self.class_name(*index)))
+                .collect();
+            let keyword = if flags.is_interface() {
+                "extends"
+            } else {
+                "implements"
+            };
+            declaration.push_str(&format!(" {} {}", keyword, names.join(",")));
+        }
+        declaration
+    }
+
+    fn print_constant_pool(&mut self) {
+        self.line(0, "Constant pool:");
+        let width = format!("#{}", self.pool.count()).len();
+        for (index, entry) in self.pool.entries().iter().enumerate() {
+            let (kind, args, comment) = match entry {
+                CpEntry::Empty {} => continue,
+                CpEntry::Utf8 { val, .. } => ("Utf8", escape(val), None),
+                CpEntry::Integer { val } => ("Integer", val.to_string(), None),
+                CpEntry::Float { val } => ("Float", java_float(*val), None),
+                CpEntry::Long { val } => ("Long", format!("{}l", val), None),
+                CpEntry::Double { val } => ("Double", java_double(*val), None),
+                CpEntry::Class { idx } => (
+                    "Class",
+                    format!("#{}", idx),
+                    Some(check_name(&self.utf8(*idx))),
+                ),
+                CpEntry::String { idx } => (
+                    "String",
+                    format!("#{}", idx),
+                    Some(escape(&self.utf8(*idx))),
+                ),
+                CpEntry::FieldRef(member_ref) => (
+                    "Fieldref",
+                    member_ref_args(member_ref.class_index, member_ref.name_type_index),
+                    Some(self.member_ref(index as u16, None)),
+                ),
+                CpEntry::MethodRef(member_ref) => (
+                    "Methodref",
+                    member_ref_args(member_ref.class_index, member_ref.name_type_index),
+                    Some(self.member_ref(index as u16, None)),
+                ),
+                CpEntry::InterfaceMethodRef(member_ref) => (
+                    "InterfaceMethodref",
+                    member_ref_args(member_ref.class_index, member_ref.name_type_index),
+                    Some(self.member_ref(index as u16, None)),
+                ),
+                CpEntry::NameAndType { name_idx, type_idx } => (
+                    "NameAndType",
+                    format!("#{}:#{}", name_idx, type_idx),
+                    Some(self.name_and_type(index as u16)),
+                ),
+                CpEntry::MethodHandle { ref_kind, ref_idx } => (
+                    "MethodHandle",
+                    format!("{}:#{}", ref_kind, ref_idx),
+                    Some(self.method_handle(index as u16)),
+                ),
+                // javap puts two spaces in front of method type comments
+                CpEntry::MethodType { desc_idx } => (
+                    "MethodType",
+                    format!("#{}", desc_idx),
+                    Some(format!(" {}", self.utf8(*desc_idx))),
+                ),
+                CpEntry::Dynamic { bsm_idx, nt_idx } => (
+                    "Dynamic",
+                    format!("#{}:#{}", bsm_idx, nt_idx),
+                    Some(format!("#{}:{}", bsm_idx, self.name_and_type(*nt_idx))),
+                ),
+                CpEntry::InvokeDynamic { bsm_idx, nt_idx } => (
+                    "InvokeDynamic",
+                    format!("#{}:#{}", bsm_idx, nt_idx),
+                    Some(format!("#{}:{}", bsm_idx, self.name_and_type(*nt_idx))),
+                ),
+                CpEntry::Module { name_idx } => (
+                    "Module",
+                    format!("#{}", name_idx),
+                    Some(check_name(&self.utf8(*name_idx))),
+                ),
+                CpEntry::Package { name_idx } => (
+                    "Package",
+                    format!("#{}", name_idx),
+                    Some(check_name(&self.utf8(*name_idx))),
+                ),
+            };
+
+            let text = format!(
+                "{:>width$} = {:<18} {}",
+                format!("#{}", index),
+                kind,
+                args,
+                width = width
+            );
+            match comment {
+                Some(comment) => self.line_with_comment(2, &text, &comment),
+                None => self.line(2, &text),
+            }
+        }
+    }
+
+    fn print_field(&mut self, field: &MemberInfo) -> Result<(), ClassFormatError> {
+        let flags = field.access_flags();
+        let mut words = modifiers(flags, FIELD_MODIFIERS);
+        let descriptor = self.utf8(field.descriptor_index());
+        let signature = self.signature(field.attributes());
+        words.push(java_type(signature.as_ref().unwrap_or(&descriptor)));
+        words.push(self.utf8(field.name_index()));
+        self.line(2, &format!("{};", words.join(" ")));
+        self.line(4, &format!("descriptor: {}", descriptor));
+        self.line(4, &format!("flags: {}", flag_names(flags, FIELD_FLAGS)));
+        self.print_attributes(4, field.attributes(), None)
+    }
+
+    fn print_method(&mut self, method: &MemberInfo) -> Result<(), ClassFormatError> {
+        let flags = method.access_flags();
+        let name = self.utf8(method.name_index());
+        let descriptor = self.utf8(method.descriptor_index());
+        let method_type = MethodType::parse(&descriptor);
+
+        let declaration = if name == "<clinit>" {
+            String::from("static {}")
+        } else {
+            let mut words = modifiers(flags, METHOD_MODIFIERS);
+            if self.class.access_flags().is_interface()
+                && !flags.is_static()
+                && !flags.is_abstract()
+                && !flags.is_private()
+            {
+                words.push(String::from("default"));
+            }
+            let generic_type = self
+                .signature(method.attributes())
+                .map(|signature| MethodType::parse(&signature));
+            let java_type = generic_type.as_ref().unwrap_or(&method_type);
+            if !java_type.type_parameters.is_empty() {
+                words.push(type_parameters(&java_type.type_parameters));
+            }
+            let mut params = java_type.parameters.clone();
+            if flags.is_varargs() {
+                if let Some(last) = params.last_mut() {
+                    if last.ends_with("[]") {
+                        let base = last.len() - 2;
+                        last.truncate(base);
+                        last.push_str("...");
+                    }
+                }
+            }
+            let name = if name == "<init>" {
+                java_name(&self.class_name(self.class.this_class_index()))
+            } else {
+                words.push(java_type.return_type.clone());
+                name
+            };
+            words.push(format!("{}({})", name, params.join(", ")));
+            let mut declaration = words.join(" ");
+
+            if !java_type.throws.is_empty() {
+                declaration.push_str(&format!(" throws {}", java_type.throws.join(", ")));
+            } else {
+                for attr in method.attributes() {
+                    if let AttributeInfo::Exceptions(exceptions) = attr {
+                        let names: Vec<String> = exceptions
+                            .exception_index_table
+                            .iter()
+                            .map(|index| java_name(&self.class_name(*index)))
+                            .collect();
+                        declaration.push_str(&format!(" throws {}", names.join(", ")));
+                    }
+                }
+            }
+            declaration
+        };
+
+        self.line(2, &format!("{};", declaration));
+        self.line(4, &format!("descriptor: {}", descriptor));
+        self.line(4, &format!("flags: {}", flag_names(flags, METHOD_FLAGS)));
+
+        // javap counts parameters rather than local variable slots
+        let args_size = method_type.parameters.len() as u16 + if flags.is_static() { 0 } else { 1 };
+        self.print_attributes(4, method.attributes(), Some(args_size))
+    }
+
+    fn print_attributes(
+        &mut self,
+        indent: usize,
+        attributes: &[AttributeInfo],
+        args_size: Option<u16>,
+    ) -> Result<(), ClassFormatError> {
+        for attr in attributes {
+            self.print_attribute(indent, attr, args_size)?;
+        }
+        Ok(())
+    }
+
+    fn print_attribute(
+        &mut self,
+        indent: usize,
+        attr: &AttributeInfo,
+        args_size: Option<u16>,
+    ) -> Result<(), ClassFormatError> {
+        match attr {
+            AttributeInfo::ConstantValue(value) => {
+                let text = match self.pool.get(value.constant_value_index) {
+                    Some(CpEntry::Integer { val }) => format!("int {}", val),
+                    Some(CpEntry::Float { val }) => format!("float {}", java_float(*val)),
+                    Some(CpEntry::Long { val }) => format!("long {}l", val),
+                    Some(CpEntry::Double { val }) => format!("double {}", java_double(*val)),
+                    Some(CpEntry::String { idx }) => format!("String {}", escape(&self.utf8(*idx))),
+                    _ => format!("#{}", value.constant_value_index),
+                };
+                self.line(indent, &format!("ConstantValue: {}", text));
+            }
+            AttributeInfo::Code(code) => self.print_code(indent, code, args_size.unwrap_or(0))?,
+            AttributeInfo::Exceptions(exceptions) => {
+                self.line(indent, "Exceptions:");
+                let names: Vec<String> = exceptions
+                    .exception_index_table
+                    .iter()
+                    .map(|index| java_name(&self.class_name(*index)))
+                    .collect();
+                self.line(indent + 2, &format!("throws {}", names.join(", ")));
+            }
+            AttributeInfo::SourceFile(source_file) => {
+                let text = format!(
+                    "SourceFile: \"{}\"",
+                    self.utf8(source_file.source_file_index)
+                );
+                self.line(indent, &text);
+            }
+            AttributeInfo::LineNumberTable(table) => {
+                self.line(indent, "LineNumberTable:");
+                for entry in &table.line_number_table {
+                    let text = format!("line {}: {}", entry.line_number, entry.start_pc);
+                    self.line(indent + 2, &text);
+                }
+            }
+            AttributeInfo::LocalVariableTable(table)
+            | AttributeInfo::LocalVariableTypeTable(table) => {
+                self.line(indent, &format!("{}:", attr.name()));
+                self.line(indent + 2, "Start  Length  Slot  Name   Signature");
+                for entry in &table.local_variable_table {
+                    let text = format!(
+                        "{:>5} {:>7} {:>5} {:>5}   {}",
+                        entry.start_pc,
+                        entry.length,
+                        entry.index,
+                        self.utf8(entry.name_index),
+                        self.utf8(entry.descriptor_index)
+                    );
+                    self.line(indent + 2, &text);
+                }
+            }
+            AttributeInfo::InnerClasses(inner_classes) => {
+                self.line(indent, "InnerClasses:");
+                for entry in &inner_classes.classes {
+                    let flags = AccessFlags::new(entry.inner_class_access_flags);
+                    let mut words: Vec<String> = vec![];
+                    for (flag, modifier) in INNER_CLASS_MODIFIERS {
+                        if flags.contains(*flag) && !(flags.is_interface() && *flag == ACC_ABSTRACT)
+                        {
+                            words.push(String::from(*modifier));
+                        }
+                    }
+                    let inner = format!("class {}", self.class_name(entry.inner_class_info_index));
+                    let (text, comment) = if entry.inner_name_index == 0 {
+                        (format!("#{};", entry.inner_class_info_index), inner)
+                    } else if entry.outer_class_info_index == 0 {
+                        (
+                            format!(
+                                "#{}= #{};",
+                                entry.inner_name_index, entry.inner_class_info_index
+                            ),
+                            format!("{}={}", self.utf8(entry.inner_name_index), inner),
+                        )
+                    } else {
+                        (
+                            format!(
+                                "#{}= #{} of #{};",
+                                entry.inner_name_index,
+                                entry.inner_class_info_index,
+                                entry.outer_class_info_index
+                            ),
+                            format!(
+                                "{}={} of class {}",
+                                self.utf8(entry.inner_name_index),
+                                inner,
+                                self.class_name(entry.outer_class_info_index)
+                            ),
+                        )
+                    };
+                    words.push(text);
+                    self.line_with_comment(indent + 2, &words.join(" "), &comment);
+                }
+            }
+            AttributeInfo::Synthetic() => self.line(indent, "Synthetic: true"),
+            AttributeInfo::Deprecated() => self.line(indent, "Deprecated: true"),
+            AttributeInfo::EnclosingMethod(enclosing) => {
+                let text = format!(
+                    "EnclosingMethod: #{}.#{}",
+                    enclosing.class_index, enclosing.method_index
+                );
+                let mut comment = java_name(&self.class_name(enclosing.class_index));
+                if let Some(CpEntry::NameAndType { name_idx, .. }) =
+                    self.pool.get(enclosing.method_index)
+                {
+                    comment.push('.');
+                    comment.push_str(&self.utf8(*name_idx));
+                }
+                self.line_with_comment(indent, &text, &comment);
+            }
+            AttributeInfo::Signature(signature) => {
+                let text = format!("Signature: #{}", signature.signature_index);
+                let comment = self.utf8(signature.signature_index);
+                self.line_with_comment(indent, &text, &comment);
+            }
+            AttributeInfo::SourceDebugExtension(extension) => {
+                self.line(indent, "SourceDebugExtension:");
+                let text = String::from_utf8_lossy(&extension.debug_extension).to_string();
+                for line in text.lines() {
+                    self.line(indent + 2, line);
+                }
+            }
+            AttributeInfo::RuntimeVisibleAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleAnnotations(annotations) => {
+                self.line(indent, &format!("{}:", attr.name()));
+                self.print_annotations(indent + 2, annotations);
+            }
+            AttributeInfo::RuntimeVisibleParameterAnnotations(parameters)
+            | AttributeInfo::RuntimeInvisibleParameterAnnotations(parameters) => {
+                self.line(indent, &format!("{}:", attr.name()));
+                for (index, annotations) in parameters.iter().enumerate() {
+                    self.line(indent + 2, &format!("parameter {}:", index));
+                    self.print_annotations(indent + 4, annotations);
+                }
+            }
+            AttributeInfo::RuntimeVisibleTypeAnnotations(annotations)
+            | AttributeInfo::RuntimeInvisibleTypeAnnotations(annotations) => {
+                self.line(indent, &format!("{}:", attr.name()));
+                for (index, annotation) in annotations.iter().enumerate() {
+                    let text = format!(
+                        "{}: {}: {}",
+                        index,
+                        raw_annotation(&annotation.annotation),
+                        type_annotation_target(annotation)
+                    );
+                    self.line(indent + 2, &text);
+                    let pretty = self.pretty_annotation(indent + 4, &annotation.annotation);
+                    self.out.push_str(&pretty);
+                }
+            }
+            AttributeInfo::AnnotationDefault(value) => {
+                self.line(indent, "AnnotationDefault:");
+                self.line(
+                    indent + 2,
+                    &format!("default_value: {}", raw_element_value(value)),
+                );
+                let pretty = self.pretty_element_value(indent + 4, value);
+                self.line(indent + 4, &pretty);
+            }
+            AttributeInfo::StackMapTable(frames) => self.print_stack_map_table(indent, frames),
+            AttributeInfo::BootstrapMethods(bootstrap) => {
+                self.line(indent, "BootstrapMethods:");
+                for (index, method) in bootstrap.bootstrap_methods.iter().enumerate() {
+                    let text = format!(
+                        "{}: #{} {}",
+                        index,
+                        method.bootstrap_method_ref,
+                        self.method_handle(method.bootstrap_method_ref)
+                    );
+                    self.line(indent + 2, &text);
+                    self.line(indent + 4, "Method arguments:");
+                    for argument in &method.bootstrap_arguments {
+                        let text = format!("#{} {}", argument, self.bootstrap_argument(*argument));
+                        self.line(indent + 6, &text);
+                    }
+                }
+            }
+            AttributeInfo::MethodParameters(method_parameters) => {
+                self.line(indent, "MethodParameters:");
+                self.line(indent + 2, &format!("{:<31}{}", "Name", "Flags"));
+                for parameter in &method_parameters.parameters {
+                    let mut flags = vec![];
+                    let access_flags = AccessFlags::new(parameter.access_flags);
+                    if access_flags.is_final() {
+                        flags.push("final");
+                    }
+                    if access_flags.is_synthetic() {
+                        flags.push("synthetic");
+                    }
+                    if access_flags.contains(ACC_MODULE) {
+                        flags.push("mandated");
+                    }
+                    let name = self.utf8(parameter.name_index);
+                    let text = if flags.is_empty() {
+                        name
+                    } else {
+                        format!("{:<31}{}", name, flags.join(" "))
+                    };
+                    self.line(indent + 2, &text);
+                }
+            }
+            AttributeInfo::Module(module) => self.print_module(indent, module),
+            AttributeInfo::ModulePackages(packages) => {
+                self.line(indent, "ModulePackages:");
+                for index in &packages.package_index {
+                    let comment = self.package_name(*index);
+                    self.line_with_comment(indent + 2, &format!("#{}", index), &comment);
+                }
+            }
+            AttributeInfo::ModuleMainClass(main_class) => {
+                let text = format!("ModuleMainClass: #{}", main_class.main_class_index);
+                let comment = java_name(&self.class_name(main_class.main_class_index));
+                self.line_with_comment(indent, &text, &comment);
+            }
+            AttributeInfo::NestHost(host) => {
+                let text = format!("NestHost: class {}", self.class_name(host.host_class_index));
+                self.line(indent, &text);
+            }
+            AttributeInfo::NestMembers(members) => {
+                self.print_class_list(indent, attr.name(), &members.classes)
+            }
+            AttributeInfo::PermittedSubclasses(permitted) => {
+                self.print_class_list(indent, attr.name(), &permitted.classes)
+            }
+            AttributeInfo::Record(record) => {
+                self.line(indent, "Record:");
+                for component in &record.components {
+                    let descriptor = self.utf8(component.descriptor_index);
+                    let text = format!(
+                        "{} {};",
+                        java_type(&descriptor),
+                        self.utf8(component.name_index)
+                    );
+                    self.line(indent + 2, &text);
+                    self.line(indent + 4, &format!("descriptor: {}", descriptor));
+                    self.print_attributes(indent + 4, &component.attributes, None)?;
+                    self.line(0, "");
+                }
+            }
+            AttributeInfo::Unknown(unknown) => {
+                self.line(
+                    indent,
+                    &format!("{}: length = {:#x}", unknown.name, unknown.info.len()),
+                );
+                let bytes: Vec<String> =
+                    unknown.info.iter().map(|b| format!("{:02x}", b)).collect();
+                self.line(indent + 1, &bytes.join(" "));
+            }
+        }
+        Ok(())
+    }
+
+    fn print_code(
+        &mut self,
+        indent: usize,
+        code: &CodeAttribute,
+        args_size: u16,
+    ) -> Result<(), ClassFormatError> {
+        self.line(indent, "Code:");
+        let text = format!(
+            "stack={}, locals={}, args_size={}",
+            code.max_stack, code.max_locals, args_size
+        );
+        self.line(indent + 2, &text);
+        for instruction in decode_bytecode(&code.code)? {
+            self.print_instruction(indent + 2, &instruction);
+        }
+
+        if !code.exception_table.is_empty() {
+            self.line(indent + 2, "Exception table:");
+            self.line(indent + 4, " from    to  target type");
+            for entry in &code.exception_table {
+                let catch_type = if entry.catch_type == 0 {
+                    String::from("any")
+                } else {
+                    format!("Class {}", self.class_name(entry.catch_type))
+                };
+                let text = format!(
+                    " {:>5} {:>5} {:>5}   {}",
+                    entry.start_pc, entry.end_pc, entry.handler_pc, catch_type
+                );
+                self.line(indent + 4, &text);
+            }
+        }
+        self.print_attributes(indent + 2, &code.attribute_table, None)
+    }
+
+    fn print_instruction(&mut self, indent: usize, instruction: &BytecodeInstruction) {
+        let mut mnemonic = String::from(opcode::mnemonic(instruction.opcode).unwrap_or("???"));
+        if instruction.wide {
+            mnemonic.push_str("_w");
+        }
+        let prefix = format!("{:>4}: ", instruction.pc);
+
+        let (text, comment) = match &instruction.operands {
+            Operands::None => (mnemonic, None),
+            Operands::Byte(value) => (format!("{:<13} {}", mnemonic, value), None),
+            Operands::Short(value) => (format!("{:<13} {}", mnemonic, value), None),
+            Operands::Local(index) => (format!("{:<13} {}", mnemonic, index), None),
+            Operands::Branch(target) => (format!("{:<13} {}", mnemonic, target), None),
+            Operands::IInc { index, value } => {
+                (format!("{:<13} {}, {}", mnemonic, index, value), None)
+            }
+            Operands::NewArray(atype) => (
+                format!("{:<13}  {}", mnemonic, array_type_name(*atype)),
+                None,
+            ),
+            Operands::ConstantIndex(index) => (
+                format!("{:<13} #{}", mnemonic, index),
+                Some(self.instruction_constant(*index)),
+            ),
+            Operands::InvokeInterface { index, count } => (
+                format!("{:<13} #{},  {}", mnemonic, index, count),
+                Some(self.instruction_constant(*index)),
+            ),
+            Operands::InvokeDynamic { index } => (
+                format!("{:<13} #{},  0", mnemonic, index),
+                Some(self.instruction_constant(*index)),
+            ),
+            Operands::MultiANewArray { index, dimensions } => (
+                format!("{:<13} #{},  {}", mnemonic, index, dimensions),
+                Some(self.instruction_constant(*index)),
+            ),
+            Operands::TableSwitch {
+                default,
+                low,
+                targets,
+                high,
+            } => {
+                self.line(
+                    indent,
+                    &format!("{}{:<13} {{ // {} to {}", prefix, mnemonic, low, high),
+                );
+                for (i, target) in targets.iter().enumerate() {
+                    let key = *low as i64 + i as i64;
+                    self.line(indent, &format!("{:>18}: {}", key, target));
+                }
+                self.line(indent, &format!("{:>18}: {}", "default", default));
+                self.line(indent + 6, "}");
+                return;
+            }
+            Operands::LookupSwitch { default, pairs } => {
+                self.line(
+                    indent,
+                    &format!("{}{:<13} {{ // {}", prefix, mnemonic, pairs.len()),
+                );
+                for (key, target) in pairs {
+                    self.line(indent, &format!("{:>18}: {}", key, target));
+                }
+                self.line(indent, &format!("{:>18}: {}", "default", default));
+                self.line(indent + 6, "}");
+                return;
+            }
+        };
+
+        let text = format!("{}{}", prefix, text);
+        match comment {
+            Some(comment) => self.line_with_comment(indent, &text, &comment),
+            None => self.line(indent, &text),
+        }
+    }
+
+    /// The comment after an instruction that references the constant pool.
+    /// Members of the class itself are printed without the class name.
+    fn instruction_constant(&self, index: u16) -> String {
+        let this_class = Some(self.class.this_class_index());
+        match self.pool.get(index) {
+            Some(CpEntry::Class { idx }) => format!("class {}", check_name(&self.utf8(*idx))),
+            Some(CpEntry::String { idx }) => format!("String {}", escape(&self.utf8(*idx))),
+            Some(CpEntry::Integer { val }) => format!("int {}", val),
+            Some(CpEntry::Float { val }) => format!("float {}", java_float(*val)),
+            Some(CpEntry::Long { val }) => format!("long {}l", val),
+            Some(CpEntry::Double { val }) => format!("double {}", java_double(*val)),
+            Some(CpEntry::FieldRef(_)) => format!("Field {}", self.member_ref(index, this_class)),
+            Some(CpEntry::MethodRef(_)) => format!("Method {}", self.member_ref(index, this_class)),
+            Some(CpEntry::InterfaceMethodRef(_)) => {
+                format!("InterfaceMethod {}", self.member_ref(index, this_class))
+            }
+            Some(CpEntry::MethodType { desc_idx }) => {
+                format!("MethodType {}", self.utf8(*desc_idx))
+            }
+            Some(CpEntry::MethodHandle { .. }) => {
+                format!("MethodHandle {}", self.method_handle(index))
+            }
+            Some(CpEntry::Dynamic { bsm_idx, nt_idx }) => {
+                format!("Dynamic #{}:{}", bsm_idx, self.name_and_type(*nt_idx))
+            }
+            Some(CpEntry::InvokeDynamic { bsm_idx, nt_idx }) => {
+                format!("InvokeDynamic #{}:{}", bsm_idx, self.name_and_type(*nt_idx))
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn print_stack_map_table(&mut self, indent: usize, frames: &[StackMapFrame]) {
+        let text = format!("StackMapTable: number_of_entries = {}", frames.len());
+        self.line(indent, &text);
+        for frame in frames {
+            let (frame_type, kind) = match frame {
+                StackMapFrame::Same { frame_type } => (*frame_type, "same"),
+                StackMapFrame::SameLocals1StackItem { frame_type, .. } => {
+                    (*frame_type, "same_locals_1_stack_item")
+                }
+                StackMapFrame::SameLocals1StackItemExtended { .. } => {
+                    (247, "same_locals_1_stack_item_frame_extended")
+                }
+                StackMapFrame::Chop { frame_type, .. } => (*frame_type, "chop"),
+                StackMapFrame::SameExtended { .. } => (251, "same_frame_extended"),
+                StackMapFrame::Append { frame_type, .. } => (*frame_type, "append"),
+                StackMapFrame::Full { .. } => (255, "full_frame"),
+            };
+            self.line(
+                indent + 2,
+                &format!("frame_type = {} /* {} */", frame_type, kind),
+            );
+
+            match frame {
+                StackMapFrame::Same { .. } | StackMapFrame::SameLocals1StackItem { .. } => {}
+                _ => {
+                    let text = format!("offset_delta = {}", frame.offset_delta());
+                    self.line(indent + 4, &text);
+                }
+            }
+            match frame {
+                StackMapFrame::SameLocals1StackItem { stack, .. }
+                | StackMapFrame::SameLocals1StackItemExtended { stack, .. } => {
+                    let text = format!(
+                        "stack = {}",
+                        self.verification_types(std::slice::from_ref(stack))
+                    );
+                    self.line(indent + 4, &text);
+                }
+                StackMapFrame::Append { locals, .. } => {
+                    let text = format!("locals = {}", self.verification_types(locals));
+                    self.line(indent + 4, &text);
+                }
+                StackMapFrame::Full { locals, stack, .. } => {
+                    let text = format!("locals = {}", self.verification_types(locals));
+                    self.line(indent + 4, &text);
+                    let text = format!("stack = {}", self.verification_types(stack));
+                    self.line(indent + 4, &text);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn verification_types(&self, types: &[VerificationTypeInfo]) -> String {
+        let names: Vec<String> = types
+            .iter()
+            .map(|info| match info {
+                VerificationTypeInfo::Top => String::from("top"),
+                VerificationTypeInfo::Integer => String::from("int"),
+                VerificationTypeInfo::Float => String::from("float"),
+                VerificationTypeInfo::Double => String::from("double"),
+                VerificationTypeInfo::Long => String::from("long"),
+                VerificationTypeInfo::Null => String::from("null"),
+                VerificationTypeInfo::UninitializedThis => String::from("this"),
+                VerificationTypeInfo::Object { cpool_index } => {
+                    format!("class {}", self.quoted_name(*cpool_index))
+                }
+                VerificationTypeInfo::Uninitialized { offset } => {
+                    format!("uninitialized {}", offset)
+                }
+            })
+            .collect();
+        if names.is_empty() {
+            String::from("[]")
+        } else {
+            format!("[ {} ]", names.join(", "))
+        }
+    }
+
+    fn print_annotations(&mut self, indent: usize, annotations: &[Annotation]) {
+        for (index, annotation) in annotations.iter().enumerate() {
+            self.line(
+                indent,
+                &format!("{}: {}", index, raw_annotation(annotation)),
+            );
+            let pretty = self.pretty_annotation(indent + 2, annotation);
+            self.out.push_str(&pretty);
+        }
+    }
+
+    fn pretty_annotation(&self, indent: usize, annotation: &Annotation) -> String {
+        let padding = " ".repeat(indent);
+        let name = java_type(&self.utf8(annotation.type_index));
+        if annotation.element_value_pairs.is_empty() {
+            return format!("{}{}\n", padding, name);
+        }
+        let mut text = format!("{}{}(\n", padding, name);
+        for pair in &annotation.element_value_pairs {
+            text.push_str(&format!(
+                "{}  {}={}\n",
+                padding,
+                self.utf8(pair.element_name_index),
+                self.pretty_element_value(indent + 2, &pair.value)
+            ));
+        }
+        text.push_str(&format!("{})\n", padding));
+        text
+    }
+
+    fn pretty_element_value(&self, indent: usize, value: &ElementValue) -> String {
+        match value {
+            ElementValue::Const {
+                tag,
+                const_value_index,
+            } => match (tag, self.pool.get(*const_value_index)) {
+                (b's', _) => format!("\"{}\"", escape(&self.utf8(*const_value_index))),
+                (b'Z', Some(CpEntry::Integer { val })) => (*val != 0).to_string(),
+                (b'C', Some(CpEntry::Integer { val })) => {
+                    format!("'{}'", std::char::from_u32(*val as u32).unwrap_or('?'))
+                }
+                (_, Some(CpEntry::Integer { val })) => val.to_string(),
+                (_, Some(CpEntry::Float { val })) => java_float(*val),
+                (_, Some(CpEntry::Long { val })) => format!("{}l", val),
+                (_, Some(CpEntry::Double { val })) => java_double(*val),
+                _ => format!("#{}", const_value_index),
+            },
+            ElementValue::Enum {
+                type_name_index,
+                const_name_index,
+            } => format!(
+                "{}.{}",
+                self.utf8(*type_name_index),
+                self.utf8(*const_name_index)
+            ),
+            ElementValue::Class { class_info_index } => {
+                format!("class {}", self.utf8(*class_info_index))
+            }
+            ElementValue::Annotation(annotation) => {
+                let text = self.pretty_annotation(indent, annotation);
+                String::from(text.trim())
+            }
+            ElementValue::Array(values) => {
+                let items: Vec<String> = values
+                    .iter()
+                    .map(|value| self.pretty_element_value(indent, value))
+                    .collect();
+                format!("[{}]", items.join(","))
+            }
+        }
+    }
+
+    fn print_module(&mut self, indent: usize, module: &ModuleAttribute) {
+        self.line(indent, "Module:");
+        let text = format!("#{},{:x}", module.module_name_index, module.module_flags);
+        let comment = self.module_name(module.module_name_index);
+        self.line_with_comment(indent + 2, &text, &comment);
+        self.print_module_version(indent + 2, module.module_version_index);
+
+        self.line_with_comment(indent + 2, &module.requires.len().to_string(), "requires");
+        for requires in &module.requires {
+            let text = format!("#{},{:x}", requires.requires_index, requires.requires_flags);
+            let comment = format!("\"{}\"", self.module_name(requires.requires_index));
+            self.line_with_comment(indent + 4, &text, &comment);
+            self.print_module_version(indent + 4, requires.requires_version_index);
+        }
+
+        for (kind, exports) in &[("exports", &module.exports), ("opens", &module.opens)] {
+            self.line_with_comment(indent + 2, &exports.len().to_string(), kind);
+            for export in exports.iter() {
+                let text = format!("#{},{:x}", export.package_index, export.flags);
+                let mut comment = self.package_name(export.package_index);
+                if !export.to_index.is_empty() {
+                    comment.push_str(&format!(" to ... {}", export.to_index.len()));
+                }
+                self.line_with_comment(indent + 4, &text, &comment);
+                for to in &export.to_index {
+                    let comment = format!("... to \"{}\"", self.module_name(*to));
+                    self.line_with_comment(indent + 6, &format!("#{}", to), &comment);
+                }
+            }
+        }
+
+        self.line_with_comment(indent + 2, &module.uses_index.len().to_string(), "uses");
+        for uses in &module.uses_index {
+            let comment = self.class_name(*uses);
+            self.line_with_comment(indent + 4, &format!("#{}", uses), &comment);
+        }
+
+        self.line_with_comment(indent + 2, &module.provides.len().to_string(), "provides");
+        for provides in &module.provides {
+            let comment = format!(
+                "{} with ... {}",
+                self.class_name(provides.provides_index),
+                provides.provides_with_index.len()
+            );
+            self.line_with_comment(
+                indent + 4,
+                &format!("#{}", provides.provides_index),
+                &comment,
+            );
+            for with in &provides.provides_with_index {
+                let comment = format!("... with {}", self.class_name(*with));
+                self.line_with_comment(indent + 6, &format!("#{}", with), &comment);
+            }
+        }
+    }
+
+    fn print_module_version(&mut self, indent: usize, index: u16) {
+        if index == 0 {
+            self.line(indent, "#0");
+        } else {
+            let comment = self.utf8(index);
+            self.line_with_comment(indent, &format!("#{}", index), &comment);
+        }
+    }
+
+    fn print_class_list(&mut self, indent: usize, name: &str, classes: &[u16]) {
+        self.line(indent, &format!("{}:", name));
+        for index in classes {
+            let text = self.class_name(*index);
+            self.line(indent + 2, &text);
+        }
+    }
+
+    fn utf8(&self, index: u16) -> String {
+        String::from(self.pool.get_utf8(index).unwrap_or(""))
+    }
+
+    fn class_name(&self, index: u16) -> String {
+        String::from(self.pool.get_class_name(index).unwrap_or(""))
+    }
+
+    fn quoted_name(&self, class_index: u16) -> String {
+        check_name(&self.class_name(class_index))
+    }
+
+    fn signature(&self, attributes: &[AttributeInfo]) -> Option<String> {
+        attributes.iter().find_map(|attr| match attr {
+            AttributeInfo::Signature(signature) => Some(self.utf8(signature.signature_index)),
+            _ => None,
+        })
+    }
+
+    fn module_name(&self, index: u16) -> String {
+        match self.pool.get(index) {
+            Some(CpEntry::Module { name_idx }) => self.utf8(*name_idx),
+            _ => String::new(),
+        }
+    }
+
+    fn package_name(&self, index: u16) -> String {
+        match self.pool.get(index) {
+            Some(CpEntry::Package { name_idx }) => self.utf8(*name_idx),
+            _ => String::new(),
+        }
+    }
+
+    fn name_and_type(&self, index: u16) -> String {
+        match self.pool.get(index) {
+            Some(CpEntry::NameAndType { name_idx, type_idx }) => {
+                let name = check_name(&self.utf8(*name_idx));
+                format!("{}:{}", name, self.utf8(*type_idx))
+            }
+            _ => String::new(),
+        }
+    }
+
+    /// `class.name:type`, leaving out the class when it is `skip_class`.
+    fn member_ref(&self, index: u16, skip_class: Option<u16>) -> String {
+        let member_ref = match self.pool.get(index) {
+            Some(CpEntry::FieldRef(member_ref))
+            | Some(CpEntry::MethodRef(member_ref))
+            | Some(CpEntry::InterfaceMethodRef(member_ref)) => member_ref,
+            _ => return String::new(),
+        };
+        let name_and_type = self.name_and_type(member_ref.name_type_index);
+        if skip_class == Some(member_ref.class_index) {
+            return name_and_type;
+        }
+        format!(
+            "{}.{}",
+            self.quoted_name(member_ref.class_index),
+            name_and_type
+        )
+    }
+
+    fn method_handle(&self, index: u16) -> String {
+        match self.pool.get(index) {
+            Some(CpEntry::MethodHandle { ref_kind, ref_idx }) => {
+                let kind = REFERENCE_KINDS.get(*ref_kind as usize).unwrap_or(&"");
+                format!("{} {}", kind, self.member_ref(*ref_idx, None))
+            }
+            _ => String::new(),
+        }
+    }
+
+    fn bootstrap_argument(&self, index: u16) -> String {
+        match self.pool.get(index) {
+            Some(CpEntry::MethodType { desc_idx }) => self.utf8(*desc_idx),
+            Some(CpEntry::MethodHandle { .. }) => self.method_handle(index),
+            Some(CpEntry::Class { idx }) => self.utf8(*idx),
+            Some(CpEntry::String { idx }) => escape(&self.utf8(*idx)),
+            Some(CpEntry::Integer { val }) => val.to_string(),
+            Some(CpEntry::Float { val }) => java_float(*val),
+            Some(CpEntry::Long { val }) => format!("{}l", val),
+            Some(CpEntry::Double { val }) => java_double(*val),
+            _ => String::new(),
+        }
+    }
+}
+
+fn member_ref_args(class_index: u16, name_type_index: u16) -> String {
+    format!("#{}.#{}", class_index, name_type_index)
+}
+
+fn flag_names(flags: AccessFlags, table: &[(u16, &str)]) -> String {
+    let names: Vec<&str> = table
+        .iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .map(|(_, name)| *name)
+        .collect();
+    let mut text = format!("({:#06x})", flags.bits());
+    if !names.is_empty() {
+        text.push(' ');
+        text.push_str(&names.join(", "));
+    }
+    text
+}
+
+fn modifiers(flags: AccessFlags, table: &[(u16, &str)]) -> Vec<String> {
+    table
+        .iter()
+        .filter(|(flag, _)| flags.contains(*flag))
+        .map(|(_, name)| String::from(*name))
+        .collect()
+}
+
+fn raw_annotation(annotation: &Annotation) -> String {
+    let pairs: Vec<String> = annotation
+        .element_value_pairs
+        .iter()
+        .map(|pair| {
+            format!(
+                "#{}={}",
+                pair.element_name_index,
+                raw_element_value(&pair.value)
+            )
+        })
+        .collect();
+    format!("#{}({})", annotation.type_index, pairs.join(","))
+}
+
+fn raw_element_value(value: &ElementValue) -> String {
+    match value {
+        ElementValue::Const {
+            tag,
+            const_value_index,
+        } => format!("{}#{}", *tag as char, const_value_index),
+        ElementValue::Enum {
+            type_name_index,
+            const_name_index,
+        } => format!("e#{}.#{}", type_name_index, const_name_index),
+        ElementValue::Class { class_info_index } => format!("c#{}", class_info_index),
+        ElementValue::Annotation(annotation) => format!("@{}", raw_annotation(annotation)),
+        ElementValue::Array(values) => {
+            let items: Vec<String> = values.iter().map(raw_element_value).collect();
+            format!("[{}]", items.join(","))
+        }
+    }
+}
+
+fn type_annotation_target(annotation: &TypeAnnotation) -> String {
+    let kind = match annotation.target_type {
+        0x00 => "CLASS_TYPE_PARAMETER",
+        0x01 => "METHOD_TYPE_PARAMETER",
+        0x10 => "CLASS_EXTENDS",
+        0x11 => "CLASS_TYPE_PARAMETER_BOUND",
+        0x12 => "METHOD_TYPE_PARAMETER_BOUND",
+        0x13 => "FIELD",
+        0x14 => "METHOD_RETURN",
+        0x15 => "METHOD_RECEIVER",
+        0x16 => "METHOD_FORMAL_PARAMETER",
+        0x17 => "THROWS",
+        0x40 => "LOCAL_VARIABLE",
+        0x41 => "RESOURCE_VARIABLE",
+        0x42 => "EXCEPTION_PARAMETER",
+        0x43 => "INSTANCEOF",
+        0x44 => "NEW",
+        0x45 => "CONSTRUCTOR_REFERENCE",
+        0x46 => "METHOD_REFERENCE",
+        0x47 => "CAST",
+        0x48 => "CONSTRUCTOR_INVOCATION_TYPE_ARGUMENT",
+        0x49 => "METHOD_INVOCATION_TYPE_ARGUMENT",
+        0x4a => "CONSTRUCTOR_REFERENCE_TYPE_ARGUMENT",
+        _ => "METHOD_REFERENCE_TYPE_ARGUMENT",
+    };
+    let mut parts = vec![String::from(kind)];
+    match &annotation.target_info {
+        TargetInfo::TypeParameter {
+            type_parameter_index,
+        } => parts.push(format!("param_index={}", type_parameter_index)),
+        TargetInfo::SuperType { supertype_index } => {
+            parts.push(format!("type_index={}", supertype_index))
+        }
+        TargetInfo::TypeParameterBound {
+            type_parameter_index,
+            bound_index,
+        } => {
+            parts.push(format!("param_index={}", type_parameter_index));
+            parts.push(format!("bound_index={}", bound_index));
+        }
+        TargetInfo::Empty => {}
+        TargetInfo::FormalParameter {
+            formal_parameter_index,
+        } => parts.push(format!("param_index={}", formal_parameter_index)),
+        TargetInfo::Throws { throws_type_index } => {
+            parts.push(format!("type_index={}", throws_type_index))
+        }
+        TargetInfo::LocalVar { table } => {
+            let entries: Vec<String> = table
+                .iter()
+                .map(|entry| {
+                    format!(
+                        "start_pc={}, length={}, index={}",
+                        entry.start_pc, entry.length, entry.index
+                    )
+                })
+                .collect();
+            parts.push(format!("{{{}}}", entries.join("; ")));
+        }
+        TargetInfo::Catch {
+            exception_table_index,
+        } => parts.push(format!("exception_index={}", exception_table_index)),
+        TargetInfo::Offset { offset } => parts.push(format!("offset={}", offset)),
+        TargetInfo::TypeArgument {
+            offset,
+            type_argument_index,
+        } => {
+            parts.push(format!("offset={}", offset));
+            parts.push(format!("type_index={}", type_argument_index));
+        }
+    }
+    if !annotation.type_path.is_empty() {
+        let path: Vec<String> = annotation
+            .type_path
+            .iter()
+            .map(|(kind, index)| match kind {
+                0 => String::from("ARRAY"),
+                1 => String::from("INNER_TYPE"),
+                2 => String::from("WILDCARD"),
+                _ => format!("TYPE_ARGUMENT({})", index),
+            })
+            .collect();
+        parts.push(format!("location=[{}]", path.join(", ")));
+    }
+    parts.join(", ")
+}
+
+fn array_type_name(atype: u8) -> &'static str {
+    match atype {
+        4 => "boolean",
+        5 => "char",
+        6 => "float",
+        7 => "double",
+        8 => "byte",
+        9 => "short",
+        10 => "int",
+        11 => "long",
+        _ => "?",
+    }
+}
+
+/// `java/lang/String` to `java.lang.String`.
+fn java_name(internal: &str) -> String {
+    internal.replace('/', ".")
+}
+
+/// A field descriptor or signature such as `[Ljava/lang/String;` as it is
+/// written in Java.
+fn java_type(descriptor: &str) -> String {
+    SignatureReader::new(descriptor).java_type()
+}
+
+fn type_parameters(parameters: &[String]) -> String {
+    if parameters.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", parameters.join(", "))
+    }
+}
+
+/// javap quotes names that are not a `/` separated list of identifiers,
+/// such as `"<init>"` or array classes.
+fn check_name(name: &str) -> String {
+    let mut previous = '/';
+    for c in name.chars() {
+        let valid = if previous == '/' {
+            c.is_alphabetic() || c == '_' || c == '$'
+        } else {
+            c == '/' || c.is_alphanumeric() || c == '_' || c == '$'
+        };
+        if !valid {
+            return format!("\"{}\"", escape(name));
+        }
+        previous = c;
+    }
+    if name.is_empty() {
+        String::from("\"\"")
+    } else {
+        String::from(name)
+    }
+}
+
+/// A method descriptor or signature with every type written as in Java.
+struct MethodType {
+    type_parameters: Vec<String>,
+    parameters: Vec<String>,
+    return_type: String,
+    throws: Vec<String>,
+}
+
+impl MethodType {
+    fn parse(signature: &str) -> MethodType {
+        let mut reader = SignatureReader::new(signature);
+        let type_parameters = reader.type_parameters();
+        let mut parameters = vec![];
+        if reader.peek() == b'(' {
+            reader.pos += 1;
+            while !reader.at_end() && reader.peek() != b')' {
+                parameters.push(reader.java_type());
+            }
+            reader.pos += 1;
+        }
+        let return_type = reader.java_type();
+        let mut throws = vec![];
+        while reader.peek() == b'^' {
+            reader.pos += 1;
+            throws.push(reader.java_type());
+        }
+        MethodType {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        }
+    }
+}
+
+/// Reads the signature grammar of JVMS 4.7.9.1, which also covers plain
+/// descriptors. Malformed input ends the current type instead of failing.
+struct SignatureReader<'s> {
+    text: &'s str,
+    pos: usize,
+}
+
+impl<'s> SignatureReader<'s> {
+    fn new(text: &'s str) -> SignatureReader<'s> {
+        SignatureReader { text, pos: 0 }
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.text.len()
+    }
+
+    fn peek(&self) -> u8 {
+        *self.text.as_bytes().get(self.pos).unwrap_or(&0)
+    }
+
+    /// Reads up to, but not including, one of `stops`.
+    fn identifier(&mut self, stops: &[u8]) -> &'s str {
+        let start = self.pos;
+        while !self.at_end() && !stops.contains(&self.peek()) {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    /// `<T:Ljava/lang/Object;U::Ljava/lang/Runnable;>`
+    fn type_parameters(&mut self) -> Vec<String> {
+        let mut parameters = vec![];
+        if self.peek() != b'<' {
+            return parameters;
+        }
+        self.pos += 1;
+        while !self.at_end() && self.peek() != b'>' {
+            let mut parameter = String::from(self.identifier(b":>"));
+            let mut separator = " extends ";
+            while self.peek() == b':' {
+                self.pos += 1;
+                // an empty class bound is followed directly by an interface bound
+                if self.peek() == b':' {
+                    continue;
+                }
+                parameter.push_str(separator);
+                parameter.push_str(&self.java_type());
+                separator = " & ";
+            }
+            parameters.push(parameter);
+        }
+        self.pos += 1;
+        parameters
+    }
+
+    fn java_type(&mut self) -> String {
+        let tag = self.peek();
+        self.pos += 1;
+        let primitive = match tag {
+            b'B' => "byte",
+            b'C' => "char",
+            b'D' => "double",
+            b'F' => "float",
+            b'I' => "int",
+            b'J' => "long",
+            b'S' => "short",
+            b'Z' => "boolean",
+            b'V' => "void",
+            b'[' => return format!("{}[]", self.java_type()),
+            b'T' => {
+                let name = String::from(self.identifier(b";"));
+                self.pos += 1;
+                return name;
+            }
+            b'L' => return self.class_type(),
+            _ => {
+                self.pos = self.text.len();
+                return String::new();
+            }
+        };
+        String::from(primitive)
+    }
+
+    /// `java/util/Map<TK;TV;>.Entry;` after the leading `L`
+    fn class_type(&mut self) -> String {
+        let mut name = java_name(self.identifier(b"<.;"));
+        loop {
+            match self.peek() {
+                b'<' => {
+                    self.pos += 1;
+                    let mut arguments = vec![];
+                    while !self.at_end() && self.peek() != b'>' {
+                        arguments.push(self.type_argument());
+                    }
+                    self.pos += 1;
+                    name.push_str(&format!("<{}>", arguments.join(", ")));
+                }
+                b'.' => {
+                    self.pos += 1;
+                    name.push('.');
+                    name.push_str(self.identifier(b"<.;"));
+                }
+                _ => {
+                    self.pos += 1;
+                    return name;
+                }
+            }
+        }
+    }
+
+    fn type_argument(&mut self) -> String {
+        match self.peek() {
+            b'*' => {
+                self.pos += 1;
+                String::from("?")
+            }
+            b'+' => {
+                self.pos += 1;
+                format!("? extends {}", self.java_type())
+            }
+            b'-' => {
+                self.pos += 1;
+                format!("? super {}", self.java_type())
+            }
+            _ => self.java_type(),
+        }
+    }
+}
+
+/// Escapes a string the way javap prints Utf8 and String constants.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '"' => escaped.push_str("\\\""),
+            '\'' => escaped.push_str("\\'"),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn java_float(value: f32) -> String {
+    format!(
+        "{}f",
+        java_decimal(value as f64, value.to_string(), format!("{:e}", value))
+    )
+}
+
+fn java_double(value: f64) -> String {
+    format!(
+        "{}d",
+        java_decimal(value, value.to_string(), format!("{:e}", value))
+    )
+}
+
+/// `Double.toString`: plain notation between 10^-3 and 10^7, otherwise
+/// computerized scientific notation like `1.0E10`.
+fn java_decimal(value: f64, plain: String, scientific: String) -> String {
+    if value.is_nan() {
+        return String::from("NaN");
+    }
+    if value.is_infinite() {
+        return String::from(if value > 0.0 { "Infinity" } else { "-Infinity" });
+    }
+    let magnitude = value.abs();
+    if magnitude == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        if plain.contains('.') {
+            plain
+        } else {
+            format!("{}.0", plain)
+        }
+    } else {
+        let (mantissa, exponent) = scientific.split_at(scientific.find('e').unwrap_or(0));
+        let mantissa = if mantissa.contains('.') {
+            String::from(mantissa)
+        } else {
+            format!("{}.0", mantissa)
+        };
+        format!("{}E{}", mantissa, &exponent[1..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classfile::class_file_stream::ClassFileStream;
+    use crate::classfile::parsed_class::ParsedClass;
+    use crate::javap::{
+        check_name, disassemble, escape, java_double, java_float, java_type, MethodType,
+    };
+
+    fn javap(path: &str) -> String {
+        let bytes = std::fs::read(path).unwrap();
+        let class = ParsedClass::new(ClassFileStream::new(bytes)).unwrap();
+        disassemble(&class).unwrap()
+    }
+
+    #[test]
+    fn should_match_javap_output() {
+        for name in &[
+            "testdata/java8/HelloWorld",
+            "testdata/java8/ConstantSample",
+            "testdata/java17/module-info",
+            "testdata/java17/sample/AttributeSample",
+        ] {
+            let expected = std::fs::read_to_string(format!("{}.javap", name)).unwrap();
+            assert_eq!(expected, javap(&format!("{}.class", name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn should_print_every_fixture() {
+        for path in &[
+            "testdata/java8/ArraySample.class",
+            "testdata/java8/StringSample.class",
+            "testdata/java17/sample/AttributeSample$Level.class",
+            "testdata/java17/sample/AttributeSample$Point.class",
+        ] {
+            assert!(javap(path).contains("Constant pool:"));
+        }
+    }
+
+    #[test]
+    fn should_format_numbers_like_java() {
+        assert_eq!("3.5f", java_float(3.5));
+        assert_eq!("1.0E10f", java_float(1e10));
+        assert_eq!("2.718281828d", java_double(2.718281828));
+        assert_eq!("1.0E-5d", java_double(0.00001));
+        assert_eq!("100.0d", java_double(100.0));
+        assert_eq!("NaNd", java_double(f64::NAN));
+    }
+
+    #[test]
+    fn should_write_descriptors_as_java_types() {
+        assert_eq!("java.lang.String[]", java_type("[Ljava/lang/String;"));
+        assert_eq!("int[][]", java_type("[[I"));
+        assert_eq!("void", java_type("V"));
+    }
+
+    #[test]
+    fn should_write_signatures_as_java_types() {
+        assert_eq!(
+            "java.util.Map<K, java.util.List<? extends T>>.Entry<?, ? super V>",
+            java_type("Ljava/util/Map<TK;Ljava/util/List<+TT;>;>.Entry<*-TV;>;")
+        );
+
+        let method = MethodType::parse("<T::Ljava/lang/Comparable<TT;>;>([TT;J)TT;^TE;");
+        assert_eq!(
+            vec!["T extends java.lang.Comparable<T>"],
+            method.type_parameters
+        );
+        assert_eq!(vec!["T[]", "long"], method.parameters);
+        assert_eq!("T", method.return_type);
+        assert_eq!(vec!["E"], method.throws);
+    }
+
+    #[test]
+    fn should_escape_strings() {
+        assert_eq!("a\\nb\\u0000\\\"é😀", escape("a\nb\0\"é😀"));
+        assert_eq!("\"<init>\"", check_name("<init>"));
+        assert_eq!("\"[I\"", check_name("[I"));
+        assert_eq!("java/lang/Object", check_name("java/lang/Object"));
+    }
+}
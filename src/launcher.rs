@@ -0,0 +1,633 @@
+//! The `java`-style command line: options, the main class or `-jar`, and
+//! running `main` until the program exits.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::classfile::access_flags::{ACC_PUBLIC, ACC_STATIC};
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::classfile::parsed_class::ParsedClass;
+use crate::classpath::class_path::{ClassPath, FEATURE_VERSION};
+use crate::classpath::java_home::{JavaHome, JavaHomeError};
+use crate::classpath::manifest::Manifest;
+use crate::rtda::heap::class_init::initialize_class;
+use crate::rtda::heap::class_loader::ClassLoadError;
+use crate::rtda::heap::runtime::Runtime;
+use crate::rtda::heap::slot::Slot;
+use crate::rtda::heap::strings::new_string_array;
+use crate::rtda::thread::{create_frame, execute_method, Thread, VmExit};
+
+pub use crate::classpath::class_path::PATH_SEPARATOR;
+
+pub const USAGE: &str = "Usage: jvm [options] <mainclass> [args...]
+           (to execute a class)
+   or  jvm [options] -jar <jarfile> [args...]
+           (to execute a jar file)
+
+ where options include:
+
+    -cp <class search path of directories and zip/jar files>
+    -classpath <class search path of directories and zip/jar files>
+    --class-path <class search path of directories and zip/jar files>
+                  A : separated list of directories, JAR archives,
+                  and ZIP archives to search for class files.
+    -D<name>=<value>
+                  set a system property, java.home picks the Java runtime
+                  and jdk.util.jar.version the release of multi-release jars
+    -Xss<size>    set java thread stack size
+    -Xms<size>    set initial Java heap size
+    -Xmx<size>    set maximum Java heap size
+    -version      print product version and exit
+    -help, -h, -? print this help message";
+
+// the limits of `java` for -Xss, -Xms and -Xmx
+const MIN_STACK_SIZE: u64 = 136 << 10;
+const MAX_STACK_SIZE: u64 = 1 << 30;
+const MIN_INITIAL_HEAP_SIZE: u64 = 1 << 20;
+const MIN_MAX_HEAP_SIZE: u64 = 2 << 20;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Launch {
+    Run(LaunchOptions),
+    Help,
+    Version,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MainTarget {
+    // a binary name such as `hello.HelloWorld`
+    Class(String),
+    // the path of a jar whose manifest names the main class
+    Jar(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LaunchOptions {
+    pub class_path: String,
+    pub main: MainTarget,
+    pub system_properties: Vec<(String, String)>,
+    pub stack_size: Option<u64>,
+    pub initial_heap_size: Option<u64>,
+    pub max_heap_size: Option<u64>,
+    /// the program arguments, handed to `main` as its `String[]`
+    pub args: Vec<String>,
+}
+
+/// Reasons the launcher gives up before `main` runs. The messages follow
+/// the ones printed by `java`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchError {
+    Usage(String),
+    // rejected while creating the VM, e.g. an unknown option
+    InvalidOption(String),
+    UnableToAccessJar(String),
+    NoMainManifestAttribute(String),
+    MainClassNotFound(String),
+    // a class path entry could not be read while looking for the main class,
+    // e.g. a corrupt jar
+    MainClassUnreadable {
+        class_name: String,
+        cause: String,
+    },
+    MainMethodNotFound(String),
+    NoJavaRuntime(JavaHomeError),
+    ClassFormat {
+        class_name: String,
+        cause: ClassFormatError,
+    },
+    // the main class parsed but could not be defined, e.g. a missing superclass
+    Linkage {
+        class_name: String,
+        // the Java error, `java.lang.NoClassDefFoundError: java/lang/Object`
+        cause: String,
+    },
+}
+
+impl LaunchError {
+    pub fn exit_code(&self) -> i32 {
+        1
+    }
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchError::Usage(message) => write!(f, "{}", message),
+            LaunchError::InvalidOption(message) => write!(
+                f,
+                "{}\nError: Could not create the Java Virtual Machine.\nError: A fatal exception has occurred. Program will exit.",
+                message
+            ),
+            LaunchError::UnableToAccessJar(jar) => {
+                write!(f, "Error: Unable to access jarfile {}", jar)
+            }
+            LaunchError::NoMainManifestAttribute(jar) => {
+                write!(f, "no main manifest attribute, in {}", jar)
+            }
+            LaunchError::MainClassNotFound(name) => write!(
+                f,
+                "Error: Could not find or load main class {}\nCaused by: java.lang.ClassNotFoundException: {}",
+                name, name
+            ),
+            LaunchError::MainClassUnreadable { class_name, cause } => write!(
+                f,
+                "Error: Could not find or load main class {}\nCaused by: java.io.IOException: {}",
+                class_name, cause
+            ),
+            LaunchError::MainMethodNotFound(name) => write!(
+                f,
+                "Error: Main method not found in class {}, please define the main method as:\n   public static void main(String[] args)",
+                name
+            ),
+            LaunchError::NoJavaRuntime(cause) => write!(
+                f,
+                "Error: {}\nError: Could not find Java SE Runtime Environment.",
+                cause
+            ),
+            LaunchError::ClassFormat { class_name, cause } => write!(
+                f,
+                "Error: LinkageError occurred while loading main class {}\n\tjava.lang.ClassFormatError: {}",
+                class_name, cause
+            ),
+            LaunchError::Linkage { class_name, cause } => write!(
+                f,
+                "Error: LinkageError occurred while loading main class {}\n\t{}",
+                class_name, cause
+            ),
+        }
+    }
+}
+
+/// Parses the arguments after the program name. Everything after the main
+/// class or the jar is passed to `main` untouched.
+pub fn parse_args(args: &[String]) -> Result<Launch, LaunchError> {
+    let mut class_path = None;
+    let mut system_properties = vec![];
+    let mut stack_size = None;
+    let mut initial_heap_size = None;
+    let mut max_heap_size = None;
+    let mut main = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        i += 1;
+        match arg {
+            "-cp" | "-classpath" | "--class-path" => match args.get(i) {
+                Some(value) => {
+                    class_path = Some(value.clone());
+                    i += 1;
+                }
+                None => {
+                    let message = format!("Error: {} requires class path specification", arg);
+                    return Err(LaunchError::Usage(message));
+                }
+            },
+            "-jar" => match args.get(i) {
+                Some(jar) => {
+                    main = Some(MainTarget::Jar(jar.clone()));
+                    i += 1;
+                    break;
+                }
+                None => {
+                    let message = String::from("Error: -jar requires jar file specification");
+                    return Err(LaunchError::Usage(message));
+                }
+            },
+            "-help" | "--help" | "-h" | "-?" => return Ok(Launch::Help),
+            "-version" | "--version" => return Ok(Launch::Version),
+            _ if arg.starts_with("--class-path=") => {
+                class_path = Some(String::from(&arg["--class-path=".len()..]));
+            }
+            _ if arg.starts_with("-D") => {
+                let property = &arg[2..];
+                let (name, value) = match property.find('=') {
+                    Some(equals) => (&property[..equals], &property[equals + 1..]),
+                    None => (property, ""),
+                };
+                system_properties.push((String::from(name), String::from(value)));
+            }
+            _ if arg.starts_with("-Xss") => {
+                stack_size = Some(parse_option_size(arg, "Invalid thread stack size")?);
+            }
+            _ if arg.starts_with("-Xms") => {
+                initial_heap_size = Some(parse_option_size(arg, "Invalid initial heap size")?);
+            }
+            _ if arg.starts_with("-Xmx") => {
+                max_heap_size = Some(parse_option_size(arg, "Invalid maximum heap size")?);
+            }
+            _ if arg.starts_with('-') => {
+                let message = format!("Unrecognized option: {}", arg);
+                return Err(LaunchError::InvalidOption(message));
+            }
+            _ => {
+                main = Some(MainTarget::Class(String::from(arg)));
+                break;
+            }
+        }
+    }
+
+    check_size_ranges(stack_size, initial_heap_size, max_heap_size)?;
+
+    let main = match main {
+        Some(main) => main,
+        None => return Err(LaunchError::Usage(String::from(USAGE))),
+    };
+    // like `java`, fall back to $CLASSPATH and then the current directory
+    let class_path = class_path
+        .or_else(|| env::var("CLASSPATH").ok())
+        .unwrap_or_else(|| String::from("."));
+
+    Ok(Launch::Run(LaunchOptions {
+        class_path,
+        main,
+        system_properties,
+        stack_size,
+        initial_heap_size,
+        max_heap_size,
+        args: args[i..].to_vec(),
+    }))
+}
+
+fn parse_option_size(arg: &str, message: &str) -> Result<u64, LaunchError> {
+    parse_size(&arg[4..]).ok_or_else(|| LaunchError::InvalidOption(format!("{}: {}", message, arg)))
+}
+
+/// Rejects the sizes `java` does not start with. A size of 0 picks the
+/// default.
+fn check_size_ranges(
+    stack_size: Option<u64>,
+    initial_heap_size: Option<u64>,
+    max_heap_size: Option<u64>,
+) -> Result<(), LaunchError> {
+    let invalid = |message: &str| Err(LaunchError::InvalidOption(String::from(message)));
+    match stack_size {
+        Some(size) if size > MAX_STACK_SIZE => return invalid(
+            "Invalid thread stack size: the specified size exceeds the maximum representable size",
+        ),
+        Some(size) if size != 0 && size < MIN_STACK_SIZE => {
+            return invalid(
+                "The Java thread stack size specified is too small. Specify at least 136k",
+            )
+        }
+        _ => {}
+    }
+    if let Some(size) = initial_heap_size {
+        if size != 0 && size < MIN_INITIAL_HEAP_SIZE {
+            return invalid("Too small initial heap");
+        }
+    }
+    if let Some(size) = max_heap_size {
+        if size < MIN_MAX_HEAP_SIZE {
+            return invalid("Too small maximum heap");
+        }
+    }
+    if let (Some(initial), Some(max)) = (initial_heap_size, max_heap_size) {
+        if initial > max {
+            return invalid("Initial heap size set to a larger value than the maximum heap size");
+        }
+    }
+    Ok(())
+}
+
+/// Reads sizes like `512`, `64k`, `256m` or `2G` into bytes.
+pub fn parse_size(text: &str) -> Option<u64> {
+    let (digits, shift) = match text.chars().last()? {
+        'k' | 'K' => (&text[..text.len() - 1], 10),
+        'm' | 'M' => (&text[..text.len() - 1], 20),
+        'g' | 'G' => (&text[..text.len() - 1], 30),
+        't' | 'T' => (&text[..text.len() - 1], 40),
+        _ => (text, 0),
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let value: u64 = digits.parse().ok()?;
+    value.checked_mul(1 << shift)
+}
+
+/// Loads the main class and runs its `main` method on a new thread.
+pub fn launch(options: &LaunchOptions) -> Result<VmExit, LaunchError> {
+    // `-jar` replaces the class path with the jar itself
+    let (class_path, main_class) = match &options.main {
+        MainTarget::Class(name) => (options.class_path.clone(), name.clone()),
+        MainTarget::Jar(jar) => (jar.clone(), read_main_class(jar)?),
+    };
+
+    // `-Djava.home` picks the runtime, otherwise it is looked up
+    let java_home = options
+        .system_properties
+        .iter()
+        .rev()
+        .find(|(name, _)| name == "java.home")
+        .map(|(_, value)| value.as_str());
+    let java_home = JavaHome::find(java_home).map_err(LaunchError::NoJavaRuntime)?;
+
+    let mut user_path = ClassPath::with_java_home(java_home.clone());
+    if let Some(release) = jar_version(options) {
+        user_path.release = release;
+    }
+    user_path.parse_user_class_path(class_path.clone());
+
+    let class_name = main_class.replace('.', "/");
+    let bytes = match user_path.read_class(&class_name) {
+        Ok(Some(found)) => found.bytes,
+        Ok(None) => return Err(LaunchError::MainClassNotFound(main_class)),
+        Err(cause) => {
+            return Err(LaunchError::MainClassUnreadable {
+                class_name: main_class,
+                cause: cause.to_string(),
+            })
+        }
+    };
+    let class_format = |cause| LaunchError::ClassFormat {
+        class_name: main_class.clone(),
+        cause,
+    };
+    let mut class = ParsedClass::new(ClassFileStream::new(bytes)).map_err(class_format)?;
+
+    let mut runtime = Runtime::new(user_path);
+    runtime.system_properties = system_properties(options, &java_home, &class_path);
+    runtime.stack_size = options.stack_size;
+    runtime.initial_heap_size = options.initial_heap_size;
+    runtime.max_heap_size = options.max_heap_size;
+    let loader = &runtime.boot_loader;
+    let klass = loader
+        .define_class(&class_name, &mut class)
+        .and_then(|klass| loader.link_class(&klass).map(|_| klass))
+        .map_err(|cause: ClassLoadError| LaunchError::Linkage {
+            class_name: main_class.clone(),
+            cause: cause.exception(),
+        })?;
+    let main_index = match find_main_method(&class) {
+        Some(index) => index,
+        None => return Err(LaunchError::MainMethodNotFound(main_class)),
+    };
+    let main = klass.methods[main_index].clone();
+
+    let thread = Rc::new(RefCell::new(Thread::new(runtime)));
+    // like `java`, the main class is initialized before `main` runs
+    if let Err(exception) = initialize_class(&thread.borrow(), &klass) {
+        return Ok(VmExit::UncaughtException(exception));
+    }
+    let main_args = match new_string_array(&thread.borrow().runtime.boot_loader, &options.args) {
+        Ok(main_args) => main_args,
+        Err(cause) => return Ok(VmExit::UncaughtException(cause.exception())),
+    };
+    let mut frame = create_frame(&main, thread);
+    frame.local_vars.set(0, Slot::from_reference(main_args));
+    execute_method(&mut frame, main.method_data.code.clone()).map_err(class_format)?;
+
+    let exit = frame.thread.borrow_mut().exit.take();
+    Ok(exit.unwrap_or(VmExit::Normal))
+}
+
+/// `-Djdk.util.jar.version=N` serves multi-release jars as release `N`,
+/// never above the release of the VM.
+fn jar_version(options: &LaunchOptions) -> Option<u32> {
+    let (_, value) = options
+        .system_properties
+        .iter()
+        .rev()
+        .find(|(name, _)| name == "jdk.util.jar.version")?;
+    let release: u32 = value.parse().ok()?;
+    Some(release.min(FEATURE_VERSION))
+}
+
+fn read_main_class(jar: &str) -> Result<String, LaunchError> {
+    let manifest = match Manifest::read_from_jar(Path::new(jar)) {
+        Ok(manifest) => manifest,
+        Err(_) => return Err(LaunchError::UnableToAccessJar(String::from(jar))),
+    };
+    match manifest.as_ref().and_then(|manifest| manifest.main_class()) {
+        Some(name) => Ok(String::from(name)),
+        None => Err(LaunchError::NoMainManifestAttribute(String::from(jar))),
+    }
+}
+
+/// Index of `public static void main(String[])` in the methods of `class`.
+fn find_main_method(class: &ParsedClass) -> Option<usize> {
+    let pool = class.constant_pool();
+    class.methods().iter().position(|method| {
+        method.access_flags().contains(ACC_PUBLIC | ACC_STATIC)
+            && pool.get_utf8(method.name_index()) == Some("main")
+            && pool.get_utf8(method.descriptor_index()) == Some("([Ljava/lang/String;)V")
+    })
+}
+
+fn system_properties(
+    options: &LaunchOptions,
+    java_home: &JavaHome,
+    class_path: &str,
+) -> HashMap<String, String> {
+    let mut properties = HashMap::new();
+    properties.insert(
+        String::from("java.home"),
+        java_home.path.to_string_lossy().to_string(),
+    );
+    properties.insert(String::from("java.class.path"), String::from(class_path));
+    if let Ok(dir) = env::current_dir() {
+        properties.insert(String::from("user.dir"), dir.to_string_lossy().to_string());
+    }
+    properties.insert(String::from("path.separator"), PATH_SEPARATOR.to_string());
+    for (name, value) in &options.system_properties {
+        properties.insert(name.clone(), value.clone());
+    }
+    properties
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::classpath::class_path::FEATURE_VERSION;
+    use crate::launcher::{
+        jar_version, launch, parse_args, parse_size, Launch, LaunchError, LaunchOptions, MainTarget,
+    };
+    use crate::rtda::thread::VmExit;
+
+    fn args(text: &str) -> Vec<String> {
+        text.split_whitespace().map(String::from).collect()
+    }
+
+    fn launch_options(text: &str) -> LaunchOptions {
+        match parse_args(&args(text)).unwrap() {
+            Launch::Run(options) => options,
+            launch => panic!("unexpected {:?}", launch),
+        }
+    }
+
+    fn with_jre(text: &str) -> String {
+        format!("-Djava.home=testdata/jre {}", text)
+    }
+
+    #[test]
+    fn should_parse_class_launch() {
+        let options = launch_options("-cp a.jar:classes -Dx=1 -Dy -Xss512k -Xmx2g hello.Main a -b");
+        assert_eq!("a.jar:classes", options.class_path);
+        assert_eq!(MainTarget::Class(String::from("hello.Main")), options.main);
+        assert_eq!(
+            vec![
+                (String::from("x"), String::from("1")),
+                (String::from("y"), String::from(""))
+            ],
+            options.system_properties
+        );
+        assert_eq!(Some(512 * 1024), options.stack_size);
+        assert_eq!(Some(2 << 30), options.max_heap_size);
+        assert_eq!(args("a -b"), options.args);
+    }
+
+    #[test]
+    fn should_pass_options_after_jar_to_main() {
+        let options = launch_options("-jar app.jar -cp x");
+        assert_eq!(MainTarget::Jar(String::from("app.jar")), options.main);
+        assert_eq!(args("-cp x"), options.args);
+    }
+
+    #[test]
+    fn should_reject_bad_options() {
+        assert_eq!(Ok(Launch::Version), parse_args(&args("-version Main")));
+        assert!(matches!(
+            parse_args(&args("-cp")),
+            Err(LaunchError::Usage(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-Xss1q Main")),
+            Err(LaunchError::InvalidOption(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-Xms2g -Xmx1g Main")),
+            Err(LaunchError::InvalidOption(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-Xss135k Main")),
+            Err(LaunchError::InvalidOption(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-Xss2g Main")),
+            Err(LaunchError::InvalidOption(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-Xmx1m Main")),
+            Err(LaunchError::InvalidOption(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-Xms0 -Xss0 -Xmx2m Main")),
+            Ok(Launch::Run(_))
+        ));
+        assert!(matches!(
+            parse_args(&args("-foo Main")),
+            Err(LaunchError::InvalidOption(_))
+        ));
+        assert!(matches!(parse_args(&args("")), Err(LaunchError::Usage(_))));
+    }
+
+    #[test]
+    fn should_parse_sizes() {
+        assert_eq!(Some(100), parse_size("100"));
+        assert_eq!(Some(64 * 1024), parse_size("64K"));
+        assert_eq!(Some(256 << 20), parse_size("256m"));
+        assert_eq!(None, parse_size("m"));
+        assert_eq!(None, parse_size("-1"));
+        assert_eq!(None, parse_size("99999999999t"));
+    }
+
+    #[test]
+    fn should_launch_main_class() {
+        let options = launch_options(&with_jre(
+            "-cp testdata/java8/jar/hello.jar:testdata/java8 HelloWorld",
+        ));
+        assert_eq!(Ok(VmExit::Normal), launch(&options));
+
+        let options = launch_options(&with_jre("-jar testdata/java8/jar/main.jar"));
+        assert_eq!(Ok(VmExit::Normal), launch(&options));
+    }
+
+    #[test]
+    fn should_initialize_main_class_before_main() {
+        let options = launch_options(&with_jre("-cp testdata/init Broken"));
+        assert_eq!(
+            Ok(VmExit::UncaughtException(String::from(
+                "java.lang.NoSuchFieldError: Class Holder does not have member field 'I missing'"
+            ))),
+            launch(&options)
+        );
+    }
+
+    #[test]
+    fn should_exit_with_system_exit_status() {
+        let options = launch_options(&with_jre("-cp testdata/exit Quit"));
+        assert_eq!(Ok(VmExit::Exit(3)), launch(&options));
+        let options = launch_options(&with_jre("-cp testdata/exit Abort"));
+        assert_eq!(Ok(VmExit::Exit(300)), launch(&options));
+    }
+
+    #[test]
+    fn should_pass_args_to_main() {
+        let options = launch_options(&with_jre("-cp testdata/exit Count a b"));
+        assert_eq!(Ok(VmExit::Exit(2)), launch(&options));
+        let options = launch_options(&with_jre("-cp testdata/exit Count"));
+        assert_eq!(Ok(VmExit::Exit(0)), launch(&options));
+    }
+
+    #[test]
+    fn should_read_jar_version_property() {
+        let options = launch_options("-Djdk.util.jar.version=11 Main");
+        assert_eq!(Some(11), jar_version(&options));
+
+        let options = launch_options("-Djdk.util.jar.version=99 Main");
+        assert_eq!(Some(FEATURE_VERSION), jar_version(&options));
+
+        let options = launch_options("-Djdk.util.jar.version=next Main");
+        assert_eq!(None, jar_version(&options));
+    }
+
+    #[test]
+    fn should_report_launch_errors() {
+        let options = launch_options(&with_jre("-cp testdata/java8 Missing"));
+        assert_eq!(
+            Err(LaunchError::MainClassNotFound(String::from("Missing"))),
+            launch(&options)
+        );
+
+        let options = launch_options(&with_jre("-cp testdata/classpath/broken.jar Missing"));
+        assert!(matches!(
+            launch(&options),
+            Err(LaunchError::MainClassUnreadable { class_name, .. }) if class_name == "Missing"
+        ));
+
+        let options = launch_options(&with_jre("-cp testdata/java8 java.lang.Object"));
+        assert_eq!(
+            Err(LaunchError::MainMethodNotFound(String::from(
+                "java.lang.Object"
+            ))),
+            launch(&options)
+        );
+
+        let options = launch_options(&with_jre("-cp testdata/java17 sample.Task"));
+        let err = launch(&options).unwrap_err();
+        assert_eq!(
+            "Error: LinkageError occurred while loading main class sample.Task\n\tjava.lang.NoClassDefFoundError: java/lang/Runnable",
+            err.to_string()
+        );
+
+        let options = launch_options(&with_jre("-jar testdata/java8/jar/hello.jar"));
+        assert_eq!(
+            Err(LaunchError::NoMainManifestAttribute(String::from(
+                "testdata/java8/jar/hello.jar"
+            ))),
+            launch(&options)
+        );
+
+        let options = launch_options("-Djava.home=testdata/java8 -cp testdata/java8 HelloWorld");
+        let err = launch(&options).unwrap_err();
+        assert_eq!(
+            "Error: java home testdata/java8 has no lib/rt.jar, lib/modules or jmods\nError: Could not find Java SE Runtime Environment.",
+            err.to_string()
+        );
+    }
+}
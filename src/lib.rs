@@ -1,42 +1,54 @@
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::classpath::class_path::ClassPath;
+use crate::classpath::java_home::JavaHomeError;
 use crate::instructions::decoder::decoder;
 use crate::rtda::heap::runtime::Runtime;
 use crate::rtda::thread::Thread;
-use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::error::Error;
+use std::rc::Rc;
 
 pub mod classfile;
 pub mod classpath;
 pub mod instructions;
+pub mod javap;
+pub mod launcher;
 pub mod rtda;
 
-pub fn create_main_thread(jre_home: String, source: String) -> Rc<RefCell<Thread>> {
-    let cp = ClassPath::parse(String::from(jre_home), String::from(source));
+/// `jre_home` of `None` looks the runtime up through `JAVA_HOME` and the
+/// usual install locations.
+pub fn create_main_thread(
+    jre_home: Option<String>,
+    source: String,
+) -> Result<Rc<RefCell<Thread>>, JavaHomeError> {
+    let cp = ClassPath::parse(jre_home, source)?;
     let runtime = Runtime::new(cp);
 
     let mut main_thread = Rc::new(RefCell::new(Thread::new(runtime)));
 
     main_thread.borrow_mut().invoke_method_with_shim();
 
-    main_thread
+    Ok(main_thread)
 }
 
-pub fn start_vm(jre: String, source: String) {
-    let thread = create_main_thread(jre, source);
+pub fn start_vm(jre: Option<String>, source: String) -> Result<(), Box<dyn Error>> {
+    let thread = create_main_thread(jre, source)?;
     // loop here
-    looper(thread)
+    looper(thread)?;
+    Ok(())
 }
 
-fn looper(thread: Rc<RefCell<Thread>>) {
+fn looper(thread: Rc<RefCell<Thread>>) -> Result<(), ClassFormatError> {
     let current_frame = thread.borrow_mut().current_frame();
     match current_frame {
         None => {}
         Some(mut frame) => {
-            let mut vec = decoder(frame.clone().method.method_data.code);
+            let mut vec = decoder(frame.method.method_data.code.clone())?;
             vec[0].ins.execute(&mut frame);
         }
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -51,24 +63,27 @@ mod tests {
 
     #[test]
     fn test_start_vm() {
-        let source = String::from("testdata/java8/HelloWorld.Class");
-        let jre_home = "/Library/Java/JavaVirtualMachines/jdk1.8.0_202.jdk/Contents/Home/jre";
-        start_vm(String::from(jre_home), source);
+        let source = String::from("testdata/java8");
+        let jre_home = String::from("testdata/jre");
+        start_vm(Some(jre_home), source).unwrap();
     }
 
     #[test]
     fn test_stack() {
-        let runtime = Runtime::new(ClassPath::new());
-        let source = String::from("testdata/java8/HelloWorld.Class");
-        let mut class_loader = runtime.boot_loader;
-        class_loader.add_user_class(source);
+        let class_path = ClassPath::parse(
+            Some(String::from("testdata/jre")),
+            String::from("testdata/java8"),
+        );
+        let runtime = Runtime::new(class_path.unwrap());
+        let class_loader = runtime.boot_loader;
+        class_loader.load_class("HelloWorld").unwrap();
     }
 
     #[test]
     fn test_main_thread() {
-        let source = String::from("testdata/java8/HelloWorld.Class");
-        let jre_home = "/Library/Java/JavaVirtualMachines/jdk1.8.0_202.jdk/Contents/Home/jre";
-        create_main_thread(String::from(jre_home), source);
+        let source = String::from("testdata/java8");
+        let jre_home = String::from("testdata/jre");
+        create_main_thread(Some(jre_home), source).unwrap();
     }
 
     #[test]
@@ -1,24 +1,37 @@
-struct C;
+use std::env;
+use std::process;
 
-struct B<'b> {
-    c: &'b C,
-}
-
-struct A<'a> {
-    b: B<'a>,
-    c: &'a C, // now this is a reference too
-}
-
-impl<'a> A<'a> {
-    fn new(c: &'a C) -> A<'a> {
-        A {c: c, b: B{c: c}}
-    }
-}
+use jvm::launcher::{launch, parse_args, Launch, USAGE};
+use jvm::rtda::thread::VmExit;
 
 fn main() {
-    let c1 = C;
-    let _ = A {
-        c: &c1,
-        b: B { c: &c1 },
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(Launch::Run(options)) => options,
+        Ok(Launch::Help) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Ok(Launch::Version) => {
+            eprintln!("jvm.rust version \"{}\"", env!("CARGO_PKG_VERSION"));
+            return;
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(err.exit_code());
+        }
     };
+
+    match launch(&options) {
+        Ok(exit) => {
+            if let VmExit::UncaughtException(class_name) = &exit {
+                eprintln!("Exception in thread \"main\" {}", class_name);
+            }
+            process::exit(exit.code());
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(err.exit_code());
+        }
+    }
 }
@@ -1,8 +1,12 @@
+use crate::rtda::heap::class_init::initialize_class;
+use crate::rtda::heap::class_loader::ClassLoader;
+use crate::rtda::heap::instanced_klass::InstanceKlass;
 use crate::rtda::heap::j_constant::JConstant;
 use crate::rtda::heap::j_method::JMethod;
-use crate::rtda::heap::slot::Slot;
+use crate::rtda::heap::slot::{EmptySlot, Slot};
 use crate::rtda::thread::Thread;
 use std::cell::RefCell;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct LocalVars {
@@ -13,6 +17,28 @@ impl LocalVars {
     pub fn new() -> LocalVars {
         LocalVars { slots: vec![] }
     }
+
+    /// `max_locals` slots, each holding zero or `null`.
+    pub fn with_size(max_locals: usize) -> LocalVars {
+        LocalVars {
+            slots: vec![EmptySlot::new(); max_locals],
+        }
+    }
+
+    /// Slot `index`, zero or `null` past the ones the method declared.
+    pub fn get(&self, index: usize) -> Slot {
+        match self.slots.get(index) {
+            Some(slot) => slot.clone(),
+            None => EmptySlot::new(),
+        }
+    }
+
+    pub fn set(&mut self, index: usize, slot: Slot) {
+        if index >= self.slots.len() {
+            self.slots.resize(index + 1, EmptySlot::new());
+        }
+        self.slots[index] = slot;
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +54,19 @@ impl OperandStack {
             slots: vec![],
         }
     }
+
+    pub fn push(&mut self, slot: Slot) {
+        self.slots.push(slot);
+        self.size += 1;
+    }
+
+    /// The top slot, `None` when the value came from an instruction that
+    /// does not push yet.
+    pub fn pop(&mut self) -> Option<Slot> {
+        let slot = self.slots.pop()?;
+        self.size -= 1;
+        Some(slot)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -35,26 +74,52 @@ pub struct Frame {
     pub local_vars: LocalVars,
     pub operand_stack: OperandStack,
     pub thread: RefCell<Thread>,
-    pub method: JMethod,
+    pub method: Rc<JMethod>,
     pub max_locals: u16,
     pub max_stack: u16,
     pub next_pc: u16  // Program Counter
 }
 
 impl Frame {
-    pub fn new(thread: RefCell<Thread>, method: JMethod) -> Frame {
+    pub fn new(thread: RefCell<Thread>, method: Rc<JMethod>) -> Frame {
         Frame {
-            max_locals: 0,
-            max_stack: 0,
+            max_locals: method.max_locals,
+            max_stack: method.max_stack,
             thread: thread,
-            local_vars: LocalVars::new(),
+            local_vars: LocalVars::with_size(method.max_locals as usize),
             operand_stack: OperandStack::new(),
             method,
             next_pc: 0
         }
     }
 
-    pub fn get_constant_pool(self) -> Vec<JConstant> {
-        self.method.klass.constant_pool
+    /// the class of the running method
+    pub fn klass(&self) -> Option<Rc<InstanceKlass>> {
+        self.method.klass()
+    }
+
+    /// Entry `index` of the run-time constant pool of the running method.
+    pub fn constant(&self, index: usize) -> Option<JConstant> {
+        self.klass()?.constant_pool.get(index).cloned()
+    }
+
+    /// The loader of the running class.
+    pub fn class_loader(&self) -> ClassLoader {
+        (*self.thread.borrow().runtime.boot_loader).clone()
+    }
+
+    /// Initializes `klass` on this frame's thread, see `initialize_class`.
+    pub fn initialize_class(&self, klass: &Rc<InstanceKlass>) -> Result<(), String> {
+        initialize_class(&self.thread.borrow(), klass)
+    }
+
+    /// Stops this frame's thread with `status`, see `Thread::exit`.
+    pub fn exit(&self, status: i32) {
+        self.thread.borrow_mut().exit(status)
+    }
+
+    /// Throws `exception` in this frame's thread, see `Thread::throw`.
+    pub fn throw(&self, exception: String) {
+        self.thread.borrow_mut().throw(exception)
     }
 }
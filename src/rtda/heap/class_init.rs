@@ -0,0 +1,340 @@
+//! Class initialization (JVMS 5.5): `<clinit>` runs on the first `new`,
+//! `getstatic`, `putstatic` or `invokestatic` of a class, after its
+//! superclass is initialized.
+
+use std::rc::Rc;
+use std::sync::{Condvar, Mutex};
+use std::thread::{self, ThreadId};
+
+use crate::rtda::heap::class_loader::ClassLoader;
+use crate::rtda::heap::instanced_klass::{ClassState, Initialization, InstanceKlass};
+use crate::rtda::heap::j_constant::JConstant;
+use crate::rtda::heap::slot::{IntSlot, Slot};
+use crate::rtda::thread::{execute_method, Thread, VmExit};
+
+const ERROR: &str = "java/lang/Error";
+
+/// The initialization lock of JVMS 5.5: the state of a class together with
+/// the thread running its `<clinit>`. Other threads wait on it until that
+/// thread is done, the thread itself may use the class right away.
+#[derive(Debug, Default)]
+pub struct InitLock {
+    state: Mutex<(ClassState, Option<ThreadId>)>,
+    done: Condvar,
+}
+
+impl InitLock {
+    pub fn state(&self) -> ClassState {
+        self.state.lock().unwrap().0
+    }
+
+    pub fn set_state(&self, state: ClassState) {
+        self.state.lock().unwrap().0 = state;
+    }
+
+    /// See `InstanceKlass::begin_initialization`.
+    pub fn begin(&self) -> Initialization {
+        let current = thread::current().id();
+        let mut guard = self.state.lock().unwrap();
+        while guard.0 == ClassState::Initializing && guard.1 != Some(current) {
+            guard = self.done.wait(guard).unwrap();
+        }
+        match guard.0 {
+            // a recursive request while `<clinit>` runs
+            ClassState::Initializing | ClassState::Initialized => Initialization::Done,
+            ClassState::Erroneous => Initialization::Erroneous,
+            ClassState::Loaded | ClassState::Linked => {
+                *guard = (ClassState::Initializing, Some(current));
+                Initialization::Started
+            }
+        }
+    }
+
+    /// See `InstanceKlass::finish_initialization`.
+    pub fn finish(&self, state: ClassState) {
+        *self.state.lock().unwrap() = (state, None);
+        self.done.notify_all();
+    }
+}
+
+/// Links and initializes `klass` on `thread` unless that happened before.
+/// A failure is the exception to throw, as `VmExit::UncaughtException`
+/// holds it.
+pub fn initialize_class(thread: &Thread, klass: &Rc<InstanceKlass>) -> Result<(), String> {
+    thread
+        .runtime
+        .boot_loader
+        .link_class(klass)
+        .map_err(|cause| cause.exception())?;
+    match klass.begin_initialization() {
+        Initialization::Started => {}
+        Initialization::Done => return Ok(()),
+        Initialization::Erroneous => {
+            return Err(format!(
+                "java.lang.NoClassDefFoundError: Could not initialize class {}",
+                klass.klass_name.replace('/', ".")
+            ))
+        }
+    }
+
+    seed_constant_values(klass);
+    let initialized =
+        initialize_supertypes(thread, klass).and_then(|_| run_class_initializer(thread, klass));
+    match initialized {
+        Ok(()) => klass.finish_initialization(ClassState::Initialized),
+        Err(_) => klass.finish_initialization(ClassState::Erroneous),
+    }
+    initialized
+}
+
+/// `static final` fields with a `ConstantValue` attribute hold their value
+/// before any initializer runs. Strings wait for a heap.
+fn seed_constant_values(klass: &InstanceKlass) {
+    for field in &klass.fields {
+        let value_index = match field.constant_value_index {
+            Some(index) if field.is_static() && field.access_flags.is_final() => index,
+            _ => continue,
+        };
+        let value = match klass.constant_pool.get(value_index as usize) {
+            Some(JConstant::Integer { val }) => IntSlot::new(*val),
+            Some(JConstant::Long { val }) => Slot::from_long(*val),
+            Some(JConstant::Float { val }) => Slot::from_float(*val),
+            Some(JConstant::Double { val }) => Slot::from_double(*val),
+            _ => continue,
+        };
+        klass.static_vars.borrow_mut()[field.slot] = value;
+    }
+}
+
+/// The superclass comes first, then the superinterfaces declaring default
+/// methods. Interfaces do not initialize their superinterfaces.
+fn initialize_supertypes(thread: &Thread, klass: &InstanceKlass) -> Result<(), String> {
+    if klass.access_flags.is_interface() {
+        return Ok(());
+    }
+    if let Some(super_klass) = klass.super_klass() {
+        initialize_class(thread, &super_klass)?;
+    }
+    initialize_default_method_interfaces(thread, klass)
+}
+
+// each superinterface after its own superinterfaces, in declaration order
+fn initialize_default_method_interfaces(
+    thread: &Thread,
+    klass: &InstanceKlass,
+) -> Result<(), String> {
+    for interface in klass.interface_klasses() {
+        initialize_default_method_interfaces(thread, interface)?;
+        let has_default_method = interface
+            .methods
+            .iter()
+            .any(|method| !method.access_flags.is_abstract() && !method.access_flags.is_static());
+        if has_default_method {
+            initialize_class(thread, interface)?;
+        }
+    }
+    Ok(())
+}
+
+fn run_class_initializer(thread: &Thread, klass: &InstanceKlass) -> Result<(), String> {
+    let clinit = match klass.find_declared_method("<clinit>", "()V") {
+        Some(clinit) if clinit.access_flags.is_static() => clinit,
+        _ => return Ok(()),
+    };
+    let mut frame = thread.new_frame(Rc::clone(&clinit));
+    execute_method(&mut frame, clinit.method_data.code.clone())
+        .map_err(|cause| format!("java.lang.ClassFormatError: {}", cause))?;
+
+    let exit = frame.thread.borrow_mut().exit.take();
+    match exit {
+        Some(VmExit::UncaughtException(exception)) => {
+            Err(initializer_error(&thread.runtime.boot_loader, exception))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// An exception out of `<clinit>` other than an `Error` is wrapped in an
+/// `ExceptionInInitializerError`.
+fn initializer_error(loader: &ClassLoader, exception: String) -> String {
+    if is_error(loader, &exception) {
+        return exception;
+    }
+    format!(
+        "java.lang.ExceptionInInitializerError\nCaused by: {}",
+        exception
+    )
+}
+
+fn is_error(loader: &ClassLoader, exception: &str) -> bool {
+    let class_name = exception
+        .split([':', '\n'])
+        .next()
+        .unwrap_or("")
+        .replace('.', "/");
+    let mut klass = loader.load_class(&class_name).ok();
+    while let Some(current) = klass {
+        if current.klass_name == ERROR {
+            return true;
+        }
+        klass = current.super_klass();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::classpath::class_path::ClassPath;
+    use crate::rtda::heap::class_init::{initialize_class, initializer_error, InitLock};
+    use crate::rtda::heap::instanced_klass::{ClassState, Initialization, InstanceKlass};
+    use crate::rtda::heap::runtime::Runtime;
+    use crate::rtda::thread::Thread;
+
+    fn thread() -> Thread {
+        let class_path = ClassPath::parse(
+            Some(String::from("testdata/jre")),
+            String::from("testdata/init"),
+        );
+        Thread::new(Runtime::new(class_path.unwrap()))
+    }
+
+    fn load(thread: &Thread, class_name: &str) -> Rc<InstanceKlass> {
+        thread.runtime.boot_loader.load_class(class_name).unwrap()
+    }
+
+    #[test]
+    fn should_initialize_superclass_first_and_seed_constants() {
+        let thread = thread();
+        let child = load(&thread, "Child");
+        let parent = load(&thread, "Parent");
+        assert_eq!(ClassState::Loaded, parent.state());
+
+        initialize_class(&thread, &child).unwrap();
+        assert_eq!(ClassState::Initialized, child.state());
+        assert_eq!(ClassState::Initialized, parent.state());
+
+        let statics = parent.static_vars.borrow();
+        let slot = |name: &str| {
+            let field = parent.fields.iter().find(|field| field.name == name);
+            field.unwrap().slot
+        };
+        assert_eq!(42, statics[slot("CONST")].int());
+        assert_eq!(1 << 40, statics[slot("BIG")].long());
+        assert_eq!(0.5, statics[slot("RATIO")].double());
+        assert_eq!(slot("BIG") + 2, slot("RATIO"));
+    }
+
+    #[test]
+    fn should_initialize_the_class_declaring_a_field() {
+        let thread = thread();
+        let peek = load(&thread, "Peek");
+        initialize_class(&thread, &peek).unwrap();
+        assert_eq!(ClassState::Initialized, load(&thread, "Parent").state());
+        assert_eq!(ClassState::Loaded, load(&thread, "Child").state());
+
+        let trigger = load(&thread, "Trigger");
+        initialize_class(&thread, &trigger).unwrap();
+        assert_eq!(ClassState::Initialized, load(&thread, "Helper").state());
+        assert_eq!(ClassState::Initialized, load(&thread, "Made").state());
+    }
+
+    #[test]
+    fn should_allow_recursive_initialization() {
+        let thread = thread();
+        let ping = load(&thread, "Ping");
+        initialize_class(&thread, &ping).unwrap();
+        assert_eq!(ClassState::Initialized, ping.state());
+        assert_eq!(ClassState::Initialized, load(&thread, "Pong").state());
+    }
+
+    fn begin_on_other_thread(lock: &Arc<InitLock>) -> mpsc::Receiver<Initialization> {
+        let lock = Arc::clone(lock);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || sender.send(lock.begin()).unwrap());
+        receiver
+    }
+
+    #[test]
+    fn should_wait_while_another_thread_initializes() {
+        let lock = Arc::new(InitLock::default());
+        assert_eq!(Initialization::Started, lock.begin());
+        assert_eq!(Initialization::Done, lock.begin());
+
+        let waiting = begin_on_other_thread(&lock);
+        let timeout = Duration::from_millis(50);
+        assert_eq!(
+            Err(RecvTimeoutError::Timeout),
+            waiting.recv_timeout(timeout)
+        );
+        lock.finish(ClassState::Initialized);
+        assert_eq!(Ok(Initialization::Done), waiting.recv());
+    }
+
+    #[test]
+    fn should_wake_waiting_threads_when_initialization_fails() {
+        let lock = Arc::new(InitLock::default());
+        lock.set_state(ClassState::Linked);
+        assert_eq!(Initialization::Started, lock.begin());
+
+        let waiting = begin_on_other_thread(&lock);
+        let timeout = Duration::from_millis(50);
+        assert_eq!(
+            Err(RecvTimeoutError::Timeout),
+            waiting.recv_timeout(timeout)
+        );
+        lock.finish(ClassState::Erroneous);
+        assert_eq!(Ok(Initialization::Erroneous), waiting.recv());
+        assert_eq!(ClassState::Erroneous, lock.state());
+    }
+
+    #[test]
+    fn should_only_initialize_interfaces_with_default_methods() {
+        let thread = thread();
+        let circle = load(&thread, "Circle");
+        initialize_class(&thread, &circle).unwrap();
+        assert_eq!(ClassState::Initialized, load(&thread, "Shape").state());
+        assert_eq!(ClassState::Linked, load(&thread, "Marker").state());
+    }
+
+    #[test]
+    fn should_mark_class_erroneous_when_initializer_fails() {
+        let thread = thread();
+        let broken = load(&thread, "Broken");
+        assert_eq!(
+            Err(String::from(
+                "java.lang.NoSuchFieldError: Class Holder does not have member field 'I missing'"
+            )),
+            initialize_class(&thread, &broken)
+        );
+        assert_eq!(ClassState::Erroneous, broken.state());
+        assert_eq!(
+            Err(String::from(
+                "java.lang.NoClassDefFoundError: Could not initialize class Broken"
+            )),
+            initialize_class(&thread, &broken)
+        );
+    }
+
+    #[test]
+    fn should_wrap_exceptions_other_than_errors() {
+        let thread = thread();
+        let loader = &thread.runtime.boot_loader;
+        assert_eq!(
+            "java.lang.ExceptionInInitializerError\nCaused by: java.lang.IllegalStateException: boom",
+            initializer_error(loader, String::from("java.lang.IllegalStateException: boom"))
+        );
+        let error =
+            "java.lang.ExceptionInInitializerError\nCaused by: java.lang.IllegalStateException";
+        assert_eq!(error, initializer_error(loader, String::from(error)));
+        assert_eq!(
+            "java.lang.NoSuchFieldError: x",
+            initializer_error(loader, String::from("java.lang.NoSuchFieldError: x"))
+        );
+    }
+}
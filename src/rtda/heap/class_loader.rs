@@ -1,37 +1,523 @@
+use crate::classfile::access_flags::AccessFlags;
 use crate::classfile::class_factory::ClassFactory;
-use crate::classpath::class_file_entry::ClassFileEntry;
-use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::classfile::class_file_stream::ClassFileStream;
+use crate::classfile::class_format_error::ClassFormatError;
+use crate::classfile::constant_member_ref::ConstantMemberRef;
+use crate::classfile::parsed_class::ParsedClass;
+use crate::classpath::class_path::ClassPath;
+use crate::rtda::heap::instanced_klass::{
+    ClassState, InstanceKlass, ResolvedField, ResolvedRef, Supertypes,
+};
+use crate::rtda::heap::j_constant::JConstant;
+use crate::rtda::heap::j_method::JMethod;
+use crate::rtda::heap::method_table::{build_method_tables, check_final_overrides};
+use crate::rtda::heap::slot::EmptySlot;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::rc::Rc;
 
+/// Why a class could not be defined, `NoClassDefFoundError`,
+/// `ClassFormatError` or another `LinkageError` on the Java side.
+#[derive(Debug)]
+pub enum ClassLoadError {
+    NotFound(String),
+    Io {
+        class_name: String,
+        cause: io::Error,
+    },
+    Format {
+        class_name: String,
+        cause: ClassFormatError,
+    },
+    /// the class file at `class_name` defines `found`
+    WrongName {
+        class_name: String,
+        found: String,
+    },
+    /// the class is its own superclass or superinterface
+    Circularity(String),
+    /// the loader already defined the class
+    Duplicate(String),
+    /// another `LinkageError`, `error` naming its class such as
+    /// `java.lang.NoSuchFieldError`
+    Linkage {
+        error: &'static str,
+        message: String,
+    },
+}
+
+pub const ABSTRACT_METHOD_ERROR: &str = "java.lang.AbstractMethodError";
+pub const INCOMPATIBLE_CLASS_CHANGE_ERROR: &str = "java.lang.IncompatibleClassChangeError";
+pub const ILLEGAL_ACCESS_ERROR: &str = "java.lang.IllegalAccessError";
+pub const NO_SUCH_FIELD_ERROR: &str = "java.lang.NoSuchFieldError";
+pub const NO_SUCH_METHOD_ERROR: &str = "java.lang.NoSuchMethodError";
+pub const VERIFY_ERROR: &str = "java.lang.VerifyError";
+
+impl ClassLoadError {
+    /// The `java.lang` error this is thrown as.
+    pub fn java_class(&self) -> &'static str {
+        match self {
+            ClassLoadError::NotFound(_)
+            | ClassLoadError::Io { .. }
+            | ClassLoadError::WrongName { .. } => "java.lang.NoClassDefFoundError",
+            ClassLoadError::Format { .. } => "java.lang.ClassFormatError",
+            ClassLoadError::Circularity(_) => "java.lang.ClassCircularityError",
+            ClassLoadError::Duplicate(_) => "java.lang.LinkageError",
+            ClassLoadError::Linkage { error, .. } => error,
+        }
+    }
+
+    /// The error as thrown, `java.lang.NoClassDefFoundError: java/lang/Object`.
+    pub fn exception(&self) -> String {
+        format!("{}: {}", self.java_class(), self)
+    }
+}
+
+impl fmt::Display for ClassLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClassLoadError::NotFound(class_name) => write!(f, "{}", class_name),
+            ClassLoadError::Io { class_name, cause } => {
+                write!(f, "{}: {}", class_name, cause)
+            }
+            ClassLoadError::Format { class_name, cause } => {
+                write!(f, "{}: {}", class_name, cause)
+            }
+            ClassLoadError::WrongName { class_name, found } => {
+                write!(f, "{} (wrong name: {})", class_name, found)
+            }
+            ClassLoadError::Circularity(class_name) => write!(f, "{}", class_name),
+            ClassLoadError::Duplicate(class_name) => {
+                write!(f, "duplicate class definition: {}", class_name)
+            }
+            ClassLoadError::Linkage { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for ClassLoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ClassLoadError::Io { cause, .. } => Some(cause),
+            ClassLoadError::Format { cause, .. } => Some(cause),
+            _ => None,
+        }
+    }
+}
+
+/// A class loader. Clones share its classes, the runtime hands it to every
+/// thread and frame.
 #[derive(Debug, Clone)]
 pub struct ClassLoader {
-    pub jl_object_class: Vec<InstanceKlass>,
+    /// the classes this loader defined, by binary name such as
+    /// `java/lang/String`
+    classes: Rc<RefCell<HashMap<String, Rc<InstanceKlass>>>>,
+    /// classes whose supertypes are being loaded, to catch cycles
+    loading: Rc<RefCell<HashSet<String>>>,
+    class_path: Rc<ClassPath>,
 }
 
 impl ClassLoader {
     pub fn new() -> ClassLoader {
+        ClassLoader::with_class_path(Rc::new(ClassPath::new()))
+    }
+
+    pub fn with_class_path(class_path: Rc<ClassPath>) -> ClassLoader {
         ClassLoader {
-            jl_object_class: vec![],
+            classes: Rc::new(RefCell::new(HashMap::new())),
+            loading: Rc::new(RefCell::new(HashSet::new())),
+            class_path,
         }
     }
 
     pub fn init(&mut self) {}
 
-    pub fn add_user_class(&mut self, class_name: String) {
-        let klass = self.build_user_class(class_name);
-        self.jl_object_class.push(klass);
+    pub fn class_path(&self) -> &ClassPath {
+        &self.class_path
     }
 
-    pub fn build_user_class(&mut self, class_name: String) -> InstanceKlass {
-        let file_name = self.file_name_for_class_name(class_name);
-        let entry = ClassFileEntry::new();
+    /// The class `class_name` once this loader defined it.
+    pub fn find_loaded_class(&self, class_name: &str) -> Option<Rc<InstanceKlass>> {
+        self.classes.borrow().get(class_name).cloned()
+    }
 
-        let stream = entry.open_stream(file_name);
-        let klass = ClassFactory::create_from_stream(stream);
-        klass
+    /// Loads `java/lang/String`, or hands out the class defined before.
+    /// Its superclass and interfaces are loaded first, a class is only
+    /// defined once they are.
+    pub fn load_class(&self, class_name: &str) -> Result<Rc<InstanceKlass>, ClassLoadError> {
+        if let Some(klass) = self.find_loaded_class(class_name) {
+            return Ok(klass);
+        }
+        if class_name.starts_with('[') {
+            return self.define_array_class(class_name);
+        }
+        if self.loading.borrow().contains(class_name) {
+            return Err(ClassLoadError::Circularity(String::from(class_name)));
+        }
+        let mut class = self.parse_class(class_name)?;
+        self.define_class(class_name, &mut class)
+    }
+
+    /// Defines `class` under `class_name` after loading its supertypes.
+    pub fn define_class(
+        &self,
+        class_name: &str,
+        class: &mut ParsedClass,
+    ) -> Result<Rc<InstanceKlass>, ClassLoadError> {
+        if self.classes.borrow().contains_key(class_name) {
+            return Err(ClassLoadError::Duplicate(String::from(class_name)));
+        }
+        if class.class_name() != class_name {
+            return Err(ClassLoadError::WrongName {
+                class_name: String::from(class_name),
+                found: String::from(class.class_name()),
+            });
+        }
+
+        self.loading.borrow_mut().insert(String::from(class_name));
+        let supertypes = self.load_supertypes(class);
+        self.loading.borrow_mut().remove(class_name);
+        let klass = class.create_instance_klass(supertypes?);
+
+        self.classes
+            .borrow_mut()
+            .insert(String::from(class_name), Rc::clone(&klass));
+        Ok(klass)
     }
 
-    fn file_name_for_class_name(&mut self, class_name: String) -> String {
-        class_name
+    // array classes are created by the loader rather than read, after the
+    // class of their elements (JVMS 5.3.3)
+    fn define_array_class(&self, class_name: &str) -> Result<Rc<InstanceKlass>, ClassLoadError> {
+        let component = &class_name[1..];
+        if component.starts_with('[') {
+            self.load_class(component)?;
+        } else if component.starts_with('L') && component.ends_with(';') {
+            self.load_class(&component[1..component.len() - 1])?;
+        } else if component.len() != 1 || !"BCDFIJSZ".contains(component) {
+            return Err(ClassLoadError::NotFound(String::from(class_name)));
+        }
+        let supertypes = Supertypes {
+            super_klass: Some(self.load_class("java/lang/Object")?),
+            interfaces: vec![
+                self.load_class("java/lang/Cloneable")?,
+                self.load_class("java/io/Serializable")?,
+            ],
+        };
+        let klass = Rc::new(InstanceKlass::new_array_klass(class_name, supertypes));
+        self.classes
+            .borrow_mut()
+            .insert(String::from(class_name), Rc::clone(&klass));
+        Ok(klass)
+    }
+
+    fn load_supertypes(&self, class: &ParsedClass) -> Result<Supertypes, ClassLoadError> {
+        // only java/lang/Object has no superclass
+        let super_klass = match class.super_class_name() {
+            Some(name) => Some(self.load_class(name)?),
+            None => None,
+        };
+        let mut interfaces = vec![];
+        for interface in class.interface_names() {
+            interfaces.push(self.load_class(interface)?);
+        }
+        Ok(Supertypes {
+            super_klass,
+            interfaces,
+        })
+    }
+
+    /// Verifies and prepares `klass`, after its superclass and interfaces.
+    /// Linking a class twice does nothing.
+    pub fn link_class(&self, klass: &Rc<InstanceKlass>) -> Result<(), ClassLoadError> {
+        if klass.state() >= ClassState::Linked {
+            return Ok(());
+        }
+        if let Some(super_klass) = klass.super_klass() {
+            self.link_class(&super_klass)?;
+        }
+        for interface in klass.interface_klasses().iter() {
+            self.link_class(interface)?;
+        }
+        verify(klass)?;
+        prepare(klass);
+        klass.set_state(ClassState::Linked);
+        Ok(())
+    }
+
+    /// Resolves the `CONSTANT_Class` entry `index` of `from`, loading the
+    /// class on first use.
+    pub fn resolve_class(
+        &self,
+        from: &Rc<InstanceKlass>,
+        index: usize,
+    ) -> Result<Rc<InstanceKlass>, ClassLoadError> {
+        if let Some(ResolvedRef::Class(klass)) = from.resolved_ref(index) {
+            return Ok(klass);
+        }
+        let name = match from.class_ref_name(index) {
+            Some(name) => name,
+            None => return Err(not_a_constant(from, index, "class")),
+        };
+        let klass = self.resolve_class_name(from, name)?;
+        from.set_resolved_ref(index, ResolvedRef::Class(Rc::clone(&klass)));
+        Ok(klass)
+    }
+
+    fn resolve_class_name(
+        &self,
+        from: &InstanceKlass,
+        class_name: &str,
+    ) -> Result<Rc<InstanceKlass>, ClassLoadError> {
+        let klass = self.load_class(class_name)?;
+        if !klass.access_flags.is_public() && klass.package_name() != from.package_name() {
+            return Err(ClassLoadError::Linkage {
+                error: ILLEGAL_ACCESS_ERROR,
+                message: format!(
+                    "failed to access class {} from class {}",
+                    klass.klass_name, from.klass_name
+                ),
+            });
+        }
+        Ok(klass)
+    }
+
+    /// Resolves the `CONSTANT_Fieldref` entry `index` of `from` to the class
+    /// declaring the field, searching the referenced class, then its
+    /// superinterfaces, then its superclasses (JVMS 5.4.3.2).
+    pub fn resolve_field(
+        &self,
+        from: &Rc<InstanceKlass>,
+        index: usize,
+    ) -> Result<ResolvedField, ClassLoadError> {
+        if let Some(ResolvedRef::Field(field)) = from.resolved_ref(index) {
+            return Ok(field);
+        }
+        let member_ref = match from.constant_pool.get(index) {
+            Some(JConstant::ConstantField(field)) => &field.member_ref,
+            _ => return Err(not_a_constant(from, index, "field")),
+        };
+        let klass = self.resolve_class_name(from, &member_ref.class_name)?;
+        let field = match find_field(&klass, &member_ref.name, &member_ref.descriptor) {
+            Some(field) => field,
+            None => {
+                return Err(ClassLoadError::Linkage {
+                    error: NO_SUCH_FIELD_ERROR,
+                    message: format!(
+                        "Class {} does not have member field '{} {}'",
+                        klass.klass_name, member_ref.descriptor, member_ref.name
+                    ),
+                })
+            }
+        };
+        check_field_access(from, &field)?;
+        from.set_resolved_ref(index, ResolvedRef::Field(field.clone()));
+        Ok(field)
+    }
+
+    /// Resolves the `CONSTANT_Methodref` or `CONSTANT_InterfaceMethodref`
+    /// entry `index` of `from` to the method, declared by the referenced
+    /// class, a superclass or a superinterface (JVMS 5.4.3.3 and 5.4.3.4).
+    pub fn resolve_method(
+        &self,
+        from: &Rc<InstanceKlass>,
+        index: usize,
+    ) -> Result<Rc<JMethod>, ClassLoadError> {
+        if let Some(ResolvedRef::Method(method)) = from.resolved_ref(index) {
+            return Ok(method);
+        }
+        let (klass, member_ref) = self.resolve_method_class(from, index)?;
+        let method = match klass.find_method(&member_ref.name, &member_ref.descriptor) {
+            Some(method) => method,
+            None => {
+                return Err(ClassLoadError::Linkage {
+                    error: NO_SUCH_METHOD_ERROR,
+                    message: format!(
+                        "{}.{}{}",
+                        klass.klass_name.replace('/', "."),
+                        member_ref.name,
+                        member_ref.descriptor
+                    ),
+                })
+            }
+        };
+        check_method_access(from, &method)?;
+        from.set_resolved_ref(index, ResolvedRef::Method(Rc::clone(&method)));
+        Ok(method)
+    }
+
+    // the referenced class has to be an interface for interface method
+    // references only
+    fn resolve_method_class<'a>(
+        &self,
+        from: &'a InstanceKlass,
+        index: usize,
+    ) -> Result<(Rc<InstanceKlass>, &'a ConstantMemberRef), ClassLoadError> {
+        let (member_ref, interface) = match from.constant_pool.get(index) {
+            Some(JConstant::ConstantMethodRef(method)) => (&method.member_ref, false),
+            Some(JConstant::ConstantInterfaceMethodRef(method)) => (&method.member_ref, true),
+            _ => return Err(not_a_constant(from, index, "method")),
+        };
+        let klass = self.resolve_class_name(from, &member_ref.class_name)?;
+        if klass.access_flags.is_interface() != interface {
+            let kind = if interface { "interface" } else { "class" };
+            return Err(incompatible_class_change(format!(
+                "Found {} {}, but {} was expected",
+                if interface { "class" } else { "interface" },
+                klass.klass_name,
+                kind
+            )));
+        }
+        Ok((klass, member_ref))
+    }
+
+    /// Reads `class_name`, e.g. `java/lang/String`, from the class path
+    /// without defining it in this loader.
+    pub fn build_user_class(&self, class_name: &str) -> Result<Rc<InstanceKlass>, ClassLoadError> {
+        let bytes = self.read_class(class_name)?;
+        ClassFactory::create_from_stream(ClassFileStream::new(bytes))
+            .map_err(|cause| format_error(class_name, cause))
+    }
+
+    fn parse_class(&self, class_name: &str) -> Result<ParsedClass, ClassLoadError> {
+        let bytes = self.read_class(class_name)?;
+        ParsedClass::new(ClassFileStream::new(bytes))
+            .map_err(|cause| format_error(class_name, cause))
+    }
+
+    fn read_class(&self, class_name: &str) -> Result<Vec<u8>, ClassLoadError> {
+        match self.class_path.read_class(class_name) {
+            Ok(Some(found)) => Ok(found.bytes),
+            Ok(None) => Err(ClassLoadError::NotFound(String::from(class_name))),
+            Err(cause) => Err(ClassLoadError::Io {
+                class_name: String::from(class_name),
+                cause,
+            }),
+        }
+    }
+}
+
+/// The checks of JVMS 4.10 and 5.4.1 this VM makes: the supertypes have to
+/// be the kind of class the class file takes them for, and final methods
+/// stay final. Bytecode is not verified.
+fn verify(klass: &InstanceKlass) -> Result<(), ClassLoadError> {
+    if let Some(super_klass) = klass.super_klass() {
+        if super_klass.access_flags.is_interface() {
+            return Err(incompatible_class_change(format!(
+                "class {} has interface {} as super class",
+                klass.klass_name, super_klass.klass_name
+            )));
+        }
+        if super_klass.access_flags.is_final() {
+            return Err(ClassLoadError::Linkage {
+                error: VERIFY_ERROR,
+                message: format!(
+                    "Cannot inherit from final class {} in class {}",
+                    super_klass.klass_name, klass.klass_name
+                ),
+            });
+        }
+    }
+    for interface in klass.interface_klasses().iter() {
+        if !interface.access_flags.is_interface() {
+            return Err(incompatible_class_change(format!(
+                "class {} can not implement {}, because it is not an interface",
+                klass.klass_name, interface.klass_name
+            )));
+        }
+    }
+    check_final_overrides(klass)
+}
+
+/// Allocates the static fields, zeroed, and builds the dispatch tables.
+fn prepare(klass: &InstanceKlass) {
+    *klass.static_vars.borrow_mut() = vec![EmptySlot::new(); klass.static_slot_count()];
+    let (vtable, itable) = build_method_tables(klass);
+    klass.set_method_tables(vtable, itable);
+}
+
+fn find_field(klass: &Rc<InstanceKlass>, name: &str, descriptor: &str) -> Option<ResolvedField> {
+    if let Some(index) = klass.find_declared_field(name, descriptor) {
+        return Some(ResolvedField {
+            klass: Rc::clone(klass),
+            index,
+        });
+    }
+    for interface in klass.interface_klasses().iter() {
+        if let Some(field) = find_field(interface, name, descriptor) {
+            return Some(field);
+        }
+    }
+    find_field(&klass.super_klass()?, name, descriptor)
+}
+
+/// JVMS 5.4.4, private members are shared within a nest.
+fn is_accessible(from: &InstanceKlass, owner: &InstanceKlass, flags: AccessFlags) -> bool {
+    let same_package = owner.package_name() == from.package_name();
+    if flags.is_public() {
+        true
+    } else if flags.is_private() {
+        owner.nest_host_name() == from.nest_host_name()
+    } else if flags.is_protected() {
+        same_package || from.is_subclass_of(owner)
+    } else {
+        same_package
+    }
+}
+
+fn check_field_access(from: &InstanceKlass, field: &ResolvedField) -> Result<(), ClassLoadError> {
+    let owner = &field.klass;
+    if is_accessible(from, owner, field.field().access_flags) {
+        return Ok(());
+    }
+    Err(ClassLoadError::Linkage {
+        error: ILLEGAL_ACCESS_ERROR,
+        message: format!(
+            "class {} tried to access field {}.{}",
+            from.klass_name,
+            owner.klass_name,
+            field.field().name
+        ),
+    })
+}
+
+fn check_method_access(from: &InstanceKlass, method: &JMethod) -> Result<(), ClassLoadError> {
+    let owner = match method.klass() {
+        Some(owner) => owner,
+        None => return Ok(()),
+    };
+    if is_accessible(from, &owner, method.access_flags) {
+        return Ok(());
+    }
+    Err(ClassLoadError::Linkage {
+        error: ILLEGAL_ACCESS_ERROR,
+        message: format!(
+            "class {} tried to access method {}.{}{}",
+            from.klass_name, owner.klass_name, method.name, method.descriptor.text
+        ),
+    })
+}
+
+fn not_a_constant(from: &InstanceKlass, index: usize, kind: &str) -> ClassLoadError {
+    incompatible_class_change(format!(
+        "constant {} of class {} is not a {} reference",
+        index, from.klass_name, kind
+    ))
+}
+
+fn incompatible_class_change(message: String) -> ClassLoadError {
+    ClassLoadError::Linkage {
+        error: INCOMPATIBLE_CLASS_CHANGE_ERROR,
+        message,
+    }
+}
+
+fn format_error(class_name: &str, cause: ClassFormatError) -> ClassLoadError {
+    ClassLoadError::Format {
+        class_name: String::from(class_name),
+        cause,
     }
 }
 
@@ -39,8 +525,20 @@ impl ClassLoader {
 mod tests {
     use crate::classfile::attribute_info::AttributeInfo;
 
-    use crate::rtda::heap::class_loader::ClassLoader;
-    use crate::rtda::heap::instanced_klass::InstanceKlass;
+    use crate::classpath::class_path::ClassPath;
+    use crate::rtda::heap::class_loader::{ClassLoadError, ClassLoader};
+    use crate::rtda::heap::instanced_klass::{ClassState, InstanceKlass};
+    use crate::rtda::heap::j_constant::JConstant;
+    use crate::rtda::heap::j_method::JMethod;
+    use crate::rtda::heap::j_object::JObject;
+    use crate::rtda::heap::slot::{IntSlot, Slot};
+    use std::rc::Rc;
+
+    fn class_loader(path: &str) -> ClassLoader {
+        let mut class_path = ClassPath::new();
+        class_path.parse_user_class_path(String::from(path));
+        ClassLoader::with_class_path(Rc::new(class_path))
+    }
 
     #[test]
     fn test_should_get_basic_info() {
@@ -66,11 +564,9 @@ mod tests {
         assert_eq!(29, klass.constant_pool.len());
     }
 
-    fn build_klass() -> InstanceKlass {
-        let path = "testdata/java8/HelloWorld.Class";
-        let mut class_loader = ClassLoader::new();
-        let klass = class_loader.build_user_class(String::from(path));
-        klass
+    fn build_klass() -> Rc<InstanceKlass> {
+        let class_loader = class_loader("testdata/java8");
+        class_loader.build_user_class("HelloWorld").unwrap()
     }
 
     #[test]
@@ -102,7 +598,7 @@ mod tests {
     #[test]
     fn should_had_source_file() {
         let klass = build_klass();
-        for attr in klass.attributes {
+        for attr in &klass.attributes {
             match attr {
                 AttributeInfo::SourceFile(source) => {
                     assert!(true);
@@ -127,8 +623,532 @@ mod tests {
 
     #[test]
     fn should_get_array_type() {
-        let path = "testdata/java8/ArraySample.class";
-        let mut class_loader = ClassLoader::new();
-        let _klass = class_loader.build_user_class(String::from(path));
+        let class_loader = class_loader("testdata/java8");
+        let _klass = class_loader.build_user_class("ArraySample").unwrap();
+    }
+
+    #[test]
+    fn should_decode_modified_utf8_strings() {
+        let class_loader = class_loader("testdata/java8");
+        let klass = class_loader.build_user_class("StringSample").unwrap();
+
+        let expected = "nul:\0 emoji:\u{1F600} accent:\u{e9} cjk:\u{4e2d}";
+        let found = klass.constant_pool.iter().any(|constant| match constant {
+            JConstant::String(str) => str.go_str == expected,
+            _ => false,
+        });
+        assert!(found);
+    }
+
+    #[test]
+    fn should_expose_access_flags() {
+        let class_loader = class_loader("testdata/java17");
+        let shape = class_loader
+            .build_user_class("sample/AttributeSample$Shape")
+            .unwrap();
+        assert!(shape.access_flags.is_interface());
+        assert!(shape.access_flags.is_abstract());
+
+        let klass = class_loader
+            .build_user_class("sample/AttributeSample")
+            .unwrap();
+        assert!(klass.access_flags.is_public());
+        assert!(klass.access_flags.is_super());
+        assert!(!klass.access_flags.is_interface());
+
+        let limit = klass.fields[0].access_flags;
+        assert!(limit.is_public() && limit.is_static() && limit.is_final());
+
+        let lambda = klass.methods.last().unwrap();
+        assert!(lambda.access_flags.is_private());
+        assert!(lambda.access_flags.is_static());
+        assert!(lambda.access_flags.is_synthetic());
+    }
+
+    #[test]
+    fn should_report_missing_classes() {
+        let class_loader = class_loader("testdata/java8");
+        match class_loader.build_user_class("java/lang/Missing") {
+            Err(ClassLoadError::NotFound(name)) => assert_eq!("java/lang/Missing", name),
+            _ => panic!("expected a missing class"),
+        }
+        assert!(ClassLoader::new().load_class("HelloWorld").is_err());
+    }
+
+    fn class_loader_with_jre(path: &str) -> ClassLoader {
+        let class_path = ClassPath::parse(Some(String::from("testdata/jre")), String::from(path));
+        ClassLoader::with_class_path(Rc::new(class_path.unwrap()))
+    }
+
+    #[test]
+    fn should_load_class_once_with_its_supertypes() {
+        let class_loader = class_loader_with_jre("testdata/java17");
+        let circle = class_loader
+            .load_class("sample/AttributeSample$Circle")
+            .unwrap();
+        assert_eq!("sample/AttributeSample$Circle", circle.klass_name);
+        assert!(class_loader.find_loaded_class("java/lang/Object").is_some());
+        assert!(class_loader
+            .find_loaded_class("sample/AttributeSample$Shape")
+            .is_some());
+
+        let again = class_loader
+            .load_class("sample/AttributeSample$Circle")
+            .unwrap();
+        assert!(Rc::ptr_eq(&circle, &again));
+        let shape = class_loader.load_class("sample/AttributeSample$Shape");
+        assert!(Rc::ptr_eq(
+            &shape.unwrap(),
+            &class_loader
+                .find_loaded_class("sample/AttributeSample$Shape")
+                .unwrap()
+        ));
+    }
+
+    #[test]
+    fn should_share_class_with_its_methods() {
+        let class_loader = class_loader_with_jre("testdata/java8");
+        let klass = class_loader.load_class("HelloWorld").unwrap();
+        for method in &klass.methods {
+            assert!(Rc::ptr_eq(&klass, &method.klass().unwrap()));
+        }
+
+        let println = klass
+            .constant_pool
+            .iter()
+            .find_map(|constant| match constant {
+                JConstant::ConstantMethodRef(method) if method.member_ref.name == "println" => {
+                    Some(&method.member_ref)
+                }
+                _ => None,
+            });
+        assert_eq!("java/io/PrintStream", println.unwrap().class_name);
+    }
+
+    #[test]
+    fn should_not_define_class_with_missing_superclass() {
+        let class_loader = class_loader("testdata/java8");
+        match class_loader.load_class("HelloWorld") {
+            Err(ClassLoadError::NotFound(name)) => assert_eq!("java/lang/Object", name),
+            _ => panic!("expected a missing superclass"),
+        }
+        assert!(class_loader.find_loaded_class("HelloWorld").is_none());
+    }
+
+    #[test]
+    fn should_reject_class_file_with_wrong_name() {
+        let class_loader = class_loader_with_jre("testdata");
+        let err = class_loader.load_class("java8/HelloWorld").unwrap_err();
+        assert_eq!("java8/HelloWorld (wrong name: HelloWorld)", err.to_string());
+        assert_eq!("java.lang.NoClassDefFoundError", err.java_class());
+    }
+
+    #[test]
+    fn should_detect_circular_superclasses() {
+        let class_loader = class_loader_with_jre("testdata/circular");
+        match class_loader.load_class("Ouroboros") {
+            Err(ClassLoadError::Circularity(name)) => assert_eq!("Ouroboros", name),
+            _ => panic!("expected a class circularity"),
+        }
+        assert!(class_loader.find_loaded_class("Tail").is_none());
+    }
+
+    #[test]
+    fn should_link_supertypes_first_and_zero_statics() {
+        let class_loader = class_loader_with_jre("testdata/linkage");
+        let holder = class_loader.load_class("Holder").unwrap();
+        assert_eq!(ClassState::Loaded, holder.state());
+
+        class_loader.link_class(&holder).unwrap();
+        assert_eq!(ClassState::Linked, holder.state());
+        let base = holder.super_klass().unwrap();
+        assert_eq!(ClassState::Linked, base.state());
+        assert_eq!(ClassState::Linked, base.super_klass().unwrap().state());
+
+        // int count, long total and int secret
+        assert_eq!(4, holder.static_vars.borrow().len());
+        assert_eq!(1, base.static_vars.borrow().len());
+    }
+
+    fn field_slots(klass: &InstanceKlass) -> Vec<(&str, usize)> {
+        let fields = klass.fields.iter();
+        fields
+            .map(|field| (field.name.as_str(), field.slot))
+            .collect()
+    }
+
+    #[test]
+    fn should_lay_out_fields_after_inherited_ones() {
+        let class_loader = class_loader_with_jre("testdata/layout");
+        let point3 = class_loader.load_class("Point3").unwrap();
+        let point = point3.super_klass().unwrap();
+
+        // `long y` takes slots 1 and 2
+        let expected = vec![("origin", 0), ("x", 0), ("y", 1), ("label", 3)];
+        assert_eq!(expected, field_slots(&point));
+        assert!(point.fields[2].is_wide());
+        assert_eq!(2, point.static_slot_count());
+        assert_eq!(4, point.instance_slot_count());
+        assert_eq!(vec![("count", 0), ("z", 4)], field_slots(&point3));
+        assert_eq!(1, point3.static_slot_count());
+        assert_eq!(5, point3.instance_slot_count());
+
+        let object = Rc::new(JObject::new(&point3));
+        object.set_field(4, IntSlot::new(7));
+        object.set_field(1, Slot::from_long(-1));
+        assert_eq!(7, object.field(4).int());
+        assert_eq!(-1, object.field(1).long());
+        assert!(object.field(3).reference().is_none());
+
+        let other = JObject::new(&point);
+        other.set_field(3, Slot::from_reference(Rc::clone(&object)));
+        assert!(Rc::ptr_eq(&object, &other.field(3).reference().unwrap()));
+    }
+
+    #[test]
+    fn should_fill_method_tables() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let animal = class_loader.load_class("Animal").unwrap();
+        let names: Vec<&str> = animal.methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(vec!["<init>", "speak", "id", "name"], names);
+
+        let speak = &animal.methods[1];
+        assert!(speak.access_flags.is_abstract());
+        assert!(speak.method_data.code.is_empty());
+        assert!(animal.methods[2].access_flags.is_native());
+
+        let name = &animal.methods[3];
+        assert_eq!("()Ljava/lang/String;", name.descriptor.text);
+        assert_eq!(
+            vec!["java/io/IOException", "java/lang/InterruptedException"],
+            name.exceptions
+        );
+        let handler = &name.method_data.exception_table[0];
+        assert_eq!(
+            (0, 4, 5),
+            (handler.start_pc, handler.end_pc, handler.handler_pc)
+        );
+        assert_eq!(Some(8), name.line_number(4));
+        assert_eq!(Some(10), name.line_number(6));
+    }
+
+    #[test]
+    fn should_find_methods_in_class_hierarchy() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let dog = class_loader.load_class("Dog").unwrap();
+        let declaring_class = |name: &str, descriptor: &str| {
+            let method = dog.find_method(name, descriptor)?;
+            Some(method.klass()?.klass_name.clone())
+        };
+        let string = "()Ljava/lang/String;";
+        assert_eq!(Some(String::from("Dog")), declaring_class("speak", string));
+        assert_eq!(
+            Some(String::from("Animal")),
+            declaring_class("name", string)
+        );
+        assert_eq!(Some(String::from("Pet")), declaring_class("greet", string));
+        assert_eq!(Some(String::from("Dog")), declaring_class("owner", string));
+        assert_eq!(
+            Some(String::from("java/lang/Object")),
+            declaring_class("hashCode", "()I")
+        );
+        assert_eq!(None, declaring_class("speak", "()V"));
+    }
+
+    fn linked(class_loader: &ClassLoader, class_name: &str) -> Rc<InstanceKlass> {
+        let klass = class_loader.load_class(class_name).unwrap();
+        class_loader.link_class(&klass).unwrap();
+        klass
+    }
+
+    fn method(klass: &InstanceKlass, name: &str) -> Rc<JMethod> {
+        let method = klass.methods.iter().find(|method| method.name == name);
+        Rc::clone(method.unwrap())
+    }
+
+    fn declared_by(method: Option<Rc<JMethod>>) -> String {
+        let method = method.unwrap();
+        format!("{}.{}", method.klass().unwrap().klass_name, method.name)
+    }
+
+    #[test]
+    fn should_select_overriding_methods_through_vtable() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let dog = linked(&class_loader, "Dog");
+        let animal = dog.super_klass().unwrap();
+        let object = animal.super_klass().unwrap();
+
+        // Dog.speak takes the slot of Animal.speak, Dog.owner comes after
+        // the methods of Animal and the default Pet.greet last
+        let speak = method(&animal, "speak");
+        assert_eq!(
+            speak.vtable_index.get(),
+            method(&dog, "speak").vtable_index.get()
+        );
+        assert_eq!(object.vtable().len() + 3, animal.vtable().len());
+        assert_eq!(animal.vtable().len() + 2, dog.vtable().len());
+        assert_eq!("Pet.greet", declared_by(dog.vtable().last().cloned()));
+
+        assert_eq!("Dog.speak", declared_by(dog.select_virtual_method(&speak)));
+        assert_eq!(
+            "Animal.speak",
+            declared_by(animal.select_virtual_method(&speak))
+        );
+        let name = method(&animal, "name");
+        assert_eq!("Animal.name", declared_by(dog.select_virtual_method(&name)));
+        let hash_code = method(&object, "hashCode");
+        assert_eq!(
+            "java/lang/Object.hashCode",
+            declared_by(dog.select_virtual_method(&hash_code))
+        );
+    }
+
+    #[test]
+    fn should_select_interface_methods_through_itable() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let dog = linked(&class_loader, "Dog");
+        let pet = &dog.interface_klasses()[0];
+        assert!(pet.vtable().is_empty());
+
+        let greet = method(pet, "greet");
+        assert_eq!(
+            "Pet.greet",
+            declared_by(dog.select_interface_method(&greet))
+        );
+        let owner = method(pet, "owner");
+        assert_eq!(
+            "Dog.owner",
+            declared_by(dog.select_interface_method(&owner))
+        );
+        assert_eq!("Pet.greet", declared_by(dog.select_virtual_method(&greet)));
+
+        let object = class_loader.find_loaded_class("java/lang/Object").unwrap();
+        let hash_code = method(&object, "hashCode");
+        assert_eq!(
+            "java/lang/Object.hashCode",
+            declared_by(dog.select_interface_method(&hash_code))
+        );
+    }
+
+    #[test]
+    fn should_resolve_maximally_specific_interface_methods() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        // Host implements Greeter before LoudGreeter, which overrides it
+        let host = linked(&class_loader, "Host");
+        assert_eq!(
+            "LoudGreeter.greet",
+            declared_by(host.find_method("greet", "()I"))
+        );
+        assert_eq!(1, host.maximally_specific_methods("greet", "()I").len());
+
+        // an abstract method of a subinterface hides the default one
+        let muted = linked(&class_loader, "Muted");
+        assert_eq!(
+            "Quiet.greet",
+            declared_by(muted.find_method("greet", "()I"))
+        );
+    }
+
+    #[test]
+    fn should_select_maximally_specific_default_methods() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let host = linked(&class_loader, "Host");
+        let greeter = class_loader.find_loaded_class("Greeter").unwrap();
+        let greet = method(&greeter, "greet");
+        assert_eq!(
+            "LoudGreeter.greet",
+            declared_by(host.select_interface_method(&greet))
+        );
+        assert_eq!(
+            "LoudGreeter.greet",
+            declared_by(host.select_virtual_method(&greet))
+        );
+
+        // the only maximally-specific method is abstract
+        let muted = linked(&class_loader, "Muted");
+        let selected = muted.select_interface_method(&greet).unwrap();
+        assert_eq!("Quiet.greet", declared_by(Some(Rc::clone(&selected))));
+        assert!(selected.access_flags.is_abstract());
+
+        // Mixed was compiled before Rude had a default hello()
+        let mixed = linked(&class_loader, "Mixed");
+        let polite = class_loader.find_loaded_class("Polite").unwrap();
+        let selected = mixed.select_interface_method(&method(&polite, "hello"));
+        assert_eq!(
+            Some("Conflicting default methods: Polite.hello Rude.hello"),
+            selected.unwrap().conflict.as_deref()
+        );
+        let rude = class_loader.find_loaded_class("Rude").unwrap();
+        let selected = mixed.select_virtual_method(&method(&rude, "hello"));
+        assert!(selected.unwrap().conflict.is_some());
+    }
+
+    #[test]
+    fn should_only_override_package_private_methods_in_package() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let sub = linked(&class_loader, "b/Sub");
+        let base = sub.super_klass().unwrap();
+
+        let hidden = method(&base, "hidden");
+        assert_ne!(
+            hidden.vtable_index.get(),
+            method(&sub, "hidden").vtable_index.get()
+        );
+        assert_eq!(
+            "a/Base.hidden",
+            declared_by(sub.select_virtual_method(&hidden))
+        );
+        let shown = method(&base, "shown");
+        assert_eq!(
+            shown.vtable_index.get(),
+            method(&sub, "shown").vtable_index.get()
+        );
+        assert_eq!(
+            "b/Sub.shown",
+            declared_by(sub.select_virtual_method(&shown))
+        );
+        assert_eq!(base.vtable().len() + 1, sub.vtable().len());
+    }
+
+    #[test]
+    fn should_not_override_final_methods() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let unsealed = class_loader.load_class("Unsealed").unwrap();
+        let err = class_loader.link_class(&unsealed).unwrap_err();
+        assert_eq!(
+            "java.lang.VerifyError: class Unsealed overrides final method Sealed.done()V",
+            err.exception()
+        );
+        assert_eq!(ClassState::Loaded, unsealed.state());
+    }
+
+    fn method_ref(klass: &InstanceKlass, name: &str) -> usize {
+        let index = klass
+            .constant_pool
+            .iter()
+            .position(|constant| match constant {
+                JConstant::ConstantMethodRef(method)
+                | JConstant::ConstantInterfaceMethodRef(method) => method.member_ref.name == name,
+                _ => false,
+            });
+        index.unwrap()
+    }
+
+    #[test]
+    fn should_resolve_methods_once() {
+        let class_loader = class_loader_with_jre("testdata/methods");
+        let caller = class_loader.load_class("Caller").unwrap();
+        let resolve = |name: &str| {
+            let resolved = class_loader.resolve_method(&caller, method_ref(&caller, name));
+            resolved.map_err(|err| err.exception())
+        };
+        assert_eq!("Dog.speak", declared_by(resolve("speak").ok()));
+        assert_eq!("Pet.greet", declared_by(resolve("greet").ok()));
+        let hash_code = resolve("hashCode").unwrap();
+        assert!(Rc::ptr_eq(&hash_code, &resolve("hashCode").unwrap()));
+
+        assert_eq!(
+            "java.lang.NoSuchMethodError: Sealed.gone()V",
+            resolve("gone").unwrap_err()
+        );
+        assert_eq!(
+            "java.lang.IllegalAccessError: class Caller tried to access method a/Base.hidden()V",
+            resolve("hidden").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn should_reject_incompatible_supertypes() {
+        let class_loader = class_loader_with_jre("testdata/linkage");
+        let link = |class_name: &str| {
+            let klass = class_loader.load_class(class_name).unwrap();
+            let err = class_loader.link_class(&klass).unwrap_err();
+            assert_eq!(ClassState::Loaded, klass.state());
+            format!("{}: {}", err.java_class(), err)
+        };
+        assert_eq!(
+            "java.lang.VerifyError: Cannot inherit from final class FinalBase in class ExtendsFinal",
+            link("ExtendsFinal")
+        );
+        assert_eq!(
+            "java.lang.IncompatibleClassChangeError: class ExtendsShape has interface Shape as super class",
+            link("ExtendsShape")
+        );
+        assert_eq!(
+            "java.lang.IncompatibleClassChangeError: class ImplementsPlain can not implement Plain, because it is not an interface",
+            link("ImplementsPlain")
+        );
+    }
+
+    fn field_ref(klass: &InstanceKlass, name: &str) -> usize {
+        let index = klass
+            .constant_pool
+            .iter()
+            .position(|constant| match constant {
+                JConstant::ConstantField(field) => field.member_ref.name == name,
+                _ => false,
+            });
+        index.unwrap()
+    }
+
+    fn class_ref(klass: &InstanceKlass, name: &str) -> usize {
+        (0..klass.constant_pool.len())
+            .find(|&index| klass.class_ref_name(index) == Some(name))
+            .unwrap()
+    }
+
+    #[test]
+    fn should_resolve_refs_on_first_use() {
+        let class_loader = class_loader_with_jre("testdata/linkage");
+        let reader = class_loader.load_class("Reader").unwrap();
+        assert!(class_loader.find_loaded_class("Holder").is_none());
+
+        let count = class_loader
+            .resolve_field(&reader, field_ref(&reader, "count"))
+            .unwrap();
+        assert_eq!("Holder", count.klass.klass_name);
+        assert_eq!("count", count.field().name);
+        let holder = class_loader
+            .resolve_class(&reader, class_ref(&reader, "Holder"))
+            .unwrap();
+        assert!(Rc::ptr_eq(&count.klass, &holder));
+
+        let inherited = class_loader
+            .resolve_field(&reader, field_ref(&reader, "inherited"))
+            .unwrap();
+        assert_eq!("Base", inherited.klass.klass_name);
+        let again = class_loader
+            .resolve_field(&reader, field_ref(&reader, "inherited"))
+            .unwrap();
+        assert!(Rc::ptr_eq(&inherited.klass, &again.klass));
+    }
+
+    #[test]
+    fn should_fail_resolution_with_linkage_errors() {
+        let class_loader = class_loader_with_jre("testdata/linkage");
+        let reader = class_loader.load_class("Reader").unwrap();
+        let resolve_field = |name: &str| {
+            let err = class_loader
+                .resolve_field(&reader, field_ref(&reader, name))
+                .unwrap_err();
+            format!("{}: {}", err.java_class(), err)
+        };
+        assert_eq!(
+            "java.lang.NoSuchFieldError: Class Holder does not have member field 'I missing'",
+            resolve_field("missing")
+        );
+        assert_eq!(
+            "java.lang.IllegalAccessError: class Reader tried to access field Holder.secret",
+            resolve_field("secret")
+        );
+
+        let err = class_loader
+            .resolve_class(&reader, class_ref(&reader, "hidden/Secret"))
+            .unwrap_err();
+        assert_eq!("java.lang.IllegalAccessError", err.java_class());
+        assert_eq!(
+            "failed to access class hidden/Secret from class Reader",
+            err.to_string()
+        );
     }
 }
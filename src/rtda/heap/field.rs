@@ -0,0 +1,37 @@
+use crate::classfile::access_flags::AccessFlags;
+use crate::classfile::attribute_info::AttributeInfo;
+
+/// A field declared by a class, with the slot it is stored in.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub descriptor: String,
+    pub access_flags: AccessFlags,
+    /// the constant a `static final` field starts with, from its
+    /// `ConstantValue` attribute
+    pub constant_value_index: Option<u16>,
+    /// the first slot of the field, in `InstanceKlass::static_vars` for a
+    /// static field and in `JObject` fields for an instance field. Instance
+    /// fields come after the ones of the superclasses.
+    pub slot: usize,
+    pub attribute_table: Vec<AttributeInfo>,
+}
+
+impl Field {
+    pub fn is_static(&self) -> bool {
+        self.access_flags.is_static()
+    }
+
+    /// `long` and `double` fields take two slots.
+    pub fn is_wide(&self) -> bool {
+        self.descriptor == "J" || self.descriptor == "D"
+    }
+
+    pub fn slot_count(&self) -> usize {
+        if self.is_wide() {
+            2
+        } else {
+            1
+        }
+    }
+}
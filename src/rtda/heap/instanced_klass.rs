@@ -1,30 +1,138 @@
-use std::borrow::Borrow;
+use std::cell::{Ref, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::{Rc, Weak};
 
 use byteorder::{BigEndian, ByteOrder};
 
+use crate::classfile::access_flags::{AccessFlags, ACC_ABSTRACT, ACC_FINAL, ACC_PUBLIC};
 use crate::classfile::attribute_info::AttributeInfo;
-use crate::classfile::constant_pool::CpEntry;
+use crate::classfile::constant_pool::{ConstantPool, CpEntry};
 use crate::classfile::member_info::MemberInfo;
 use crate::classfile::parsed_class::ParsedClass;
+use crate::rtda::heap::class_init::InitLock;
+use crate::rtda::heap::field::Field;
 use crate::rtda::heap::j_constant::{JConstant, JField, JMethodRef, JString};
-use crate::rtda::heap::j_method::{JMethod};
+use crate::rtda::heap::j_method::JMethod;
 use crate::rtda::heap::method_descriptor::MethodDescriptor;
+use crate::rtda::heap::method_table::ItableEntry;
+use crate::rtda::heap::slot::Slot;
 
-#[derive(Debug, Clone)]
+/// How far a class got through loading, linking and initialization, in
+/// that order (JVMS 5).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ClassState {
+    /// defined by its loader, with its superclass and interfaces loaded
+    #[default]
+    Loaded,
+    /// verified and prepared, its static fields zeroed
+    Linked,
+    /// `<clinit>` is running
+    Initializing,
+    Initialized,
+    /// initialization failed, the class cannot be used
+    Erroneous,
+}
+
+/// What a thread about to initialize a class has to do, see
+/// `InstanceKlass::begin_initialization`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Initialization {
+    /// the thread took the class over and runs its initializer
+    Started,
+    /// the class is initialized, or this thread is initializing it
+    Done,
+    /// an earlier initialization failed
+    Erroneous,
+}
+
+/// The superclass and direct superinterfaces of a class, loaded before the
+/// class is created. Both are missing for classes built outside a loader.
+#[derive(Debug, Clone, Default)]
+pub struct Supertypes {
+    pub super_klass: Option<Rc<InstanceKlass>>,
+    pub interfaces: Vec<Rc<InstanceKlass>>,
+}
+
+/// A constant pool reference after resolution.
+#[derive(Clone)]
+pub enum ResolvedRef {
+    Class(Rc<InstanceKlass>),
+    Field(ResolvedField),
+    /// the method a method reference resolved to, declared by the
+    /// referenced class or one of its supertypes
+    Method(Rc<JMethod>),
+}
+
+/// A field reference resolved to the class declaring the field, which may
+/// be a superclass or superinterface of the class named by the reference.
+#[derive(Clone)]
+pub struct ResolvedField {
+    pub klass: Rc<InstanceKlass>,
+    /// index into `klass.fields`
+    pub index: usize,
+}
+
+impl ResolvedField {
+    pub fn field(&self) -> &Field {
+        &self.klass.fields[self.index]
+    }
+}
+
+// by name, a class may well have resolved a reference to itself
+impl fmt::Debug for ResolvedRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolvedRef::Class(klass) => write!(f, "Class({})", klass.klass_name),
+            ResolvedRef::Field(field) => write!(f, "Field({:?})", field),
+            ResolvedRef::Method(method) => {
+                write!(f, "Method({}{})", method.name, method.descriptor.text)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ResolvedField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.klass.klass_name, self.field().name)
+    }
+}
+
+/// A loaded class. It is built once into an `Rc` that its methods, frames
+/// and class loader share, see `ParsedClass::create_instance_klass`.
+#[derive(Debug)]
 pub struct InstanceKlass {
-    constant_pool_count: u8,
-    constant_pool_entries: Vec<CpEntry>,
+    constant_pool_count: u16,
+    constant_pool_entries: ConstantPool,
 
     pub minor_version: u16,
     pub major_version: u16,
     pub constant_pool: Vec<JConstant>,
+    pub access_flags: AccessFlags,
     pub klass_name: String,
     pub super_klass_name: String,
     pub interfaces: Vec<String>,
-    pub methods: Vec<JMethod>,
-    pub fields: Vec<MemberInfo>,
+    pub methods: Vec<Rc<JMethod>>,
+    pub fields: Vec<Field>,
     pub attributes: Vec<AttributeInfo>,
     pub source_file: String,
+
+    /// the state, guarded by the initialization lock of JVMS 5.5
+    init_lock: InitLock,
+    super_klass: Option<Rc<InstanceKlass>>,
+    interface_klasses: Vec<Rc<InstanceKlass>>,
+    /// slots taken by the static fields
+    static_slot_count: usize,
+    /// slots taken by the instance fields, the inherited ones included
+    instance_slot_count: usize,
+    /// storage of the static fields, allocated by preparation
+    pub static_vars: RefCell<Vec<Slot>>,
+    /// the methods instances of the class run for each vtable slot, filled
+    /// by preparation
+    vtable: RefCell<Vec<Rc<JMethod>>>,
+    itable: RefCell<Vec<ItableEntry>>,
+    /// constant pool entries resolved so far, by index
+    resolved: RefCell<HashMap<usize, ResolvedRef>>,
 }
 
 impl InstanceKlass {
@@ -33,8 +141,9 @@ impl InstanceKlass {
             minor_version: 0,
             major_version: 0,
             constant_pool_count: 0,
-            constant_pool_entries: vec![],
+            constant_pool_entries: ConstantPool::new(),
             constant_pool: vec![],
+            access_flags: AccessFlags::default(),
             klass_name: String::from(""),
             super_klass_name: String::from(""),
             interfaces: vec![],
@@ -42,11 +151,289 @@ impl InstanceKlass {
             fields: vec![],
             attributes: vec![],
             source_file: String::from(""),
+            init_lock: InitLock::default(),
+            super_klass: None,
+            interface_klasses: vec![],
+            static_slot_count: 0,
+            instance_slot_count: 0,
+            static_vars: RefCell::new(vec![]),
+            vtable: RefCell::new(vec![]),
+            itable: RefCell::new(vec![]),
+            resolved: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The class of arrays such as `[Ljava/lang/String;`: a `public final`
+    /// subclass of `java/lang/Object` that implements `java/lang/Cloneable`
+    /// and `java/io/Serializable`, the supertypes given, and declares no
+    /// members (JVMS 5.3.3).
+    pub fn new_array_klass(klass_name: &str, supertypes: Supertypes) -> InstanceKlass {
+        let mut klass = InstanceKlass::new();
+        klass.access_flags = AccessFlags::new(ACC_PUBLIC | ACC_FINAL | ACC_ABSTRACT);
+        klass.klass_name = String::from(klass_name);
+        if let Some(super_klass) = &supertypes.super_klass {
+            klass.super_klass_name = super_klass.klass_name.clone();
+        }
+        klass.interfaces = supertypes
+            .interfaces
+            .iter()
+            .map(|interface| interface.klass_name.clone())
+            .collect();
+        klass.fill_supertypes(supertypes);
+        klass
+    }
+
+    pub fn state(&self) -> ClassState {
+        self.init_lock.state()
+    }
+
+    pub fn set_state(&self, state: ClassState) {
+        self.init_lock.set_state(state)
+    }
+
+    /// Steps 1 to 6 of JVMS 5.5: waits while another thread initializes
+    /// the class, then takes it over unless there is nothing left to do.
+    /// `Started` has to be followed by `finish_initialization`.
+    pub fn begin_initialization(&self) -> Initialization {
+        self.init_lock.begin()
+    }
+
+    /// Marks the class `Initialized` or `Erroneous` and wakes the threads
+    /// waiting for it.
+    pub fn finish_initialization(&self, state: ClassState) {
+        self.init_lock.finish(state)
+    }
+
+    /// The superclass, `None` for `java/lang/Object` or a class built
+    /// outside a loader.
+    pub fn super_klass(&self) -> Option<Rc<InstanceKlass>> {
+        self.super_klass.clone()
+    }
+
+    /// The direct superinterfaces, in declaration order.
+    pub fn interface_klasses(&self) -> &[Rc<InstanceKlass>] {
+        &self.interface_klasses
+    }
+
+    /// Whether this is an array class such as `[I`.
+    pub fn is_array(&self) -> bool {
+        self.klass_name.starts_with('[')
+    }
+
+    /// `java/lang` for `java/lang/String`, `""` in the unnamed package.
+    pub fn package_name(&self) -> &str {
+        match self.klass_name.rfind('/') {
+            Some(slash) => &self.klass_name[..slash],
+            None => "",
+        }
+    }
+
+    /// Whether `other` is this class or one of its superclasses.
+    pub fn is_subclass_of(&self, other: &InstanceKlass) -> bool {
+        if std::ptr::eq(self, other) {
+            return true;
+        }
+        match self.super_klass() {
+            Some(super_klass) => super_klass.is_subclass_of(other),
+            None => false,
+        }
+    }
+
+    /// The class whose nest this class belongs to, itself unless it has a
+    /// `NestHost` attribute.
+    pub fn nest_host_name(&self) -> &str {
+        for attribute in &self.attributes {
+            if let AttributeInfo::NestHost(host) = attribute {
+                let name = self
+                    .constant_pool_entries
+                    .get_class_name(host.host_class_index);
+                return name.unwrap_or("");
+            }
+        }
+        &self.klass_name
+    }
+
+    /// Index of the field this class declares as `name` of type
+    /// `descriptor`.
+    pub fn find_declared_field(&self, name: &str, descriptor: &str) -> Option<usize> {
+        self.fields
+            .iter()
+            .position(|field| field.name == name && field.descriptor == descriptor)
+    }
+
+    /// How many slots `static_vars` has.
+    pub fn static_slot_count(&self) -> usize {
+        self.static_slot_count
+    }
+
+    /// How many slots an instance has, for its own fields and the ones of
+    /// its superclasses.
+    pub fn instance_slot_count(&self) -> usize {
+        self.instance_slot_count
+    }
+
+    /// The method this class declares as `name` of type `descriptor`.
+    pub fn find_declared_method(&self, name: &str, descriptor: &str) -> Option<Rc<JMethod>> {
+        self.methods
+            .iter()
+            .find(|method| method.name == name && method.descriptor.text == descriptor)
+            .cloned()
+    }
+
+    /// The method `name` of type `descriptor` as method resolution finds it
+    /// (JVMS 5.4.3.3): declared by this class or a superclass, otherwise by
+    /// a superinterface, see `find_interface_method`.
+    pub fn find_method(&self, name: &str, descriptor: &str) -> Option<Rc<JMethod>> {
+        match self.find_class_method(name, descriptor) {
+            Some(method) => Some(method),
+            None => self.find_interface_method(name, descriptor),
+        }
+    }
+
+    /// The method `name` of type `descriptor` declared by a superinterface
+    /// (JVMS 5.4.3.3): the only default method among the maximally-specific
+    /// ones, otherwise any of those.
+    pub fn find_interface_method(&self, name: &str, descriptor: &str) -> Option<Rc<JMethod>> {
+        let methods = self.maximally_specific_methods(name, descriptor);
+        let mut defaults = methods
+            .iter()
+            .filter(|method| !method.access_flags.is_abstract());
+        match (defaults.next(), defaults.next()) {
+            (Some(method), None) => Some(Rc::clone(method)),
+            _ => methods.into_iter().next(),
+        }
+    }
+
+    /// The superinterface methods `name` of type `descriptor` that no method
+    /// of a subinterface overrides, in the order of `superinterfaces`.
+    pub fn maximally_specific_methods(&self, name: &str, descriptor: &str) -> Vec<Rc<JMethod>> {
+        let candidates: Vec<(Rc<InstanceKlass>, Rc<JMethod>)> = self
+            .superinterfaces()
+            .into_iter()
+            .filter_map(|interface| {
+                let method = interface.find_declared_method(name, descriptor)?;
+                // private and static interface methods are not inherited
+                let flags = method.access_flags;
+                if flags.is_private() || flags.is_static() {
+                    return None;
+                }
+                Some((interface, method))
+            })
+            .collect();
+        candidates
+            .iter()
+            .filter(|(interface, _)| {
+                !candidates.iter().any(|(other, _)| {
+                    !Rc::ptr_eq(other, interface) && other.is_subinterface_of(interface)
+                })
+            })
+            .map(|(_, method)| Rc::clone(method))
+            .collect()
+    }
+
+    /// Every superinterface, the ones of the superclasses included, each
+    /// once.
+    pub fn superinterfaces(&self) -> Vec<Rc<InstanceKlass>> {
+        let mut found: Vec<Rc<InstanceKlass>> = vec![];
+        self.collect_superinterfaces(&mut found);
+        found
+    }
+
+    fn collect_superinterfaces(&self, found: &mut Vec<Rc<InstanceKlass>>) {
+        for interface in &self.interface_klasses {
+            if !found.iter().any(|known| Rc::ptr_eq(known, interface)) {
+                found.push(Rc::clone(interface));
+                interface.collect_superinterfaces(found);
+            }
+        }
+        if let Some(super_klass) = &self.super_klass {
+            super_klass.collect_superinterfaces(found);
+        }
+    }
+
+    /// Whether `other` is this interface or one of its superinterfaces.
+    pub fn is_subinterface_of(&self, other: &InstanceKlass) -> bool {
+        std::ptr::eq(self, other)
+            || self
+                .interface_klasses
+                .iter()
+                .any(|interface| interface.is_subinterface_of(other))
+    }
+
+    fn find_class_method(&self, name: &str, descriptor: &str) -> Option<Rc<JMethod>> {
+        match self.find_declared_method(name, descriptor) {
+            Some(method) => Some(method),
+            None => self
+                .super_klass
+                .as_ref()?
+                .find_class_method(name, descriptor),
+        }
+    }
+
+    /// The vtable, empty until the class is linked.
+    pub fn vtable(&self) -> Ref<'_, Vec<Rc<JMethod>>> {
+        self.vtable.borrow()
+    }
+
+    pub fn set_method_tables(&self, vtable: Vec<Rc<JMethod>>, itable: Vec<ItableEntry>) {
+        *self.vtable.borrow_mut() = vtable;
+        *self.itable.borrow_mut() = itable;
+    }
+
+    /// The method `invokevirtual` runs on an instance of this class for the
+    /// resolved `method` (JVMS 5.4.6), by its vtable slot. Private methods
+    /// are not overridden.
+    pub fn select_virtual_method(&self, method: &Rc<JMethod>) -> Option<Rc<JMethod>> {
+        if method.access_flags.is_private() {
+            return Some(Rc::clone(method));
+        }
+        let vtable = self.vtable.borrow();
+        match method.vtable_index.get() {
+            Some(index) => vtable.get(index).cloned(),
+            // interface methods take a different slot in each class
+            None => vtable
+                .iter()
+                .find(|entry| {
+                    entry.name == method.name && entry.descriptor.text == method.descriptor.text
+                })
+                .cloned(),
+        }
+    }
+
+    /// The method `invokeinterface` runs on an instance of this class for
+    /// the resolved `method`, from the itable of the interface declaring it.
+    /// Methods of `java/lang/Object` go through the vtable.
+    pub fn select_interface_method(&self, method: &Rc<JMethod>) -> Option<Rc<JMethod>> {
+        let interface = method.klass()?;
+        if !interface.access_flags.is_interface() || method.access_flags.is_private() {
+            return self.select_virtual_method(method);
         }
+        let itable = self.itable.borrow();
+        let entry = itable
+            .iter()
+            .find(|entry| Rc::ptr_eq(&entry.interface, &interface))?;
+        entry.methods.get(method.index)?.clone()
+    }
+
+    /// The class named by the `CONSTANT_Class` entry at `index`.
+    pub fn class_ref_name(&self, index: usize) -> Option<&str> {
+        match self.constant_pool.get(index) {
+            Some(JConstant::Class { idx }) => self.constant_pool_entries.get_utf8(*idx),
+            _ => None,
+        }
+    }
+
+    pub fn resolved_ref(&self, index: usize) -> Option<ResolvedRef> {
+        self.resolved.borrow().get(&index).cloned()
     }
 
-    pub fn set_origin_pool_entries(&mut self, entries: Vec<CpEntry>) {
-        self.constant_pool_entries = entries;
+    pub fn set_resolved_ref(&self, index: usize, resolved: ResolvedRef) {
+        self.resolved.borrow_mut().insert(index, resolved);
+    }
+
+    pub fn set_origin_pool_entries(&mut self, pool: ConstantPool) {
+        self.constant_pool_count = pool.count();
+        self.constant_pool_entries = pool;
     }
     pub fn set_minor_version(&mut self, vector: Vec<u8>) {
         self.minor_version = BigEndian::read_u16(&vector);
@@ -57,8 +444,7 @@ impl InstanceKlass {
     }
 
     pub fn fill_super_name(&mut self, index: u16) {
-        let entry = self.constant_pool_entries[index as usize].clone();
-        self.super_klass_name = self.get_string_from_cp(entry);
+        self.super_klass_name = self.get_class_name(index);
     }
 
     pub fn fill_class_name(&mut self, index: u16) {
@@ -66,36 +452,93 @@ impl InstanceKlass {
     }
 
     fn get_class_name(&mut self, index: u16) -> String {
-        let entry = self.constant_pool_entries[index as usize].clone();
-        self.get_string_from_cp(entry)
+        let name = self.constant_pool_entries.get_class_name(index);
+        String::from(name.unwrap_or(""))
+    }
+
+    pub fn fill_supertypes(&mut self, supertypes: Supertypes) {
+        self.super_klass = supertypes.super_klass;
+        self.interface_klasses = supertypes.interfaces;
     }
 
+    /// Lays the fields out in declaration order, the instance fields after
+    /// the ones of the superclass. Needs the supertypes filled first.
     pub fn fill_fields(&mut self, fields: Vec<MemberInfo>) {
-        self.fields = fields;
+        let mut static_slot = 0;
+        let mut instance_slot = match &self.super_klass {
+            Some(super_klass) => super_klass.instance_slot_count,
+            None => 0,
+        };
+        for member in fields {
+            let constant_value_index =
+                member
+                    .attributes()
+                    .iter()
+                    .find_map(|attribute| match attribute {
+                        AttributeInfo::ConstantValue(value) => Some(value.constant_value_index),
+                        _ => None,
+                    });
+            let mut field = Field {
+                name: self.get_string_by_index(member.name_index),
+                descriptor: self.get_string_by_index(member.descriptor_index),
+                access_flags: member.access_flags,
+                constant_value_index,
+                slot: 0,
+                attribute_table: member.attribute_table,
+            };
+            if field.is_static() {
+                field.slot = static_slot;
+                static_slot += field.slot_count();
+            } else {
+                field.slot = instance_slot;
+                instance_slot += field.slot_count();
+            }
+            self.fields.push(field);
+        }
+        self.static_slot_count = static_slot;
+        self.instance_slot_count = instance_slot;
     }
 
-    pub fn fill_methods(&mut self, methods: Vec<MemberInfo>) {
+    /// `this` is the shared handle the class is being built in, which its
+    /// methods point back at.
+    pub fn fill_methods(&mut self, methods: Vec<MemberInfo>, this: &Weak<InstanceKlass>) {
         for x in methods {
             let mut j_method = JMethod::new();
-            // let method_data = MethodData::new();
-            j_method.name = self.klass_name.clone();
-            j_method.method_data.attribute_table = x.attribute_table.clone();
-            j_method.klass = self.clone().borrow().clone();
+            j_method.name = self.get_string_by_index(x.name_index);
+            j_method.access_flags = x.access_flags;
+            j_method.klass = this.clone();
+            j_method.index = self.methods.len();
             j_method.descriptor = self.get_method_descriptor(x.clone());
 
-            for j in x.attribute_table {
-                match j {
+            // abstract and native methods have no code
+            for attribute in &x.attribute_table {
+                match attribute {
                     AttributeInfo::Code(code) => {
                         j_method.max_stack = code.max_stack;
-                        j_method.method_data.code = code.code;
                         j_method.max_locals = code.max_locals;
+                        j_method.method_data.code = code.code.clone();
+                        j_method.method_data.exception_table = code.exception_table.clone();
+                        for code_attribute in &code.attribute_table {
+                            if let AttributeInfo::LineNumberTable(lines) = code_attribute {
+                                j_method
+                                    .method_data
+                                    .line_num_table
+                                    .line_number_table
+                                    .extend(lines.line_number_table.iter().cloned());
+                            }
+                        }
+                    }
+                    AttributeInfo::Exceptions(exceptions) => {
+                        for &index in &exceptions.exception_index_table {
+                            let exception = self.get_class_name(index);
+                            j_method.exceptions.push(exception);
+                        }
                     }
                     _ => {}
                 }
             }
-            if j_method.name != String::from("") {
-                self.methods.push(j_method);
-            }
+            j_method.method_data.attribute_table = x.attribute_table;
+            self.methods.push(Rc::new(j_method));
         }
     }
 
@@ -130,55 +573,44 @@ impl InstanceKlass {
 
     pub fn fill_pool(&mut self, cf: &mut ParsedClass) {
         let entries = self.constant_pool_entries.clone();
-        let mut pool: Vec<JConstant> = Vec::with_capacity(entries.len());
+        let mut pool: Vec<JConstant> = Vec::with_capacity(entries.count() as usize);
 
-        for x in entries {
+        for x in entries.entries().iter().cloned() {
             match x {
                 CpEntry::Empty {} => pool.push(JConstant::Empty {}),
                 CpEntry::Class { idx } => pool.push(JConstant::Class { idx }),
                 CpEntry::String { idx } => {
-                    let str = self.get_string_by_index(idx);
-                    pool.push(JConstant::String(JString { go_str: str }))
+                    let bytes = self.constant_pool_entries.get_utf8_bytes(idx);
+                    let str = bytes.and_then(JString::from_modified_utf8);
+                    pool.push(JConstant::String(str.unwrap_or_default()))
                 }
                 CpEntry::MethodRef(method_ref) => {
-                    let info = JMethodRef::new(self, cf.borrow(), method_ref);
+                    let info = JMethodRef::new(cf, method_ref);
                     pool.push(JConstant::ConstantMethodRef(info));
                 }
-                CpEntry::Utf8 { val } => pool.push(JConstant::Utf8 { val }),
+                CpEntry::Utf8 { val, .. } => pool.push(JConstant::Utf8 { val }),
                 CpEntry::FieldRef(field_ref) => {
-                    let info = JField::new(self, cf.borrow(), field_ref);
+                    let info = JField::new(cf, field_ref);
                     pool.push(JConstant::ConstantField(info))
                 }
-                CpEntry::InterfaceMethodRef { .. } => {}
-                CpEntry::NameAndType { name_idx, type_idx } => {
-                    pool.push(JConstant::ConstantInfo(CpEntry::NameAndType {
-                        name_idx,
-                        type_idx,
-                    }))
-                }
-                _ => {
-                    println!("{:?}", x);
+                CpEntry::InterfaceMethodRef(method_ref) => {
+                    let info = JMethodRef::new(cf, method_ref);
+                    pool.push(JConstant::ConstantInterfaceMethodRef(info));
                 }
+                CpEntry::Integer { val } => pool.push(JConstant::Integer { val }),
+                CpEntry::Float { val } => pool.push(JConstant::Float { val }),
+                CpEntry::Long { val } => pool.push(JConstant::Long { val }),
+                CpEntry::Double { val } => pool.push(JConstant::Double { val }),
+                // keep the remaining entries raw so the pool indices stay aligned
+                _ => pool.push(JConstant::ConstantInfo(x)),
             }
         }
 
         self.constant_pool = pool
     }
 
-    fn get_string_from_cp(&mut self, entry: CpEntry) -> String {
-        let mut class_name: String = String::from("");
-        if let CpEntry::Class { idx } = entry {
-            class_name = self.get_string_by_index(idx)
-        }
-        class_name
-    }
-
     fn get_string_by_index(&mut self, idx: u16) -> String {
-        let mut str: String = String::from("");
-        let name = self.constant_pool_entries[idx as usize].clone();
-        if let CpEntry::Utf8 { val } = name {
-            str = val;
-        }
-        str
+        let name = self.constant_pool_entries.get_utf8(idx);
+        String::from(name.unwrap_or(""))
     }
 }
@@ -1,7 +1,7 @@
 use crate::classfile::constant_member_ref::ConstantMemberRef;
 use crate::classfile::constant_pool::{CpEntry, MemberRef};
+use crate::classfile::modified_utf8;
 use crate::classfile::parsed_class::ParsedClass;
-use crate::rtda::heap::instanced_klass::InstanceKlass;
 
 #[derive(Clone, Debug)]
 pub enum JConstant {
@@ -15,6 +15,7 @@ pub enum JConstant {
     String(JString),
 
     ConstantMethodRef(JMethodRef),
+    ConstantInterfaceMethodRef(JMethodRef),
     ConstantField(JField),
     ConstantInfo(CpEntry),
 }
@@ -26,16 +27,40 @@ pub struct JMethodRef {
 }
 
 impl JMethodRef {
-    pub fn new(class: &InstanceKlass, cf: &ParsedClass, method_ref: MemberRef) -> JMethodRef {
-        let member_ref = ConstantMemberRef::new(class, cf, method_ref);
+    pub fn new(cf: &ParsedClass, method_ref: MemberRef) -> JMethodRef {
+        let member_ref = ConstantMemberRef::new(cf, method_ref);
         JMethodRef { member_ref }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct JString {
     // pub class: InstanceKlass,
+    /// the text, unpaired surrogates showing as U+FFFD
     pub go_str: String,
+    units: Vec<u16>,
+}
+
+impl JString {
+    pub fn new(go_str: String) -> JString {
+        let units = go_str.encode_utf16().collect();
+        JString { go_str, units }
+    }
+
+    pub fn from_modified_utf8(bytes: &[u8]) -> Option<JString> {
+        let units = modified_utf8::decode_utf16(bytes).ok()?;
+        let go_str = String::from_utf16_lossy(&units);
+        Some(JString { go_str, units })
+    }
+
+    pub fn to_modified_utf8(&self) -> Vec<u8> {
+        modified_utf8::encode_utf16(self.units.iter().copied())
+    }
+
+    /// the UTF-16 code units backing `java.lang.String`
+    pub fn to_utf16(&self) -> Vec<u16> {
+        self.units.clone()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -44,8 +69,8 @@ pub struct JField {
 }
 
 impl JField {
-    pub fn new(class: &InstanceKlass, cf: &ParsedClass, field_ref: MemberRef) -> JField {
-        let member_ref = ConstantMemberRef::new(class, cf, field_ref);
+    pub fn new(cf: &ParsedClass, field_ref: MemberRef) -> JField {
+        let member_ref = ConstantMemberRef::new(cf, field_ref);
         JField { member_ref }
     }
 }
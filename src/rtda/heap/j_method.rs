@@ -1,8 +1,11 @@
+use crate::classfile::access_flags::AccessFlags;
 use crate::classfile::attribute_info::{
     AttributeInfo, ExceptionTableEntry, LineNumberTableAttribute,
 };
 use crate::rtda::heap::instanced_klass::InstanceKlass;
 use crate::rtda::heap::method_descriptor::MethodDescriptor;
+use std::cell::Cell;
+use std::rc::{Rc, Weak};
 
 #[derive(Debug, Clone)]
 pub struct MethodData {
@@ -22,7 +25,7 @@ impl MethodData {
             attribute_table: vec![],
             parameter_annotation_data: vec![],
             annotation_default_data: vec![],
-            line_num_table: LineNumberTableAttribute::new()
+            line_num_table: LineNumberTableAttribute::new(),
         }
     }
 }
@@ -30,22 +33,72 @@ impl MethodData {
 #[derive(Debug, Clone)]
 pub struct JMethod {
     pub name: String,
-    pub klass: InstanceKlass,
+    pub access_flags: AccessFlags,
+    /// the class declaring the method, it owns the method
+    pub klass: Weak<InstanceKlass>,
     pub max_stack: u16,
     pub max_locals: u16,
     pub descriptor: MethodDescriptor,
-    pub method_data: MethodData
+    pub method_data: MethodData,
+    /// the checked exceptions the method declares, such as
+    /// `java/io/IOException`
+    pub exceptions: Vec<String>,
+    /// position in `klass.methods`
+    pub index: usize,
+    /// the slot of the method in the vtable of its class, set when the
+    /// class is linked
+    pub vtable_index: Cell<Option<usize>>,
+    /// set on the method a vtable holds in place of default methods that
+    /// conflict (JVMS 5.4.6): the message of the
+    /// `IncompatibleClassChangeError` invoking it throws
+    pub conflict: Option<String>,
 }
 
 impl JMethod {
     pub fn new() -> JMethod {
         JMethod {
             name: String::from(""),
-            klass: InstanceKlass::new(),
+            access_flags: AccessFlags::default(),
+            klass: Weak::new(),
             max_stack: 0,
             max_locals: 0,
             descriptor: MethodDescriptor::new(String::from("")),
-            method_data: MethodData::new()
+            method_data: MethodData::new(),
+            exceptions: vec![],
+            index: 0,
+            vtable_index: Cell::new(None),
+            conflict: None,
         }
     }
+
+    /// The declaring class, `None` for shim methods that have none.
+    pub fn klass(&self) -> Option<Rc<InstanceKlass>> {
+        self.klass.upgrade()
+    }
+
+    /// How many slots the arguments take, the receiver of an instance
+    /// method included.
+    pub fn arg_slot_count(&self) -> usize {
+        let receiver = if self.access_flags.is_static() { 0 } else { 1 };
+        let params = &self.descriptor.parameter_types;
+        receiver + params.iter().map(|param| param.slot_count()).sum::<usize>()
+    }
+
+    /// Whether calls to the method dispatch on the receiver: instance
+    /// methods other than constructors and private ones.
+    pub fn is_virtual(&self) -> bool {
+        let flags = self.access_flags;
+        !flags.is_static() && !flags.is_private() && self.name != "<init>"
+    }
+
+    /// The source line of the instruction at `pc`, from the
+    /// `LineNumberTable`.
+    pub fn line_number(&self, pc: u16) -> Option<u16> {
+        let lines = &self.method_data.line_num_table.line_number_table;
+        lines
+            .iter()
+            .filter(|entry| entry.start_pc <= pc)
+            .max_by_key(|entry| entry.start_pc)
+            .map(|entry| entry.line_number)
+    }
 }
@@ -1,2 +1,60 @@
-#[derive(Debug, Clone)]
-pub struct JObject {}
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::rtda::heap::slot::{EmptySlot, Slot};
+
+/// An instance of a class. Its fields are stored at the slots of
+/// `Field::slot`, the inherited ones first. The slots of an array are its
+/// elements.
+pub struct JObject {
+    klass: Rc<InstanceKlass>,
+    fields: RefCell<Vec<Slot>>,
+}
+
+impl JObject {
+    /// A new instance of `klass`, its fields zeroed.
+    pub fn new(klass: &Rc<InstanceKlass>) -> JObject {
+        JObject {
+            klass: Rc::clone(klass),
+            fields: RefCell::new(vec![EmptySlot::new(); klass.instance_slot_count()]),
+        }
+    }
+
+    /// A new array of `klass`, an array class, with `length` zeroed
+    /// elements.
+    pub fn new_array(klass: &Rc<InstanceKlass>, length: usize) -> JObject {
+        JObject {
+            klass: Rc::clone(klass),
+            fields: RefCell::new(vec![EmptySlot::new(); length]),
+        }
+    }
+
+    /// How many elements the array has, `None` if this is no array.
+    pub fn array_length(&self) -> Option<usize> {
+        if !self.klass.is_array() {
+            return None;
+        }
+        Some(self.fields.borrow().len())
+    }
+
+    pub fn klass(&self) -> &Rc<InstanceKlass> {
+        &self.klass
+    }
+
+    pub fn field(&self, slot: usize) -> Slot {
+        self.fields.borrow()[slot].clone()
+    }
+
+    pub fn set_field(&self, slot: usize, value: Slot) {
+        self.fields.borrow_mut()[slot] = value;
+    }
+}
+
+// objects may well refer to each other, only the class is printed
+impl fmt::Debug for JObject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JObject({})", self.klass.klass_name)
+    }
+}
@@ -7,6 +7,16 @@ impl TypeDescriptor {
     pub fn new(str: String) -> TypeDescriptor {
         TypeDescriptor { str }
     }
+
+    /// How many slots a value of the type takes in local variables and on
+    /// the operand stack: two for `long` and `double`, none for `void`.
+    pub fn slot_count(&self) -> usize {
+        match self.str.as_str() {
+            "J" | "D" => 2,
+            "V" => 0,
+            _ => 1,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,11 +36,14 @@ impl MethodDescriptor {
     }
 
     pub fn parse(&mut self) -> &mut MethodDescriptor {
+        // parsing consumes `text`, which keeps the whole descriptor after
+        let text = self.text.clone();
         let param_types = self.parse_param_types();
         let return_type = self.parse_return_type();
 
         self.parameter_types = param_types;
         self.return_type = return_type;
+        self.text = text;
 
         self
     }
@@ -66,13 +79,14 @@ impl MethodDescriptor {
             let x: &str = string.as_str();
             match x {
                 "B" | "C" | "D" | "F" | "I" | "J" | "S" | "Z" => {
-                    let t = &self.text[0..1];
-                    let descriptor = TypeDescriptor::new(String::from(t));
+                    let t = self.text[0..1].to_string();
+                    self.text = self.text[1..].to_string();
+                    let descriptor = TypeDescriptor::new(t);
                     return Option::from(descriptor);
                 }
                 "L" => {
                     let len = self.text.len();
-                    for i in 0..len - 1 {
+                    for i in 0..len {
                         let text = self.text.clone();
                         if get_char_by_index(text.clone(), i) == ';'.to_string() {
                             let x = &text[0..i + 1];
@@ -83,7 +97,13 @@ impl MethodDescriptor {
                         }
                     }
                 }
-                "[" => {}
+                "[" => {
+                    self.text = self.text[1..].to_string();
+                    if let Some(component) = self.parse_field_type() {
+                        let descriptor = TypeDescriptor::new(format!("[{}", component.str));
+                        return Some(descriptor);
+                    }
+                }
                 _ => return None,
             }
         }
@@ -116,6 +136,7 @@ mod tests {
     fn should_get_desc_from_void_no_params() {
         let mut descriptor = MethodDescriptor::new(String::from("()V"));
         descriptor.parse();
+        assert_eq!("()V", descriptor.text);
         assert_eq!(0, descriptor.parameter_types.len());
         let option = descriptor.return_type;
         assert_eq!("V", option.str);
@@ -131,4 +152,15 @@ mod tests {
         let option = descriptor.return_type;
         assert_eq!("V", option.str);
     }
+
+    #[test]
+    fn should_get_desc_from_primitive_and_array_params() {
+        let mut descriptor = MethodDescriptor::new(String::from("(I[JLjava/util/List;)I"));
+        descriptor.parse();
+        assert_eq!(3, descriptor.parameter_types.len());
+        assert_eq!("I", descriptor.parameter_types[0].str);
+        assert_eq!("[J", descriptor.parameter_types[1].str);
+        assert_eq!("Ljava/util/List;", descriptor.parameter_types[2].str);
+        assert_eq!("I", descriptor.return_type.str);
+    }
 }
@@ -0,0 +1,198 @@
+//! Dispatch tables, built when a class is prepared. The vtable of a class
+//! starts with the one of its superclass, an overriding method taking the
+//! slot of the method it overrides. The itable of each superinterface
+//! holds the method the class selects for every interface method.
+
+use std::fmt;
+use std::rc::Rc;
+
+use crate::rtda::heap::class_loader::{ClassLoadError, VERIFY_ERROR};
+use crate::rtda::heap::instanced_klass::InstanceKlass;
+use crate::rtda::heap::j_method::JMethod;
+
+/// The methods a class selects for the methods of one of its
+/// superinterfaces, by index into `interface.methods`. Static and private
+/// interface methods have none.
+#[derive(Clone)]
+pub struct ItableEntry {
+    pub interface: Rc<InstanceKlass>,
+    pub methods: Vec<Option<Rc<JMethod>>>,
+}
+
+impl fmt::Debug for ItableEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ItableEntry({})", self.interface.klass_name)
+    }
+}
+
+/// A class overriding a `final` method of a superclass fails verification.
+pub fn check_final_overrides(klass: &InstanceKlass) -> Result<(), ClassLoadError> {
+    let super_klass = match klass.super_klass() {
+        Some(super_klass) => super_klass,
+        None => return Ok(()),
+    };
+    let inherited = super_klass.vtable();
+    for method in klass.methods.iter().filter(|method| method.is_virtual()) {
+        let overridden = inherited
+            .iter()
+            .find(|entry| entry.access_flags.is_final() && overrides(klass, method, entry));
+        if let Some(overridden) = overridden {
+            let owner = overridden.klass();
+            return Err(ClassLoadError::Linkage {
+                error: VERIFY_ERROR,
+                message: format!(
+                    "class {} overrides final method {}.{}{}",
+                    klass.klass_name,
+                    owner.map_or(String::new(), |owner| owner.klass_name.clone()),
+                    overridden.name,
+                    overridden.descriptor.text
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The vtable and itables of `klass`, whose superclass is linked. Interfaces
+/// have neither.
+pub fn build_method_tables(klass: &InstanceKlass) -> (Vec<Rc<JMethod>>, Vec<ItableEntry>) {
+    if klass.access_flags.is_interface() {
+        return (vec![], vec![]);
+    }
+    let vtable = build_vtable(klass);
+    let itable = klass
+        .superinterfaces()
+        .into_iter()
+        .map(|interface| {
+            let methods = interface
+                .methods
+                .iter()
+                .map(|method| {
+                    if !method.is_virtual() {
+                        return None;
+                    }
+                    vtable
+                        .iter()
+                        .find(|entry| same_signature(entry, method))
+                        .cloned()
+                })
+                .collect();
+            ItableEntry { interface, methods }
+        })
+        .collect();
+    (vtable, itable)
+}
+
+fn build_vtable(klass: &InstanceKlass) -> Vec<Rc<JMethod>> {
+    let mut vtable = match klass.super_klass() {
+        Some(super_klass) => super_klass.vtable().clone(),
+        None => vec![],
+    };
+    for method in klass.methods.iter().filter(|method| method.is_virtual()) {
+        // a method may override several entries when a package-private
+        // method was overridden from another package
+        let mut index = None;
+        for (slot, entry) in vtable.iter_mut().enumerate() {
+            if overrides(klass, method, entry) {
+                *entry = Rc::clone(method);
+                index.get_or_insert(slot);
+            }
+        }
+        let index = index.unwrap_or_else(|| {
+            vtable.push(Rc::clone(method));
+            vtable.len() - 1
+        });
+        method.vtable_index.set(Some(index));
+    }
+
+    // interface methods no class method implements, the default ones and
+    // the abstract ones an abstract class leaves to its subclasses. Slots
+    // inherited that way are selected again, a new superinterface may
+    // override them.
+    for interface in klass.superinterfaces() {
+        for method in interface
+            .methods
+            .iter()
+            .filter(|method| method.is_virtual())
+        {
+            let slot = vtable
+                .iter()
+                .position(|entry| same_signature(entry, method));
+            if slot.is_some_and(|slot| is_class_method(&vtable[slot])) {
+                continue;
+            }
+            let selected = match select_default_method(klass, method) {
+                Some(selected) => selected,
+                None => continue,
+            };
+            match slot {
+                Some(slot) => vtable[slot] = selected,
+                None => vtable.push(selected),
+            }
+        }
+    }
+    vtable
+}
+
+/// The superinterface method a class declaring none selects for `method`
+/// (JVMS 5.4.6): the only default method among the maximally-specific ones,
+/// otherwise one of them when all are abstract. Conflicting default methods
+/// leave a method throwing `IncompatibleClassChangeError`.
+fn select_default_method(klass: &InstanceKlass, method: &JMethod) -> Option<Rc<JMethod>> {
+    let methods = klass.maximally_specific_methods(&method.name, &method.descriptor.text);
+    let defaults: Vec<&Rc<JMethod>> = methods
+        .iter()
+        .filter(|method| !method.access_flags.is_abstract())
+        .collect();
+    match defaults.as_slice() {
+        [] => methods.first().cloned(),
+        [selected] => Some(Rc::clone(selected)),
+        conflicting => Some(Rc::new(conflict_method(conflicting))),
+    }
+}
+
+// in place of the overpass methods of HotSpot, with the same message
+fn conflict_method(conflicting: &[&Rc<JMethod>]) -> JMethod {
+    let names: Vec<String> = conflicting
+        .iter()
+        .map(|method| {
+            let owner = method.klass();
+            let owner = owner.as_ref().map_or("", |owner| owner.klass_name.as_str());
+            format!("{}.{}", owner.replace('/', "."), method.name)
+        })
+        .collect();
+    let mut method = JMethod::new();
+    method.name = conflicting[0].name.clone();
+    method.descriptor = conflicting[0].descriptor.clone();
+    method.access_flags = conflicting[0].access_flags;
+    method.conflict = Some(format!("Conflicting default methods: {}", names.join(" ")));
+    method
+}
+
+/// Whether a class declares `method`, rather than an interface.
+fn is_class_method(method: &JMethod) -> bool {
+    match method.klass() {
+        Some(owner) => !owner.access_flags.is_interface(),
+        None => false,
+    }
+}
+
+/// Whether `method` of `klass` overrides the inherited `entry` (JVMS
+/// 5.4.5). A package-private method is only overridden from its package.
+fn overrides(klass: &InstanceKlass, method: &JMethod, entry: &JMethod) -> bool {
+    if !same_signature(method, entry) {
+        return false;
+    }
+    let flags = entry.access_flags;
+    if flags.is_public() || flags.is_protected() {
+        return true;
+    }
+    match entry.klass() {
+        Some(owner) => owner.package_name() == klass.package_name(),
+        None => false,
+    }
+}
+
+fn same_signature(method: &JMethod, other: &JMethod) -> bool {
+    method.name == other.name && method.descriptor.text == other.descriptor.text
+}
@@ -1,9 +1,13 @@
 pub mod member;
+pub mod class_init;
 pub mod class_loader;
+pub mod field;
 pub mod instanced_klass;
 pub mod j_constant;
 pub mod j_method;
 pub mod j_object;
 pub mod method_descriptor;
+pub mod method_table;
 pub mod runtime;
 pub mod slot;
+pub mod strings;
@@ -1,17 +1,30 @@
 use crate::classpath::class_path::ClassPath;
 use crate::rtda::heap::class_loader::ClassLoader;
+use std::collections::HashMap;
+use std::rc::Rc;
 
 #[derive(Debug, Clone)]
 pub struct Runtime {
     pub boot_loader: Box<ClassLoader>,
+    pub system_properties: HashMap<String, String>,
+    // -Xss, in bytes
+    pub stack_size: Option<u64>,
+    // -Xms, in bytes
+    pub initial_heap_size: Option<u64>,
+    // -Xmx, in bytes
+    pub max_heap_size: Option<u64>,
 }
 
 impl Runtime {
-    pub fn new(_cp: ClassPath) -> Runtime {
-        let mut loader = ClassLoader::new();
+    pub fn new(cp: ClassPath) -> Runtime {
+        let mut loader = ClassLoader::with_class_path(Rc::new(cp));
 
         let runtime = Runtime {
             boot_loader: Box::new(loader.clone()),
+            system_properties: HashMap::new(),
+            stack_size: None,
+            initial_heap_size: None,
+            max_heap_size: None,
         };
 
         loader.init();
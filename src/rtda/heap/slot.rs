@@ -1,19 +1,18 @@
+use std::rc::Rc;
+
 use crate::rtda::heap::j_object::JObject;
 
 #[derive(Debug, Clone)]
 pub struct Slot {
     val: i64,
-    obj: JObject,
+    obj: Option<Rc<JObject>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct EmptySlot {}
 impl EmptySlot {
     pub(crate) fn new() -> Slot {
-        Slot {
-            val: 0,
-            obj: JObject {},
-        }
+        Slot { val: 0, obj: None }
     }
 }
 
@@ -23,7 +22,56 @@ impl IntSlot {
     pub(crate) fn new(n: i32) -> Slot {
         Slot {
             val: n as i64,
-            obj: JObject {},
+            obj: None,
+        }
+    }
+}
+
+// a `long` or `double` is held whole by the first of its two slots
+impl Slot {
+    pub(crate) fn from_long(n: i64) -> Slot {
+        Slot { val: n, obj: None }
+    }
+
+    pub(crate) fn from_float(n: f32) -> Slot {
+        Slot {
+            val: n.to_bits() as i64,
+            obj: None,
+        }
+    }
+
+    pub(crate) fn from_double(n: f64) -> Slot {
+        Slot {
+            val: n.to_bits() as i64,
+            obj: None,
         }
     }
+
+    pub fn from_reference(obj: Rc<JObject>) -> Slot {
+        Slot {
+            val: 0,
+            obj: Some(obj),
+        }
+    }
+
+    pub fn int(&self) -> i32 {
+        self.val as i32
+    }
+
+    pub fn long(&self) -> i64 {
+        self.val
+    }
+
+    pub fn float(&self) -> f32 {
+        f32::from_bits(self.val as u32)
+    }
+
+    pub fn double(&self) -> f64 {
+        f64::from_bits(self.val as u64)
+    }
+
+    /// The object referred to, `None` for `null`.
+    pub fn reference(&self) -> Option<Rc<JObject>> {
+        self.obj.clone()
+    }
 }
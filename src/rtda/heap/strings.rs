@@ -0,0 +1,75 @@
+//! `java.lang.String` objects created by the VM, such as the arguments of
+//! `main`.
+
+use std::rc::Rc;
+
+use crate::rtda::heap::class_loader::{ClassLoadError, ClassLoader};
+use crate::rtda::heap::j_object::JObject;
+use crate::rtda::heap::slot::{IntSlot, Slot};
+
+/// A `String` holding `text` in its `char[] value`.
+pub fn new_string(loader: &ClassLoader, text: &str) -> Result<Rc<JObject>, ClassLoadError> {
+    let units: Vec<u16> = text.encode_utf16().collect();
+    let chars = JObject::new_array(&loader.load_class("[C")?, units.len());
+    for (index, unit) in units.into_iter().enumerate() {
+        chars.set_field(index, IntSlot::new(unit as i32));
+    }
+
+    let klass = loader.load_class("java/lang/String")?;
+    loader.link_class(&klass)?;
+    let string = JObject::new(&klass);
+    // from JDK 9 on the text is held in a `byte[]`, which is left empty
+    if let Some(index) = klass.find_declared_field("value", "[C") {
+        string.set_field(
+            klass.fields[index].slot,
+            Slot::from_reference(Rc::new(chars)),
+        );
+    }
+    Ok(Rc::new(string))
+}
+
+/// A `String[]` holding `texts`, as `main` takes its arguments.
+pub fn new_string_array(
+    loader: &ClassLoader,
+    texts: &[String],
+) -> Result<Rc<JObject>, ClassLoadError> {
+    let array = JObject::new_array(&loader.load_class("[Ljava/lang/String;")?, texts.len());
+    for (index, text) in texts.iter().enumerate() {
+        array.set_field(index, Slot::from_reference(new_string(loader, text)?));
+    }
+    Ok(Rc::new(array))
+}
+
+/// The text of `string`, a `String` created by `new_string`.
+pub fn string_text(string: &JObject) -> Option<String> {
+    let klass = string.klass();
+    let index = klass.find_declared_field("value", "[C")?;
+    let chars = string.field(klass.fields[index].slot).reference()?;
+    let units: Vec<u16> = (0..chars.array_length()?)
+        .map(|index| chars.field(index).int() as u16)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::classpath::class_path::ClassPath;
+    use crate::rtda::heap::class_loader::ClassLoader;
+    use crate::rtda::heap::strings::{new_string_array, string_text};
+
+    #[test]
+    fn should_create_string_arrays() {
+        let class_path = ClassPath::parse(Some(String::from("testdata/jre")), String::from("."));
+        let loader = ClassLoader::with_class_path(Rc::new(class_path.unwrap()));
+
+        let texts = vec![String::from("a"), String::from("b\u{e9}\u{1f600}")];
+        let array = new_string_array(&loader, &texts).unwrap();
+        assert_eq!("[Ljava/lang/String;", array.klass().klass_name);
+        assert_eq!(Some(2), array.array_length());
+        let second = array.field(1).reference().unwrap();
+        assert_eq!("java/lang/String", second.klass().klass_name);
+        assert_eq!(Some(String::from("b\u{e9}\u{1f600}")), string_text(&second));
+    }
+}
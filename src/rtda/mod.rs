@@ -2,5 +2,6 @@ pub mod shim_method;
 pub mod frame;
 pub mod heap;
 pub mod jvm_stack;
+pub mod native;
 pub mod path_conv;
 pub mod thread;
@@ -0,0 +1,34 @@
+//! Methods the VM runs itself rather than from bytecode: `native` methods
+//! and the few Java ones that need the VM, such as `System.exit`.
+
+use crate::rtda::frame::Frame;
+use crate::rtda::heap::slot::Slot;
+
+/// Runs in the frame of the caller, with the popped arguments.
+pub type NativeMethod = fn(&mut Frame, Vec<Slot>);
+
+/// The implementation of the method `name` of type `descriptor` that
+/// `class_name`, such as `java/lang/System`, declares.
+pub fn find_native_method(class_name: &str, name: &str, descriptor: &str) -> Option<NativeMethod> {
+    match (class_name, name, descriptor) {
+        ("java/lang/System", "exit", "(I)V") => Some(system_exit),
+        _ => None,
+    }
+}
+
+// stops the program at once, whatever the other threads do
+fn system_exit(frame: &mut Frame, args: Vec<Slot>) {
+    frame.exit(args[0].int());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rtda::native::find_native_method;
+
+    #[test]
+    fn should_find_native_methods_by_class_name_and_descriptor() {
+        assert!(find_native_method("java/lang/System", "exit", "(I)V").is_some());
+        assert!(find_native_method("java/lang/System", "exit", "(J)V").is_none());
+        assert!(find_native_method("Quit", "exit", "(I)V").is_none());
+    }
+}
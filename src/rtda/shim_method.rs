@@ -3,6 +3,7 @@ use crate::rtda::heap::j_method::{JMethod, MethodData};
 use crate::rtda::frame::{Frame, LocalVars, OperandStack};
 use crate::rtda::thread::Thread;
 use std::cell::RefCell;
+use std::rc::Rc;
 
 pub fn new_shim_member(name: String) -> ClassMember {
     ClassMember {
@@ -29,7 +30,7 @@ pub fn new_shim_frame(thread: RefCell<Thread>) -> Frame {
         local_vars: LocalVars::new(),
         operand_stack: OperandStack::new(),
         thread,
-        method: JMethod::new(),
+        method: Rc::new(JMethod::new()),
         max_locals: 0,
         max_stack: 0,
         next_pc: 0
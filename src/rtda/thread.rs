@@ -1,13 +1,14 @@
+use crate::classfile::class_format_error::ClassFormatError;
 use crate::instructions::decoder::{decoder, Decode};
 use crate::rtda::frame::Frame;
 use crate::rtda::heap::j_method::JMethod;
 use crate::rtda::heap::runtime::Runtime;
 use crate::rtda::jvm_stack::JVMStack;
+use crate::rtda::shim_method::new_shim_frame;
 use std::borrow::Borrow;
-use std::sync::{Mutex, Arc};
-use crate::rtda::shim_method::{new_shim_frame};
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
 pub struct ThreadPool {}
@@ -18,12 +19,36 @@ impl ThreadPool {
     }
 }
 
+/// How a program run by the VM finished.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VmExit {
+    /// `main` returned
+    Normal,
+    /// `System.exit` was called with this status
+    Exit(i32),
+    /// the thread died with an exception of this class
+    UncaughtException(String),
+}
+
+impl VmExit {
+    /// the process exit status, `java` uses 1 for uncaught exceptions
+    pub fn code(&self) -> i32 {
+        match self {
+            VmExit::Normal => 0,
+            VmExit::Exit(code) => *code,
+            VmExit::UncaughtException(_) => 1,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Thread {
     pub PC: i64,
     pub stack: Box<JVMStack>,
     pub runtime: Box<Runtime>,
     pub lock: Arc<Mutex<ThreadPool>>,
+    // set once the thread has to stop, by `System.exit` or an uncaught exception
+    pub exit: Option<VmExit>,
 }
 
 impl Thread {
@@ -33,6 +58,7 @@ impl Thread {
             runtime: Box::from(runtime),
             stack: Box::from(JVMStack::new(0)),
             lock: Arc::new(Mutex::new(ThreadPool::new())),
+            exit: None,
         }
     }
 
@@ -49,7 +75,7 @@ impl Thread {
         self.stack.push(frame)
     }
 
-    pub fn new_frame(&self, method: JMethod) -> Frame {
+    pub fn new_frame(&self, method: Rc<JMethod>) -> Frame {
         Frame::new(RefCell::new(self.clone()), method)
     }
 
@@ -57,25 +83,45 @@ impl Thread {
         self.stack.top()
     }
 
+    /// Ends the thread with `exception`, such as
+    /// `java.lang.NoSuchFieldError: count`, since nothing catches it yet.
+    /// The first exception thrown is kept.
+    pub fn throw(&mut self, exception: String) {
+        if self.exit.is_none() {
+            self.exit = Some(VmExit::UncaughtException(exception));
+        }
+    }
+
+    /// Ends the program with `status`, as `System.exit` does. Like
+    /// `throw`, the first reason to stop is kept.
+    pub fn exit(&mut self, status: i32) {
+        if self.exit.is_none() {
+            self.exit = Some(VmExit::Exit(status));
+        }
+    }
+
     pub fn invoke_method_with_shim(&mut self) {
         // let frame = new_shim_frame(RefCell::from(**self));
         // self.push_frame(&frame)
     }
 }
 
-pub fn execute_method(frame: &mut Frame, instr: Vec<u8>) -> Vec<Decode> {
+pub fn execute_method(frame: &mut Frame, instr: Vec<u8>) -> Result<Vec<Decode>, ClassFormatError> {
     let _length = instr.len();
-    let mut vec = decoder(instr.clone());
+    let mut vec = decoder(instr.clone())?;
     for i in 0..vec.len() {
+        if frame.thread.borrow().exit.is_some() {
+            break;
+        }
         vec[i].ins.execute(frame);
     }
 
-    vec
+    Ok(vec)
 }
 
-pub fn create_frame(method: &JMethod, thread: Rc<RefCell<Thread>>) -> Frame {
+pub fn create_frame(method: &Rc<JMethod>, thread: Rc<RefCell<Thread>>) -> Frame {
     let mut ref_mut = thread.borrow_mut();
-    let frame = ref_mut.new_frame(method.clone());
+    let frame = ref_mut.new_frame(Rc::clone(method));
     ref_mut.push_frame(frame.borrow());
     frame
 }
@@ -86,8 +132,9 @@ mod tests {
     use crate::create_main_thread;
     use crate::rtda::heap::runtime::Runtime;
     use crate::rtda::thread::{create_frame, execute_method};
-    use std::sync::Arc;
     use std::borrow::BorrowMut;
+    use std::rc::Rc;
+    use std::sync::Arc;
 
     #[test]
     fn test_vec() {
@@ -98,24 +145,26 @@ mod tests {
 
     #[test]
     fn test_frame() {
-        let runtime = Runtime::new(ClassPath::new());
-        let string = String::from("testdata/java8/HelloWorld.Class");
-        let mut class_loader = runtime.boot_loader;
-        class_loader.add_user_class(string);
-
-        let klass = class_loader.jl_object_class.get(0).unwrap();
+        let class_path = ClassPath::parse(
+            Some(String::from("testdata/jre")),
+            String::from("testdata/java8"),
+        );
+        let runtime = Runtime::new(class_path.unwrap());
+        let class_loader = runtime.boot_loader;
+        let klass = class_loader.load_class("HelloWorld").unwrap();
         let second = klass.methods.get(1).unwrap();
         let first = klass.methods.get(0).unwrap();
 
-        let jre_home = "/Library/Java/JavaVirtualMachines/jdk1.8.0_202.jdk/Contents/Home/jre";
-        let mut thread = create_main_thread(String::from(jre_home), String::from(""));
+        let jre_home = String::from("testdata/jre");
+        let mut thread = create_main_thread(Some(jre_home), String::from("")).unwrap();
 
         let mut frame1 = create_frame(first, thread.clone());
-        let first_execs = execute_method(&mut frame1, first.method_data.clone().code);
+        assert!(Rc::ptr_eq(&klass, &frame1.klass().unwrap()));
+        let first_execs = execute_method(&mut frame1, first.method_data.clone().code).unwrap();
         assert_eq!(5, first_execs.len());
 
         let mut frame2 = create_frame(second, thread.clone());
-        let execs = execute_method(&mut frame2, second.method_data.clone().code);
+        let execs = execute_method(&mut frame2, second.method_data.clone().code).unwrap();
         assert_eq!(9, execs.len());
     }
 }
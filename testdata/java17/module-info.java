@@ -0,0 +1,7 @@
+module sample {
+    requires java.base;
+    exports sample;
+    opens sample to java.base;
+    uses java.lang.Runnable;
+    provides java.lang.Runnable with sample.Task;
+}
@@ -0,0 +1,63 @@
+package sample;
+
+import java.io.IOException;
+import java.lang.annotation.ElementType;
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+import java.lang.annotation.Target;
+import java.util.ArrayList;
+import java.util.List;
+import java.util.function.Supplier;
+
+@AttributeSample.Marker(name = "sample", level = AttributeSample.Level.HIGH, types = {String.class, int.class})
+public class AttributeSample<T extends Comparable<T>> {
+    public static final long LIMIT = 42L;
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @interface Marker {
+        String name() default "none";
+
+        Level level();
+
+        Class<?>[] types() default {};
+    }
+
+    @Retention(RetentionPolicy.RUNTIME)
+    @Target(ElementType.TYPE_USE)
+    @interface NonEmpty {}
+
+    enum Level { LOW, HIGH }
+
+    record Point(int x, int y) {}
+
+    sealed interface Shape permits Circle, Square {}
+
+    static final class Circle implements Shape {}
+
+    static final class Square implements Shape {}
+
+    /**
+     * @deprecated use something else
+     */
+    @Deprecated
+    public int sum(@Deprecated List<@NonEmpty String> items, final int base) throws IOException {
+        int total = base;
+        for (String item : items) {
+            if (item.isEmpty()) {
+                continue;
+            }
+            total += item.length();
+        }
+        return total;
+    }
+
+    public Supplier<List<T>> factory() {
+        Object local = new Object() {
+            @Override
+            public String toString() {
+                return "anonymous";
+            }
+        };
+        return () -> new ArrayList<>(List.of());
+    }
+}
@@ -0,0 +1,19 @@
+import java.util.ArrayList;
+import java.util.List;
+import java.util.function.Supplier;
+
+public class ConstantSample {
+    static final int INT_VALUE = 65536;
+    static final float FLOAT_VALUE = 3.5f;
+    static final long LONG_VALUE = 1234567890123L;
+    static final double DOUBLE_VALUE = 2.718281828;
+
+    public static void main(String[] args) {
+        List<String> names = new ArrayList<>();
+        names.add("constant");
+        Supplier<Integer> size = names::size;
+        long total = LONG_VALUE + size.get();
+        double ratio = DOUBLE_VALUE * FLOAT_VALUE + INT_VALUE;
+        System.out.println(total + ratio);
+    }
+}
@@ -0,0 +1,5 @@
+public class StringSample {
+    public static void main(String[] args) {
+        System.out.println("nul:\0 emoji:😀 accent:é cjk:中");
+    }
+}
@@ -0,0 +1,157 @@
+#!/usr/bin/env python3
+"""Writes lib/modules, a tiny little-endian jimage for the reader tests.
+
+  /java.base/java/lang/Object.class   stored as is
+  /java.base/java/lang/Number.class   compact-cp, then zip
+  /packages/java.lang                 java.lang -> java.base
+
+usage: make_modules.py Object.class Number.class
+"""
+import struct
+import sys
+import zlib
+
+HASH_MULTIPLIER = 0x01000193
+
+
+def hash_code(name, seed=HASH_MULTIPLIER):
+    for b in name.encode():
+        seed = ((seed * HASH_MULTIPLIER) & 0xFFFFFFFF) ^ b
+    return seed & 0x7FFFFFFF
+
+
+class Strings:
+    def __init__(self):
+        self.data = bytearray(b"\0")
+        self.offsets = {"": 0}
+
+    def add(self, s):
+        if s not in self.offsets:
+            self.offsets[s] = len(self.data)
+            self.data += s.encode() + b"\0"
+        return self.offsets[s]
+
+
+def compress_int(value):
+    for size in (1, 2, 3):
+        if value < 1 << (5 + 8 * (size - 1)):
+            b = value.to_bytes(size, "big")
+            return bytes([b[0] | 0x80 | size << 5]) + b[1:]
+    return value.to_bytes(4, "big")
+
+
+SIZES = {3: 4, 4: 4, 5: 8, 6: 8, 7: 2, 8: 2, 9: 4, 10: 4, 11: 4, 12: 4,
+         15: 3, 16: 2, 17: 4, 18: 4, 19: 2, 20: 2}
+
+
+def compact_cp(klass, strings):
+    out = bytearray(klass[:10])
+    count = struct.unpack(">H", klass[8:10])[0]
+    at, i = 10, 1
+    while i < count:
+        tag = klass[at]
+        if tag == 1:
+            length = struct.unpack(">H", klass[at + 1:at + 3])[0]
+            text = klass[at + 3:at + 3 + length].decode()
+            at += 3 + length
+            if text.startswith("(") and "L" in text:
+                skeleton, names, rest = "", [], text
+                while rest:
+                    c, rest = rest[0], rest[1:]
+                    skeleton += c
+                    if c == "L":
+                        name, rest = rest.split(";", 1)
+                        rest = ";" + rest
+                        package, _, simple = name.rpartition("/")
+                        names += [strings.add(package), strings.add(simple)]
+                indexes = b"".join(compress_int(n) for n in names)
+                out += bytes([25]) + compress_int(strings.add(skeleton))
+                out += compress_int(len(indexes)) + indexes
+            else:
+                out += bytes([23]) + compress_int(strings.add(text))
+        else:
+            size = SIZES[tag]
+            out += klass[at:at + 1 + size]
+            at += 1 + size
+            i += tag in (5, 6)
+        i += 1
+    return bytes(out + klass[at:])
+
+
+def compressed(content, decompressor, strings, uncompressed_size):
+    header = struct.pack("<IQQIIB", 0xCAFEFAFA, len(content), uncompressed_size,
+                         strings.add(decompressor), 0, 0)
+    return header + content
+
+
+def attribute(kind, value):
+    b = value.to_bytes(max(1, (value.bit_length() + 7) // 8), "big")
+    return bytes([kind << 3 | len(b) - 1]) + b
+
+
+def main(object_class, number_class):
+    strings = Strings()
+    klass = open(number_class, "rb").read()
+    shared = compact_cp(klass, strings)
+    shared = compressed(shared, "compact-cp", strings, len(klass))
+    zipped = compressed(zlib.compress(shared), "zip", strings, len(shared))
+    resources = [
+        ("java.base", "java/lang", "Object", "class", open(object_class, "rb").read(), 0),
+        ("java.base", "java/lang", "Number", "class", zipped, len(klass)),
+        ("packages", "", "java.lang", "", struct.pack("<II", 0, strings.add("java.base")), 0),
+    ]
+
+    locations, content = bytearray(), bytearray()
+    location_offsets, names = [], []
+    for module, parent, base, extension, data, uncompressed in resources:
+        name = "/%s/%s%s%s" % (module, parent + "/" if parent else "", base,
+                               "." + extension if extension else "")
+        names.append(name)
+        location_offsets.append(len(locations))
+        locations += attribute(1, strings.add(module)) + attribute(2, strings.add(parent))
+        locations += attribute(3, strings.add(base)) + attribute(4, strings.add(extension))
+        locations += attribute(5, len(content))
+        if uncompressed:
+            locations += attribute(6, len(data)) + attribute(7, uncompressed)
+        else:
+            locations += attribute(7, len(data))
+        locations += b"\0"
+        content += data
+
+    # perfect hash: crowded buckets get a seed, the rest a slot of their own
+    length = len(names)
+    buckets = {}
+    for i, name in enumerate(names):
+        buckets.setdefault(hash_code(name) % length, []).append(i)
+    redirect, offsets, free = [0] * length, [0] * length, set(range(length))
+    for bucket, members in sorted(buckets.items(), key=lambda b: -len(b[1])):
+        if len(members) == 1:
+            continue
+        seed = 1
+        while True:
+            slots = [hash_code(names[i], seed) % length for i in members]
+            if len(set(slots)) == len(slots) and set(slots) <= free:
+                break
+            seed += 1
+        redirect[bucket] = seed
+        for i, slot in zip(members, slots):
+            offsets[slot] = location_offsets[i]
+            free.discard(slot)
+    for bucket, members in buckets.items():
+        if len(members) == 1:
+            slot = min(free)
+            free.discard(slot)
+            redirect[bucket] = -slot - 1
+            offsets[slot] = location_offsets[members[0]]
+
+    header = struct.pack("<7I", 0xCAFEDADA, 1 << 16, 0, len(names), length,
+                         len(locations), len(strings.data))
+    with open("lib/modules", "wb") as f:
+        f.write(header)
+        f.write(struct.pack("<%di" % length, *redirect))
+        f.write(struct.pack("<%dI" % length, *offsets))
+        f.write(locations + strings.data + content)
+
+
+if __name__ == "__main__":
+    main(*sys.argv[1:])